@@ -0,0 +1,306 @@
+//! Archive worktrees as git bundles and restore them later.
+//!
+//! An archive consists of a `<name>.bundle` holding the branch history and,
+//! if the worktree had uncommitted changes, a `<name>.patch` with those
+//! changes (untracked files included). Archives live under
+//! `~/.local/share/vibe/archive/<repo>/`.
+
+use anyhow::{Context, Result, bail};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::{git, lock, paths, style};
+
+/// A stored archive and the branch it contains
+struct Archive {
+    bundle: PathBuf,
+    branch: String,
+}
+
+impl Archive {
+    /// Path of the accompanying uncommitted-changes patch (may not exist)
+    fn patch(&self) -> PathBuf {
+        self.bundle.with_extension("patch")
+    }
+}
+
+/// Run the `archive` command: bundle a worktree's branch and changes, then remove it.
+pub fn run(worktree_name: &str) -> Result<()> {
+    let repo_info = git::require_bare_repo()?;
+    let _lock = lock::acquire(&repo_info.bare_path)?;
+
+    let Some(wt) = git::find_worktree_strict(worktree_name)? else {
+        bail!("Worktree '{}' not found", worktree_name);
+    };
+    if git::is_main_worktree(&wt) {
+        bail!("Refusing to archive the main branch '{}'", wt.branch);
+    }
+
+    let dir = archive_dir(&repo_info.workspace_root)?;
+    fs::create_dir_all(&dir).context("Failed to create archive directory")?;
+
+    let archive = Archive {
+        bundle: dir.join(bundle_file_name(&wt.branch)),
+        branch: wt.branch.clone(),
+    };
+    if archive.bundle.exists() {
+        bail!(
+            "An archive for '{}' already exists at {}",
+            wt.branch,
+            archive.bundle.display()
+        );
+    }
+
+    println!("Archiving {}...", wt.branch);
+
+    let output = Command::new("git")
+        .args([
+            "bundle",
+            "create",
+            archive.bundle.to_str().unwrap(),
+            &wt.branch,
+        ])
+        .output()
+        .context("Failed to run git bundle")?;
+
+    if !output.status.success() {
+        let _ = fs::remove_file(&archive.bundle);
+        bail!(
+            "Failed to create bundle: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let status = git::get_worktree_status(&wt.path).unwrap_or_default();
+    if status.has_uncommitted {
+        write_patch(&wt.path, &archive.patch())?;
+    }
+
+    git::remove_worktree_with_branch(&wt.path, &wt.branch, true)?;
+
+    print!("Archived {} to ", wt.branch);
+    style::println_colored(&dir.display().to_string(), style::theme().dim);
+    println!("Use 'vibe unarchive {}' to restore it", wt.branch);
+
+    Ok(())
+}
+
+/// Run the `unarchive` command: recreate a worktree from a stored archive.
+///
+/// Without a name, lists the available archives.
+pub fn run_unarchive(name: Option<&str>) -> Result<()> {
+    let repo_info = git::require_bare_repo()?;
+
+    let dir = archive_dir(&repo_info.workspace_root)?;
+    let archives = list_archives(&dir);
+
+    let Some(name) = name else {
+        if archives.is_empty() {
+            println!("No archived worktrees found");
+        } else {
+            println!("Archived worktrees:");
+            for archive in &archives {
+                println!("  {}", archive.branch);
+            }
+        }
+        return Ok(());
+    };
+
+    let exact: Vec<_> = archives.iter().filter(|a| a.branch == name).collect();
+    let matches = if exact.is_empty() {
+        archives
+            .iter()
+            .filter(|a| a.branch.contains(name))
+            .collect()
+    } else {
+        exact
+    };
+
+    let archive = match matches.as_slice() {
+        [archive] => *archive,
+        [] => bail!("No archive matching '{}' found", name),
+        _ => {
+            let candidates: Vec<_> = matches.iter().map(|a| format!("  {}", a.branch)).collect();
+            bail!(
+                "'{}' matches multiple archives:\n{}\nUse a more specific name",
+                name,
+                candidates.join("\n")
+            );
+        }
+    };
+
+    let _lock = lock::acquire(&repo_info.bare_path)?;
+
+    if git::branch_exists(&archive.branch) {
+        bail!(
+            "Branch '{}' already exists; remove it before unarchiving",
+            archive.branch
+        );
+    }
+
+    println!("Restoring {}...", archive.branch);
+
+    let refspec = format!("refs/heads/{0}:refs/heads/{0}", archive.branch);
+    let output = Command::new("git")
+        .current_dir(&repo_info.workspace_root)
+        .args(["fetch", archive.bundle.to_str().unwrap(), &refspec])
+        .output()
+        .context("Failed to run git fetch")?;
+
+    if !output.status.success() {
+        bail!(
+            "Failed to restore branch from bundle: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let worktree_path = git::checkout_worktree(&repo_info.workspace_root, &archive.branch)?;
+
+    let patch = archive.patch();
+    if patch.exists() {
+        let status = Command::new("git")
+            .current_dir(&worktree_path)
+            .args(["apply", patch.to_str().unwrap()])
+            .status()
+            .context("Failed to run git apply")?;
+
+        if !status.success() {
+            style::print_colored("Warning:", style::theme().uncommitted);
+            println!(
+                " uncommitted changes did not apply cleanly; archive kept at {}",
+                dir.display()
+            );
+            println!("Restored {} at {}", archive.branch, worktree_path.display());
+            return Ok(());
+        }
+    }
+
+    let _ = fs::remove_file(&archive.bundle);
+    let _ = fs::remove_file(&patch);
+
+    println!("Restored {} at {}", archive.branch, worktree_path.display());
+
+    Ok(())
+}
+
+/// File name of the bundle archiving `branch`.
+///
+/// `/` and `%` are percent-encoded, so different branches never share a
+/// file (`claude/a-b` and `claude/a/b` would, with `/` turned into `-`).
+fn bundle_file_name(branch: &str) -> String {
+    format!("{}.bundle", branch.replace('%', "%25").replace('/', "%2F"))
+}
+
+/// Get the archive directory for a repository.
+fn archive_dir(workspace_root: &Path) -> Result<PathBuf> {
+    let repo_name = workspace_root
+        .file_name()
+        .context("Invalid workspace root")?;
+    Ok(paths::data_dir()?.join("archive").join(repo_name))
+}
+
+/// List archives in a directory, reading each bundle's branch name.
+fn list_archives(dir: &Path) -> Vec<Archive> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut archives: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "bundle"))
+        .filter_map(|bundle| {
+            let branch = bundle_branch(&bundle)?;
+            Some(Archive { bundle, branch })
+        })
+        .collect();
+
+    archives.sort_by(|a, b| a.branch.cmp(&b.branch));
+    archives
+}
+
+/// Read the branch stored in a bundle.
+fn bundle_branch(bundle: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .args(["bundle", "list-heads", bundle.to_str()?])
+        .output()
+        .ok()?;
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.split_once(" refs/heads/").map(|(_, b)| b.to_string()))
+}
+
+/// Save uncommitted changes (including untracked files) as a binary patch.
+fn write_patch(worktree_path: &Path, patch: &Path) -> Result<()> {
+    // Mark untracked files as intent-to-add so they show up in the diff
+    let output = Command::new("git")
+        .current_dir(worktree_path)
+        .args(["add", "--all", "--intent-to-add"])
+        .output()
+        .context("Failed to stage untracked files")?;
+
+    if !output.status.success() {
+        bail!(
+            "Failed to stage untracked files: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let output = Command::new("git")
+        .current_dir(worktree_path)
+        .args(["diff", "HEAD", "--binary"])
+        .output()
+        .context("Failed to diff worktree")?;
+
+    if !output.status.success() {
+        bail!("Failed to diff worktree");
+    }
+
+    fs::write(patch, &output.stdout).context("Failed to write patch")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundle_names_round_trip() {
+        let repo = std::env::temp_dir().join(format!("vibe-archive-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&repo);
+        fs::create_dir_all(&repo).unwrap();
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .current_dir(&repo)
+                .args(["-c", "user.name=vibe", "-c", "user.email=vibe@example.com"])
+                .args(args)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {:?} failed", args);
+        };
+        git(&["init", "--quiet"]);
+        git(&["commit", "--quiet", "--allow-empty", "-m", "init"]);
+
+        let dir = repo.join("archive");
+        fs::create_dir_all(&dir).unwrap();
+        let branches = ["claude/a%2Fb", "claude/a-b", "claude/a/b"];
+        for branch in branches {
+            git(&["branch", branch]);
+            let bundle = dir.join(bundle_file_name(branch));
+            assert!(!bundle.exists(), "{} collides", branch);
+            git(&[
+                "bundle",
+                "create",
+                "--quiet",
+                bundle.to_str().unwrap(),
+                branch,
+            ]);
+        }
+
+        let listed: Vec<_> = list_archives(&dir).into_iter().map(|a| a.branch).collect();
+        assert_eq!(listed, branches);
+
+        fs::remove_dir_all(&repo).unwrap();
+    }
+}
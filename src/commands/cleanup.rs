@@ -1,79 +1,463 @@
 //! Clean up worktrees that are synced with remote or unused.
 
 use anyhow::Result;
+use std::collections::HashSet;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
 use tokio::sync::mpsc;
 
-use crate::{git, style, tui};
+use crate::{docker, git, lock, meta, style, summary, trash, tui, util};
+
+/// Options for the `cleanup` command
+pub struct CleanupOptions {
+    /// Select worktrees to delete with the TUI
+    pub interactive: bool,
+    /// Only consider worktrees whose branch matches this glob
+    pub pattern: Option<String>,
+    /// Remove worktrees inactive for longer than this
+    pub older_than: Option<Duration>,
+    /// Remove inactive worktrees even if they have local changes
+    pub force: bool,
+    /// Remove without asking for confirmation
+    pub yes: bool,
+    /// Only remove images of sessions that no longer exist
+    pub images_only: bool,
+    /// Only list what would be removed
+    pub dry_run: bool,
+    /// Ask about each worktree instead of once for all of them
+    pub confirm_each: bool,
+    /// Only remove worktrees whose directory is missing
+    pub orphans_only: bool,
+}
 
 /// Run the `cleanup` command: remove synced or unused worktrees.
 ///
 /// In default mode, automatically removes worktrees that are:
 /// - Synced with remote (branch pushed and up-to-date)
 /// - Unused (no commits beyond base, no changes)
+/// - Inactive for longer than `older_than`, if set (only without local
+///   changes, unless `force`)
+///
+/// Removals are listed and confirmed once before any deletion, unless `yes`.
+/// With `dry_run`, they are only listed.
+///
+/// In interactive mode (-i), shows a TUI for selecting which worktrees to delete,
+/// limited to inactive ones when `older_than` is set.
+///
+/// Worktrees in the middle of a rebase, merge, cherry-pick or revert are
+/// never removed automatically, and in interactive mode only with `force`.
+///
+/// With `pattern`, only worktrees whose branch matches the glob are considered.
 ///
-/// In interactive mode (-i), shows a TUI for selecting which worktrees to delete.
-pub async fn run(interactive: bool) -> Result<()> {
-    git::require_bare_repo()?;
+/// With `images_only`, worktrees are left alone and only leftover session
+/// images are removed (see `run_images_only`).
+///
+/// With `orphans_only`, only worktrees whose directory is missing are
+/// removed, without any of the network checks (see `run_orphans_only`).
+pub async fn run(options: CleanupOptions) -> Result<()> {
+    let repo_info = git::require_bare_repo()?;
+
+    if options.images_only {
+        return run_images_only(&repo_info.workspace_root, options.yes, options.dry_run);
+    }
 
-    let worktrees = git::list_claude_worktrees()?;
+    let mut worktrees = git::list_claude_worktrees()?;
+
+    // Never offer the main checkout for deletion
+    worktrees.retain(|wt| !git::is_main_worktree(wt));
 
     if worktrees.is_empty() {
         println!("No claude worktrees found");
         return Ok(());
     }
 
-    if interactive {
-        run_interactive(worktrees).await
+    let mut filters = Vec::new();
+    if let Some(pattern) = &options.pattern {
+        worktrees = git::filter_by_glob(&worktrees, pattern)?;
+        filters.push(format!("--match {}", pattern));
+    }
+
+    if options.interactive
+        && let Some(threshold) = options.older_than
+    {
+        worktrees.retain(|wt| inactive_for(&wt.path).is_none_or(|age| age > threshold));
+        filters.push(format!("--older-than {}", util::format_duration(threshold)));
+    }
+
+    if worktrees.is_empty() {
+        println!(
+            "No worktrees matched the given filters ({})",
+            filters.join(", ")
+        );
+        return Ok(());
+    }
+
+    if options.interactive {
+        run_interactive(worktrees, &repo_info, options.force).await
+    } else if options.orphans_only {
+        let _lock = lock::acquire(&repo_info.bare_path)?;
+        run_orphans_only(worktrees, &options, &repo_info.workspace_root)
     } else {
-        run_automatic(worktrees)
+        let _lock = lock::acquire(&repo_info.bare_path)?;
+        run_automatic(worktrees, &options, &repo_info.workspace_root)
+    }
+}
+
+/// How long a worktree has been inactive, if its last activity is known.
+///
+/// A new worktree's last commit is often an old one from its base branch,
+/// so the worktree counts as active at least since it was created.
+fn inactive_for(worktree_path: &Path) -> Option<Duration> {
+    let last = git::last_activity(worktree_path).max(meta::created(worktree_path))?;
+    Some(SystemTime::now().duration_since(last).unwrap_or_default())
+}
+
+/// Why automatic cleanup removes a worktree
+#[derive(Clone, Copy)]
+enum Reason {
+    Orphaned,
+    Inactive,
+    Synced,
+    Unused,
+}
+
+impl Reason {
+    fn describe(self) -> &'static str {
+        match self {
+            Reason::Orphaned => "orphaned (directory missing)",
+            Reason::Inactive => "inactive",
+            Reason::Synced => "synced",
+            Reason::Unused => "unused",
+        }
     }
 }
 
 /// Run automatic cleanup (default mode)
-fn run_automatic(worktrees: Vec<git::Worktree>) -> Result<()> {
+///
+/// Decides for every worktree first and asks once before removing anything,
+/// or about each worktree with `confirm_each`.
+fn run_automatic(
+    worktrees: Vec<git::Worktree>,
+    options: &CleanupOptions,
+    workspace_root: &Path,
+) -> Result<()> {
+    style::print_offline_notice();
     println!("Checking worktrees for cleanup...\n");
 
-    let mut cleaned = 0;
+    let mut removals = Vec::new();
 
-    for wt in worktrees {
-        let status = git::get_worktree_status(&wt.path).unwrap_or_default();
+    for wt in &worktrees {
+        if let Some(reason) = check_worktree(wt, options)? {
+            removals.push((wt, reason));
+        }
+    }
+
+    println!();
+    if removals.is_empty() {
+        println!("Nothing to clean up");
+        return Ok(());
+    }
+
+    let confirm_each = options.confirm_each && !options.yes;
+    if options.dry_run {
+        println!("{} worktree(s) would be removed (dry run):", removals.len());
+    } else if confirm_each {
+        println!("{} worktree(s) can be removed:", removals.len());
+    } else {
+        println!("{} worktree(s) will be removed:", removals.len());
+    }
+    for (wt, reason) in &removals {
+        println!("  - {} ({})", wt.branch, reason.describe());
+    }
+    println!();
+
+    if options.dry_run {
+        println!("Nothing was removed (dry run)");
+        return Ok(());
+    }
+
+    if confirm_each {
+        let mut confirmed = Vec::new();
+        for (wt, reason) in removals {
+            let mut details = reason.describe().to_string();
+            if let Ok(status) = git::get_worktree_status(&wt.path) {
+                let changes = status.change_summary();
+                if !changes.is_empty() {
+                    details = format!("{}, {}", details, changes);
+                }
+            }
+            if tui::confirm(&format!("Remove {} ({})?", wt.branch, details))? {
+                confirmed.push((wt, reason));
+            }
+        }
+        if confirmed.is_empty() {
+            println!("Nothing was removed");
+            return Ok(());
+        }
+        println!();
+        removals = confirmed;
+    } else if !options.yes {
+        if !tui::confirm("Remove these worktrees?")? {
+            println!("Cancelled.");
+            return Ok(());
+        }
+        println!();
+    }
+
+    remove_worktrees(
+        removals.into_iter().map(|(wt, _)| wt),
+        workspace_root,
+        "Cleaned up",
+    )
+}
+
+/// Remove only orphaned worktrees, whose directory no longer exists.
+///
+/// Orphans are always safe to remove, so this skips the sync and unused
+/// checks of `run_automatic` and never touches the network.
+fn run_orphans_only(
+    worktrees: Vec<git::Worktree>,
+    options: &CleanupOptions,
+    workspace_root: &Path,
+) -> Result<()> {
+    let orphans: Vec<_> = worktrees
+        .iter()
+        .filter(|wt| git::get_worktree_status(&wt.path).is_ok_and(|s| s.is_orphaned))
+        .collect();
+
+    if orphans.is_empty() {
+        println!("No orphaned worktrees found");
+        return Ok(());
+    }
+
+    if options.dry_run {
+        println!(
+            "{} orphaned worktree(s) would be removed (dry run):",
+            orphans.len()
+        );
+    } else {
+        println!("{} orphaned worktree(s) will be removed:", orphans.len());
+    }
+    for wt in &orphans {
+        println!("  - {} ({})", wt.branch, wt.path.display());
+    }
+    println!();
+
+    if options.dry_run {
+        println!("Nothing was removed (dry run)");
+        return Ok(());
+    }
+
+    if !options.yes {
+        if !tui::confirm("Remove these worktrees?")? {
+            println!("Cancelled.");
+            return Ok(());
+        }
+        println!();
+    }
+
+    remove_worktrees(orphans, workspace_root, "Removed")
+}
+
+/// Session images that don't belong to any of `worktrees`.
+///
+/// The setup image is shared and always kept.
+fn stale_images(
+    images: Vec<docker::LocalImage>,
+    worktrees: &[git::Worktree],
+) -> Vec<docker::LocalImage> {
+    let live: HashSet<_> = worktrees
+        .iter()
+        .map(|wt| docker::session_image(&wt.branch))
+        .collect();
+    images
+        .into_iter()
+        .filter(|image| image.name != docker::SETUP_IMAGE && !live.contains(&image.name))
+        .collect()
+}
+
+/// Remove `claude-vibe-*` images whose session worktree no longer exists.
+///
+/// Only images built for this workspace are considered, so sessions of
+/// other workspaces keep theirs. With `dry_run`, they are only listed.
+fn run_images_only(workspace_root: &Path, yes: bool, dry_run: bool) -> Result<()> {
+    let worktrees = git::list_worktrees()?;
+    let stale = stale_images(docker::list_vibe_images(workspace_root)?, &worktrees);
 
-        print!("  {} ", wt.branch);
+    if stale.is_empty() {
+        println!("No leftover images found");
+        return Ok(());
+    }
+
+    if dry_run {
+        println!(
+            "{} image(s) without a worktree would be removed (dry run):",
+            stale.len()
+        );
+    } else {
+        println!(
+            "{} image(s) without a worktree will be removed:",
+            stale.len()
+        );
+    }
+    for image in &stale {
+        print!("  - {} ", image.name);
+        style::println_colored(
+            &format!("({})", util::format_bytes(image.size)),
+            style::theme().dim,
+        );
+    }
+    println!();
+
+    if dry_run {
+        println!("Nothing was removed (dry run)");
+        return Ok(());
+    }
+
+    if !yes {
+        if !tui::confirm("Remove these images?")? {
+            println!("Cancelled.");
+            return Ok(());
+        }
+        println!();
+    }
+
+    let mut removed = 0;
+    let mut reclaimed = 0;
+    for image in &stale {
+        print!("Removing {}... ", image.name);
+        io::stdout().flush()?;
+        match docker::remove_image(&image.name) {
+            Ok(()) => {
+                style::println_colored("done", style::theme().clean);
+                removed += 1;
+                reclaimed += image.size;
+            }
+            Err(e) => {
+                style::print_colored("failed: ", style::theme().danger);
+                println!("{}", e);
+            }
+        }
+    }
+
+    // Layers shared with remaining images stay on disk
+    println!();
+    println!(
+        "Removed {} image(s), reclaiming up to {}",
+        removed,
+        util::format_bytes(reclaimed)
+    );
+    Ok(())
+}
+
+/// Remove worktrees and their branches, keeping them restorable with `vibe undo`.
+///
+/// Ends with a summary line starting with `verb`.
+fn remove_worktrees<'a>(
+    worktrees: impl IntoIterator<Item = &'a git::Worktree>,
+    workspace_root: &Path,
+    verb: &str,
+) -> Result<()> {
+    let mut removed = Vec::new();
 
-        if status.is_orphaned {
-            style::print_colored("✗", style::indicators::DANGER);
-            println!(" orphaned (directory missing), removing...");
+    for wt in worktrees {
+        print!("Removing {}... ", wt.branch);
+        io::stdout().flush()?;
+        let result = trash::preserve(workspace_root, wt).and_then(|saved| {
             git::remove_worktree_with_branch(&wt.path, &wt.branch, true)?;
-            cleaned += 1;
-        } else if status.is_safe_to_delete() {
-            if git::is_worktree_synced(&wt.path)? {
-                style::print_colored("✓", style::indicators::CLEAN);
-                println!(" synced, removing...");
-                git::remove_worktree_with_branch(&wt.path, &wt.branch, true)?;
-                cleaned += 1;
-            } else if git::is_worktree_unused(&wt.path)? {
-                style::print_colored("✓", style::indicators::CLEAN);
-                println!(" unused, removing...");
-                git::remove_worktree_with_branch(&wt.path, &wt.branch, true)?;
-                cleaned += 1;
-            } else {
-                style::print_colored("-", style::indicators::DIM);
-                println!(" keeping (has commits)");
+            Ok(saved)
+        });
+        match result {
+            Ok(saved) => {
+                style::println_colored("done", style::theme().clean);
+                removed.push(saved);
+            }
+            Err(e) => {
+                style::print_colored("failed: ", style::theme().danger);
+                println!("{}", e);
             }
-        } else {
-            style::print_colored("!", style::indicators::UNCOMMITTED);
-            println!(" keeping (has local changes)");
         }
     }
 
     println!();
-    println!("Cleaned up {} worktree(s)", cleaned);
+    println!("{} {} worktree(s)", verb, removed.len());
+
+    if !removed.is_empty() {
+        match trash::record(workspace_root, removed) {
+            Ok(()) => println!("Run 'vibe undo' to restore them"),
+            Err(e) => {
+                style::print_colored("Warning:", style::theme().uncommitted);
+                println!(" failed to record cleanup for undo: {}", e);
+            }
+        }
+    }
 
     Ok(())
 }
 
+/// Print the cleanup decision for a worktree, returning why it should be
+/// removed, or None to keep it.
+fn check_worktree(wt: &git::Worktree, options: &CleanupOptions) -> Result<Option<Reason>> {
+    let status = git::get_worktree_status(&wt.path).unwrap_or_default();
+
+    print!("  {} ", wt.branch);
+
+    // Age is only relevant (and shown) for time-based cleanup
+    let age = options.older_than.and(inactive_for(&wt.path));
+    if let Some(age) = age {
+        style::print_colored(
+            &format!("({}) ", util::format_duration(age)),
+            style::theme().dim,
+        );
+    }
+    let inactive = matches!((age, options.older_than), (Some(age), Some(t)) if age > t);
+
+    let reason = if git::contains_current_dir(&wt.path) {
+        style::print_colored("!", style::theme().uncommitted);
+        println!(" keeping (current directory is inside it)");
+        return Ok(None);
+    } else if status.is_orphaned {
+        style::print_colored(style::theme().fail, style::theme().danger);
+        Reason::Orphaned
+    } else if let Some(op) = &status.in_progress_op {
+        style::print_colored("!", style::theme().uncommitted);
+        println!(" keeping ({} in progress)", op);
+        return Ok(None);
+    } else if inactive && (status.is_safe_to_delete() || options.force) {
+        style::print_colored(style::theme().ok, style::theme().clean);
+        Reason::Inactive
+    } else if !status.is_safe_to_delete() {
+        style::print_colored("!", style::theme().uncommitted);
+        println!(" keeping (has local changes)");
+        return Ok(None);
+    } else if git::is_worktree_synced(&wt.path)? {
+        style::print_colored(style::theme().ok, style::theme().clean);
+        Reason::Synced
+    } else if git::is_worktree_unused(&wt.path)? {
+        style::print_colored(style::theme().ok, style::theme().clean);
+        Reason::Unused
+    } else {
+        style::print_colored("-", style::theme().dim);
+        println!(" keeping (has commits)");
+        return Ok(None);
+    };
+
+    let verb = if options.dry_run {
+        "would remove"
+    } else {
+        "will remove"
+    };
+    println!(" {}, {}", reason.describe(), verb);
+    Ok(Some(reason))
+}
+
 /// Run interactive cleanup with TUI selection
-async fn run_interactive(worktrees: Vec<git::Worktree>) -> Result<()> {
+async fn run_interactive(
+    worktrees: Vec<git::Worktree>,
+    repo_info: &git::BareRepoInfo,
+    force: bool,
+) -> Result<()> {
     // Create items with just branch names (status will be loaded async)
     let items: Vec<_> = worktrees
         .iter()
@@ -81,6 +465,8 @@ async fn run_interactive(worktrees: Vec<git::Worktree>) -> Result<()> {
             branch: wt.branch.clone(),
             status: None,
             summary_state: tui::SummaryState::None,
+            created: meta::created(&wt.path),
+            log: None,
         })
         .collect();
 
@@ -95,17 +481,26 @@ async fn run_interactive(worktrees: Vec<git::Worktree>) -> Result<()> {
         tokio::task::spawn_blocking(move || {
             // First fetch status
             let status = git::get_worktree_status(&path).unwrap_or_default();
-            let needs_summary = status.has_uncommitted && !status.is_orphaned;
+            let needs_summary = summary::needed(&status);
             let _ = tx.send(tui::WorktreeUpdate::Status {
                 index,
                 status: status.clone(),
             });
 
-            // Then fetch AI summary if needed
+            // Then fetch AI summary if needed, unless it is cached
             if needs_summary {
-                let _ = tx.send(tui::WorktreeUpdate::SummaryStarted { index });
-                if let Some(summary) = git::get_ai_summary(&path) {
+                if let Some(summary) = summary::cached_summary(&path) {
                     let _ = tx.send(tui::WorktreeUpdate::Summary { index, summary });
+                } else {
+                    let _ = tx.send(tui::WorktreeUpdate::SummaryStarted { index });
+                    let update = match summary::generate(&path) {
+                        Ok(summary) => tui::WorktreeUpdate::Summary { index, summary },
+                        Err(e) => tui::WorktreeUpdate::SummaryFailed {
+                            index,
+                            reason: e.to_string(),
+                        },
+                    };
+                    let _ = tx.send(update);
                 }
             }
         });
@@ -115,7 +510,7 @@ async fn run_interactive(worktrees: Vec<git::Worktree>) -> Result<()> {
     drop(update_tx);
 
     // Run multi-selection TUI with async updates
-    let selection = tui::run_multi_selection_async(items, update_rx).await?;
+    let selection = tui::run_multi_selection_async(items, update_rx, false).await?;
 
     let Some(indices) = selection else {
         // User cancelled
@@ -127,67 +522,144 @@ async fn run_interactive(worktrees: Vec<git::Worktree>) -> Result<()> {
     }
 
     // Collect selected worktrees and check for ones with changes
-    let selected_worktrees: Vec<_> = indices.iter().map(|&i| &worktrees[i]).collect();
+    let (inside, selected_worktrees): (Vec<_>, Vec<_>) = indices
+        .iter()
+        .map(|&i| &worktrees[i])
+        .partition(|wt| git::contains_current_dir(&wt.path));
+
+    for wt in &inside {
+        style::print_colored("Skipping", style::theme().uncommitted);
+        println!(
+            " {}: the current directory is inside it (cd out of it first)",
+            wt.branch
+        );
+    }
+
+    let mut selected: Vec<_> = selected_worktrees
+        .into_iter()
+        .map(|wt| (wt, git::get_worktree_status(&wt.path).unwrap_or_default()))
+        .collect();
+
+    if !force {
+        selected.retain(|(wt, status)| {
+            let Some(op) = &status.in_progress_op else {
+                return true;
+            };
+            style::print_colored("Skipping", style::theme().uncommitted);
+            println!(
+                " {}: {} in progress (use --force to delete it anyway)",
+                wt.branch, op
+            );
+            false
+        });
+    }
+
+    if selected.is_empty() {
+        return Ok(());
+    }
 
-    let worktrees_with_changes: Vec<_> = selected_worktrees
+    let worktrees_with_changes: Vec<_> = selected
         .iter()
-        .filter(|wt| {
-            git::get_worktree_status(&wt.path)
-                .map(|s| s.has_local_changes())
-                .unwrap_or(false)
-        })
+        .filter(|(_, status)| status.has_local_changes() || status.in_progress_op.is_some())
         .collect();
 
-    // If any selected worktrees have changes, ask for confirmation
+    // If any selected worktrees have changes, have them confirmed one by one
     if !worktrees_with_changes.is_empty() {
         println!();
-        style::print_colored("Warning:", style::indicators::UNCOMMITTED);
+        style::print_colored("Warning:", style::theme().uncommitted);
         println!(
-            " {} worktree(s) have uncommitted or unpushed changes:",
+            " {} worktree(s) have uncommitted or unpushed changes or unfinished operations:",
             worktrees_with_changes.len()
         );
-        for wt in &worktrees_with_changes {
-            let status = git::get_worktree_status(&wt.path).unwrap_or_default();
-            let mut details = Vec::new();
-            let total_added = status.lines_added + status.untracked_files;
-            if total_added > 0 {
-                details.push(format!("+{}", total_added));
-            }
-            if status.lines_deleted > 0 {
-                details.push(format!("-{}", status.lines_deleted));
-            }
-            if status.commits_ahead > 0 {
-                details.push(format!("↑{}", status.commits_ahead));
+        for (wt, status) in &worktrees_with_changes {
+            let mut details = status.change_summary();
+            if let Some(op) = &status.in_progress_op {
+                if !details.is_empty() {
+                    details.push_str(", ");
+                }
+                details.push_str(&format!("{} in progress", op));
             }
-            println!("  - {} ({})", wt.branch, details.join(" "));
+            println!("  - {} ({})", wt.branch, details);
         }
         println!();
+        println!("Uncheck the ones to keep, the others are deleted anyway:");
 
-        if !tui::confirm("Delete these worktrees anyway?")? {
+        let items = worktrees_with_changes
+            .iter()
+            .map(|(wt, status)| tui::WorktreeItem {
+                branch: wt.branch.clone(),
+                status: Some(status.clone()),
+                summary_state: tui::SummaryState::None,
+                created: meta::created(&wt.path),
+                log: None,
+            })
+            .collect();
+        let (_, no_updates) = mpsc::unbounded_channel();
+        let Some(confirmed) = tui::run_multi_selection_async(items, no_updates, true).await? else {
             println!("Cancelled.");
             return Ok(());
+        };
+
+        let keep: Vec<_> = worktrees_with_changes
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !confirmed.contains(i))
+            .map(|(_, (wt, _))| wt.branch.clone())
+            .collect();
+        for branch in &keep {
+            println!("Keeping {}", branch);
+        }
+        selected.retain(|(wt, _)| !keep.contains(&wt.branch));
+
+        if selected.is_empty() {
+            return Ok(());
         }
     }
 
     // Delete selected worktrees
+    let _lock = lock::acquire(&repo_info.bare_path)?;
     println!();
-    let mut deleted = 0;
-    for wt in selected_worktrees {
-        print!("Removing {}... ", wt.branch);
-        match git::remove_worktree_with_branch(&wt.path, &wt.branch, true) {
-            Ok(()) => {
-                style::println_colored("done", style::indicators::CLEAN);
-                deleted += 1;
-            }
-            Err(e) => {
-                style::print_colored("failed: ", style::indicators::DANGER);
-                println!("{}", e);
-            }
+    remove_worktrees(
+        selected.into_iter().map(|(wt, _)| wt),
+        &repo_info.workspace_root,
+        "Deleted",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn image(name: &str) -> docker::LocalImage {
+        docker::LocalImage {
+            name: name.to_string(),
+            size: 1,
         }
     }
 
-    println!();
-    println!("Deleted {} worktree(s)", deleted);
+    #[test]
+    fn test_stale_images() {
+        let worktrees = vec![
+            git::Worktree {
+                path: PathBuf::from("/ws/claude/live"),
+                branch: "claude/live".to_string(),
+            },
+            git::Worktree {
+                path: PathBuf::from("/ws/feature"),
+                branch: "feature".to_string(),
+            },
+        ];
+        let images = vec![
+            image("claude-vibe-live"),
+            image("claude-vibe-gone"),
+            image("claude-vibe-feature"),
+            image("claude-vibe-setup"),
+        ];
 
-    Ok(())
+        assert_eq!(
+            stale_images(images, &worktrees),
+            vec![image("claude-vibe-gone")]
+        );
+    }
 }
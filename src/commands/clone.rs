@@ -2,17 +2,16 @@
 
 use anyhow::{Context, Result, bail};
 use std::fs;
+use std::io::{self, IsTerminal, Write};
 use std::path::Path;
 use std::process::Command;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
 
-use crate::docker;
-
-const SETUP_PROMPT: &str = "\
-Analyze this project and create a Dockerfile.vibes file that includes all necessary \
-dependencies and tools for development. The Dockerfile should be based on sirsedev/claude-vibe \
-as the base image (which already includes Claude Code). Add any project-specific dependencies \
-needed to build and run this project. Please examine the project structure, dependencies, \
-and build system to determine the requirements.";
+use crate::commands::{new, setup};
+use crate::{docker, du, style, util};
 
 /// Extract repository name from URL.
 fn extract_repo_name(url: &str) -> Option<String> {
@@ -34,8 +33,42 @@ fn extract_repo_name(url: &str) -> Option<String> {
     }
 }
 
+/// Show the growing size of `bare_dir` until `done` is set.
+///
+/// Git's own progress is hard to follow for huge repositories, so this
+/// replaces it with a single line that is cleared once the clone finishes.
+fn show_clone_size(bare_dir: &Path, done: Arc<AtomicBool>) -> thread::JoinHandle<()> {
+    let bare_dir = bare_dir.to_path_buf();
+    thread::spawn(move || {
+        while !done.load(Ordering::Relaxed) {
+            style::clear_line();
+            print!(
+                "  Downloaded {}",
+                util::format_bytes(du::usage(&bare_dir, Default::default()).bytes)
+            );
+            let _ = io::stdout().flush();
+            thread::sleep(Duration::from_millis(500));
+        }
+        style::clear_line();
+    })
+}
+
 /// Run the `clone` command: clone as bare repo, setup worktree structure, run setup.
-pub fn run(url: &str, directory: Option<String>) -> Result<()> {
+///
+/// Unless `quiet`, the size of the download is shown while cloning. With
+/// `start`, a first session is started in a new worktree, as `vibe new` would.
+/// Setup runs with the `prompt` given on the command line, if any.
+pub fn run(
+    url: &str,
+    directory: Option<String>,
+    format: docker::OutputFormat,
+    quiet: bool,
+    start: bool,
+    prompt: setup::PromptSource,
+) -> Result<()> {
+    // Before cloning, so a bad prompt file doesn't leave a repository behind
+    let prompt = prompt.resolve()?;
+
     // Determine target directory name
     let dir_name = match directory {
         Some(d) => d,
@@ -56,11 +89,24 @@ pub fn run(url: &str, directory: Option<String>) -> Result<()> {
 
     let bare_dir = target_dir.join(".bare");
 
-    // Clone as bare repository into .bare subdirectory
-    let status = Command::new("git")
-        .args(["clone", "--bare", url, bare_dir.to_str().unwrap()])
-        .status()
-        .context("Failed to run git clone")?;
+    // Clone as bare repository into .bare subdirectory. Git's progress is
+    // replaced by the size display, or hidden entirely when quiet.
+    let show_size = !quiet && io::stdout().is_terminal();
+    let done = Arc::new(AtomicBool::new(false));
+    let progress = show_size.then(|| show_clone_size(&bare_dir, done.clone()));
+
+    let mut clone = Command::new("git");
+    clone.args(["clone", "--bare", url, bare_dir.to_str().unwrap()]);
+    if quiet || show_size {
+        clone.arg("--quiet");
+    }
+    let status = clone.status();
+
+    done.store(true, Ordering::Relaxed);
+    if let Some(progress) = progress {
+        let _ = progress.join();
+    }
+    let status = status.context("Failed to run git clone")?;
 
     if !status.success() {
         // Cleanup on failure
@@ -101,13 +147,29 @@ pub fn run(url: &str, directory: Option<String>) -> Result<()> {
     println!("Running setup to initialize Dockerfile.vibes...");
 
     let target_path = fs::canonicalize(target_dir).context("Failed to resolve target path")?;
-    let image_name = "claude-vibe-setup";
-
     // Fresh clone won't have Dockerfile.vibes, so this will use default image
-    let image = docker::prepare_image(&target_path, image_name)?;
+    let image = docker::prepare_image(&target_path, docker::SETUP_IMAGE)?;
 
     println!("Starting Claude Code for project setup...");
-    docker::run_container_with_output(&target_path, &image, SETUP_PROMPT)
+    docker::run_container_with_output(
+        &target_path,
+        &image,
+        &prompt,
+        format,
+        Default::default(),
+        &Default::default(),
+    )?;
+
+    if start {
+        println!();
+        std::env::set_current_dir(&target_path).context("Failed to enter cloned repository")?;
+        return new::run(new::NewOptions {
+            format,
+            ..Default::default()
+        });
+    }
+
+    Ok(())
 }
 
 #[cfg(test)]
@@ -0,0 +1,94 @@
+//! Read and write settings in the user config file.
+
+use anyhow::{Result, bail};
+use toml_edit::{DocumentMut, Item};
+
+use crate::config;
+
+/// Run `config path`: print where the config file lives.
+pub fn run_path() -> Result<()> {
+    println!("{}", config::config_path()?.display());
+    Ok(())
+}
+
+/// Run `config` without an action: print the effective settings.
+///
+/// Settings not in the config file show their defaults, marked with a
+/// comment. Optional settings that aren't set are printed as comments.
+pub fn run_show() -> Result<()> {
+    let document = load()?;
+    let config = config::Config::from_document(&document)?;
+
+    let mut table = "";
+    for (name, value) in config.values() {
+        let (section, key) = name.rsplit_once('.').unwrap_or(("", name));
+        if section != table {
+            println!("\n[{}]", section);
+            table = section;
+        }
+        match (value, config::lookup(&document, name).is_some()) {
+            (Some(value), true) => println!("{} = {}", key, value),
+            (Some(value), false) => println!("{} = {}  # default", key, value),
+            (None, _) => println!("# {} is not set", key),
+        }
+    }
+    Ok(())
+}
+
+/// Run `config get`: print the value of a setting.
+///
+/// Strings are printed as-is, other values as TOML. Settings not in the
+/// config file print their default; fails only for optional settings that
+/// aren't set.
+pub fn run_get(key: &str) -> Result<()> {
+    config::find_key(key)?;
+    let document = load()?;
+
+    let value = match config::lookup(&document, key).and_then(Item::as_value) {
+        Some(value) => Some(value.clone()),
+        None => config::Config::from_document(&document)?
+            .values()
+            .into_iter()
+            .find(|(name, _)| *name == key)
+            .and_then(|(_, value)| value),
+    };
+    match value {
+        Some(value) => match value.as_str() {
+            Some(s) => println!("{}", s),
+            // Without the whitespace and comment around it in the file
+            None => println!("{}", value.decorated("", "")),
+        },
+        None => bail!("'{}' is not set", key),
+    }
+    Ok(())
+}
+
+/// Run `config set`: validate a value and store it in the config file.
+pub fn run_set(key: &str, value: &str) -> Result<()> {
+    let key = config::find_key(key)?;
+    let value = config::parse_arg(key, value)?;
+
+    let mut document = load()?;
+    config::set(&mut document, key.name, value.clone())?;
+    // Catch values that only make sense together with the others
+    config::Config::from_document(&document)?;
+    config::save_document(&document)?;
+
+    println!("{} = {}", key.name, value);
+    Ok(())
+}
+
+/// Load the config file, warning about settings vibe doesn't know.
+///
+/// Warnings go to stderr to keep `config get` output usable in scripts.
+fn load() -> Result<DocumentMut> {
+    let document = config::load_document()?;
+    for key in config::unknown_keys(&document) {
+        eprintln!(
+            "Warning: unknown key '{}' in {}",
+            key,
+            config::config_path()?.display()
+        );
+    }
+    Ok(document)
+}
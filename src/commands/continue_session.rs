@@ -1,19 +1,149 @@
 //! Continue an existing Claude Code session.
 
-use anyhow::{bail, Result};
+use anyhow::{Result, anyhow, bail};
+use std::io::{self, IsTerminal};
 use tokio::sync::mpsc;
 
-use crate::{docker, git, tui, WORKTREE_PREFIX};
+use crate::commands::new;
+use crate::{docker, git, meta, style, summary, tui};
+
+/// Options for the `continue` command
+#[derive(Default)]
+pub struct ContinueOptions {
+    /// Worktree to continue, the picker is shown if None
+    pub worktree_name: Option<String>,
+    /// Continue the worktree containing the current directory
+    pub cwd: bool,
+    /// Prompt to resume the session with
+    pub prompt: Option<String>,
+    /// Session whose recorded variables are added
+    pub env_from: Option<String>,
+    /// Use the session's image as-is instead of preparing it
+    pub no_build: bool,
+    /// Session whose built image is used instead
+    pub image_from: Option<String>,
+    /// Extra arguments passed to `docker run`
+    pub docker_args: Vec<String>,
+}
 
 /// Run the `continue` command: attach to an existing worktree session.
-pub async fn run(worktree_name: Option<String>) -> Result<()> {
+///
+/// With `cwd`, the worktree containing the current directory is continued
+/// directly, without showing the picker. A non-empty `prompt` is given to
+/// Claude as the first instruction of the resumed session. With `env_from`,
+/// another session's recorded environment is added to this session's.
+/// With `no_build`, the session's image is used as-is instead of being
+/// prepared, and must already exist. With `image_from`, the image built
+/// for that session is used instead. `docker_args` are passed to
+/// `docker run` before the image name.
+pub async fn run(options: ContinueOptions) -> Result<()> {
     git::require_bare_repo()?;
+    docker::require_credentials()?;
+    let ContinueOptions {
+        worktree_name,
+        cwd,
+        prompt,
+        env_from,
+        no_build,
+        image_from,
+        docker_args,
+    } = options;
+
+    // Look the sources up first so a typo fails before anything is shown
+    let inherited_env = env_from.as_deref().map(new::recorded_env).transpose()?;
+    let shared_image = image_from.as_deref().map(new::shared_image).transpose()?;
+
+    let Some(worktree) = choose_worktree(worktree_name, cwd).await? else {
+        // User cancelled selection - exit silently
+        return Ok(());
+    };
+
+    let image_name = docker::session_image(&worktree.branch);
+
+    println!("Continuing session in: {}", worktree.path.display());
 
-    let name = match worktree_name {
-        Some(n) => n,
+    let mut meta = meta::read_meta(&worktree.path).unwrap_or_default();
+    if let Some(inherited) = inherited_env {
+        meta.env = meta::merge_env(&meta.env, &inherited);
+        meta::write_meta(&worktree.path, &meta)?;
+    }
+
+    let image = if let Some(image) = shared_image {
+        println!("Using image: {}", image);
+        image
+    } else if no_build {
+        if !docker::image_exists(&image_name)? {
+            bail!(
+                "Image {} does not exist\n\
+                 Continue without --no-build to build it (or to use the default image)",
+                image_name
+            );
+        }
+        image_name
+    } else {
+        docker::prepare_image(&worktree.path, &image_name)?
+    };
+
+    let launch = meta::Launch {
+        image,
+        prompt: prompt.filter(|p| !p.trim().is_empty()),
+        headless: false,
+        format: Default::default(),
+        secrets: Vec::new(),
+        docker_args,
+        // Keep offering what the session was started with
+        keep_on_exit: meta.launch.as_ref().is_some_and(|l| l.keep_on_exit),
+    };
+    if let Err(e) = meta::record_launch(&worktree.path, launch.clone()) {
+        style::print_colored("Warning:", style::theme().uncommitted);
+        println!(" {}", e);
+    }
+
+    new::launch_container(&worktree.path, &launch, meta.env)
+}
+
+/// Resolve the worktree to work on, like `vibe continue` does.
+///
+/// A name may be partial or a position in `vibe status`; if several
+/// worktrees match, or no name is given, the picker is shown. With `cwd`,
+/// the worktree containing the current directory is used without the
+/// picker. Returns None if the user cancelled.
+pub async fn choose_worktree(
+    worktree_name: Option<String>,
+    cwd: bool,
+) -> Result<Option<git::Worktree>> {
+    let worktree = match worktree_name {
+        Some(name) if is_index(&name) => match resolve_index(&name)? {
+            Some(wt) => wt,
+            None => {
+                println!("Error: Worktree '{}' not found", name);
+                println!();
+                print_available_worktrees()?;
+                bail!("Worktree not found");
+            }
+        },
+        Some(name) => {
+            let mut matches = git::find_worktrees(&name)?;
+            matches.retain(|wt| !git::is_main_worktree(wt));
+
+            if matches.len() > 1 && io::stdin().is_terminal() {
+                // Ambiguous name - let the user pick among the matches
+                return select_worktree(&matches).await;
+            } else {
+                match git::find_worktree(&name)? {
+                    Some(wt) => wt,
+                    None => {
+                        println!("Error: Worktree '{}' not found", name);
+                        println!();
+                        print_available_worktrees()?;
+                        bail!("Worktree not found");
+                    }
+                }
+            }
+        }
         None => {
-            // Interactive selection
-            let worktrees = git::list_claude_worktrees()?;
+            // Interactive selection (the main checkout isn't a session)
+            let worktrees = git::list_session_worktrees()?;
 
             if worktrees.is_empty() {
                 println!("No claude worktrees found");
@@ -21,96 +151,178 @@ pub async fn run(worktree_name: Option<String>) -> Result<()> {
                 bail!("No worktrees available");
             }
 
-            // Create items with just branch names (status will be loaded async)
-            let items: Vec<_> = worktrees
-                .iter()
-                .map(|wt| tui::WorktreeItem {
-                    branch: wt.branch.clone(),
-                    status: None,
-                    summary_state: tui::SummaryState::None,
-                })
-                .collect();
-
-            // Create channel for async updates
-            let (update_tx, update_rx) = mpsc::unbounded_channel();
-
-            // Spawn background tasks to fetch status and summaries
-            for (index, wt) in worktrees.iter().enumerate() {
-                let path = wt.path.clone();
-                let tx = update_tx.clone();
-
-                tokio::task::spawn_blocking(move || {
-                    // First fetch status
-                    let status = git::get_worktree_status(&path).unwrap_or_default();
-                    let needs_summary = status.has_uncommitted && !status.is_orphaned;
-                    let _ = tx.send(tui::WorktreeUpdate::Status {
-                        index,
-                        status: status.clone(),
-                    });
-
-                    // Then fetch AI summary if needed
-                    if needs_summary {
-                        let _ = tx.send(tui::WorktreeUpdate::SummaryStarted { index });
-                        if let Some(summary) = git::get_ai_summary(&path) {
-                            let _ = tx.send(tui::WorktreeUpdate::Summary { index, summary });
-                        }
-                    }
-                });
+            // With --cwd, skip the picker when standing inside a worktree
+            let current = if cwd {
+                worktrees
+                    .iter()
+                    .find(|wt| git::contains_current_dir(&wt.path))
+            } else {
+                None
+            };
+
+            match current {
+                Some(wt) => wt.clone(),
+                None => return select_worktree(&worktrees).await,
             }
+        }
+    };
+    Ok(Some(worktree))
+}
+
+/// Pick a worktree with the selection TUI.
+///
+/// Status and summaries are loaded in the background while the TUI is shown.
+/// Returns None if the user cancelled.
+async fn select_worktree(worktrees: &[git::Worktree]) -> Result<Option<git::Worktree>> {
+    // Create items with just branch names (status will be loaded async)
+    let items: Vec<_> = worktrees
+        .iter()
+        .map(|wt| tui::WorktreeItem {
+            branch: wt.branch.clone(),
+            status: None,
+            summary_state: tui::SummaryState::None,
+            created: meta::created(&wt.path),
+            log: None,
+        })
+        .collect();
 
-            // Drop the original sender so the channel closes when all tasks complete
-            drop(update_tx);
+    // Create channel for async updates
+    let (update_tx, update_rx) = mpsc::unbounded_channel();
 
-            // Run interactive selection with async updates
-            let selection = tui::run_single_selection_async(items, update_rx).await?;
+    // Spawn background tasks to fetch status and summaries
+    for (index, wt) in worktrees.iter().enumerate() {
+        let path = wt.path.clone();
+        let tx = update_tx.clone();
 
-            match selection {
-                Some(idx) => worktrees[idx].branch.clone(),
-                None => {
-                    // User cancelled selection - exit silently
-                    return Ok(());
+        tokio::task::spawn_blocking(move || {
+            // First fetch status
+            let status = git::get_worktree_status(&path).unwrap_or_default();
+            let needs_summary = summary::needed(&status);
+            let _ = tx.send(tui::WorktreeUpdate::Status {
+                index,
+                status: status.clone(),
+            });
+
+            // The commit log is quick, so it comes before the summary
+            if let Ok(log) = git::recent_log(&path, tui::DETAILS_LOG_COUNT) {
+                let _ = tx.send(tui::WorktreeUpdate::Log { index, log });
+            }
+
+            // Then fetch AI summary if needed, unless it is cached
+            if needs_summary {
+                if let Some(summary) = summary::cached_summary(&path) {
+                    let _ = tx.send(tui::WorktreeUpdate::Summary { index, summary });
+                } else {
+                    let _ = tx.send(tui::WorktreeUpdate::SummaryStarted { index });
+                    let update = match summary::generate(&path) {
+                        Ok(summary) => tui::WorktreeUpdate::Summary { index, summary },
+                        Err(e) => tui::WorktreeUpdate::SummaryFailed {
+                            index,
+                            reason: e.to_string(),
+                        },
+                    };
+                    let _ = tx.send(update);
                 }
             }
-        }
-    };
+        });
+    }
 
-    let worktree = match git::find_worktree(&name)? {
-        Some(wt) => wt,
-        None => {
-            println!("Error: Worktree '{}' not found", name);
-            println!();
-            print_available_worktrees()?;
-            bail!("Worktree not found");
-        }
-    };
+    // Drop the original sender so the channel closes when all tasks complete
+    drop(update_tx);
 
-    // Extract random part from worktree name for image naming
-    let random_part = worktree
-        .branch
-        .strip_prefix(WORKTREE_PREFIX)
-        .unwrap_or(&worktree.branch);
-    let image_name = format!("claude-vibe-{}", random_part);
+    // Run interactive selection with async updates
+    let selection = tui::run_single_selection_async(items, update_rx).await?;
 
-    println!("Continuing session in: {}", worktree.path.display());
+    Ok(selection.map(|idx| worktrees[idx].clone()))
+}
+
+/// Whether `name` is purely numeric, so it may be a position in `vibe status`.
+fn is_index(name: &str) -> bool {
+    !name.is_empty() && name.bytes().all(|b| b.is_ascii_digit())
+}
 
-    let image = docker::prepare_image(&worktree.path, &image_name)?;
+/// Resolve a numeric argument to a worktree.
+///
+/// A branch named by the number (or starting with it) takes precedence;
+/// otherwise it is the 1-based position among the worktrees `vibe status`
+/// numbers. Paths are never matched, so a digit in the workspace path
+/// doesn't shadow the index.
+fn resolve_index(name: &str) -> Result<Option<git::Worktree>> {
+    if let Some(wt) = git::find_worktree_by_name(name)? {
+        return Ok(Some(wt));
+    }
 
-    println!("Starting Claude Code session...");
-    docker::run_container(&worktree.path, &image, None)
+    let sessions = git::list_session_worktrees()?;
+    if sessions.is_empty() {
+        return Ok(None);
+    }
+    session_at(&sessions, name).cloned().map(Some)
+}
+
+/// Pick the worktree at the 1-based position `index` in `sessions`.
+fn session_at<'a>(sessions: &'a [git::Worktree], index: &str) -> Result<&'a git::Worktree> {
+    index
+        .parse::<usize>()
+        .ok()
+        .and_then(|i| i.checked_sub(1))
+        .and_then(|i| sessions.get(i))
+        .ok_or_else(|| {
+            anyhow!(
+                "Worktree index {} is out of range (expected 1-{})",
+                index,
+                sessions.len()
+            )
+        })
 }
 
 /// Print list of available Claude worktrees.
 fn print_available_worktrees() -> Result<()> {
     println!("Available worktrees:");
-    let worktrees = git::list_claude_worktrees()?;
+    let worktrees = git::list_session_worktrees()?;
 
     if worktrees.is_empty() {
         println!("  No claude worktrees found");
     } else {
-        for wt in worktrees {
-            println!("  {} ({})", wt.branch, wt.path.display());
+        for (i, wt) in worktrees.iter().enumerate() {
+            println!("  {}. {} ({})", i + 1, wt.branch, wt.path.display());
         }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn sessions(branches: &[&str]) -> Vec<git::Worktree> {
+        branches
+            .iter()
+            .map(|b| git::Worktree {
+                path: PathBuf::from(format!("/ws/{}", b)),
+                branch: b.to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_is_index() {
+        assert!(is_index("1"));
+        assert!(is_index("042"));
+        assert!(!is_index(""));
+        assert!(!is_index("1a"));
+        assert!(!is_index("-1"));
+    }
+
+    #[test]
+    fn test_session_at() {
+        let list = sessions(&["claude/aaa", "claude/bbb"]);
+        assert_eq!(session_at(&list, "1").unwrap().branch, "claude/aaa");
+        assert_eq!(session_at(&list, "2").unwrap().branch, "claude/bbb");
+        for index in ["0", "3", "99999999999999999999999"] {
+            let err = session_at(&list, index).err().unwrap().to_string();
+            assert!(err.contains("expected 1-2"), "{}", err);
+        }
+    }
+}
@@ -0,0 +1,222 @@
+//! Show a session's uncommitted changes, or everything it changed since its
+//! base branch.
+
+use anyhow::{Context, Result, bail};
+use crossterm::style::Color;
+use std::io::{BufRead, BufReader, IsTerminal, Write};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+
+use crate::{git, meta, style};
+
+/// Pager used when `PAGER` isn't set
+const DEFAULT_PAGER: &str = "less -R";
+
+/// Run the `diff` command: print the uncommitted changes of a worktree,
+/// followed by its untracked files.
+///
+/// With `base`, the diff covers everything since the worktree left its base
+/// branch, committed or not. With `stat`, only a summary of changed files is shown. On a terminal
+/// the output goes through `PAGER` (`less -R` by default; an empty `PAGER`
+/// turns paging off). Colors follow the usual color settings
+/// (`--no-color`, `NO_COLOR` and friends).
+pub fn run(worktree_name: &str, stat: bool, base: bool) -> Result<()> {
+    git::require_bare_repo()?;
+
+    let Some(wt) = git::find_worktree(worktree_name)? else {
+        bail!("Worktree '{}' not found", worktree_name);
+    };
+    let since = if base {
+        let base = meta::read_meta(&wt.path)
+            .and_then(|m| m.base_branch)
+            .unwrap_or_else(|| git::main_branch().to_string());
+        merge_base(&wt.path, &git::resolve_base_ref(&base))
+    } else {
+        None
+    };
+
+    let mut command = Command::new("git");
+    command.current_dir(&wt.path).args(["diff", "--no-color"]);
+    if stat {
+        command.arg("--stat");
+    }
+    command.arg(since.as_deref().unwrap_or("HEAD"));
+    let untracked = untracked_files(&wt.path)?;
+
+    let color = style::color_enabled();
+    let mut pager = start_pager()?;
+    let result = match pager.as_mut().and_then(|p| p.stdin.take()) {
+        Some(stdin) => write_diff(command, &untracked, color, stdin),
+        None => write_diff(command, &untracked, color, std::io::stdout().lock()),
+    };
+    if let Some(mut pager) = pager {
+        pager.wait().context("Failed to wait for the pager")?;
+    }
+    result
+}
+
+/// Start the pager if stdout is a terminal and paging isn't turned off.
+///
+/// Like git, `less` is told to exit right away if everything fits on
+/// one screen, unless `LESS` says otherwise.
+fn start_pager() -> Result<Option<Child>> {
+    if !std::io::stdout().is_terminal() {
+        return Ok(None);
+    }
+    let pager = std::env::var("PAGER").unwrap_or_else(|_| DEFAULT_PAGER.to_string());
+    if pager.trim().is_empty() || pager.trim() == "cat" {
+        return Ok(None);
+    }
+
+    let mut command = Command::new("sh");
+    command.args(["-c", &pager]).stdin(Stdio::piped());
+    if std::env::var_os("LESS").is_none() {
+        command.env("LESS", "FRX");
+    }
+    let child = command
+        .spawn()
+        .with_context(|| format!("Failed to run pager '{}'", pager))?;
+    Ok(Some(child))
+}
+
+/// Write the output of `git diff`, then the list of untracked files.
+fn write_diff(
+    mut command: Command,
+    untracked: &[String],
+    color: bool,
+    mut out: impl Write,
+) -> Result<()> {
+    let mut child = command
+        .stdout(Stdio::piped())
+        .spawn()
+        .context("Failed to run git diff")?;
+    let stdout = child
+        .stdout
+        .take()
+        .context("Failed to read git diff output")?;
+
+    let mut highlighter = Highlighter::default();
+    let mut written = Ok(());
+    for line in BufReader::new(stdout).lines() {
+        let line = line.context("Failed to read git diff output")?;
+        written = match highlighter.color(&line).filter(|_| color) {
+            Some(color) => writeln!(out, "{}", style::paint(&line, color)),
+            None => writeln!(out, "{}", line),
+        };
+        // The reader went away, e.g. the pager was closed
+        if written.is_err() {
+            let _ = child.kill();
+            break;
+        }
+    }
+
+    let status = child.wait().context("Failed to wait for git diff")?;
+    if written.is_err() {
+        return Ok(());
+    }
+    if !status.success() {
+        bail!("git diff failed");
+    }
+
+    if !untracked.is_empty() {
+        // A closed pager is not an error
+        let _ = writeln!(out, "\nUntracked files:");
+        for path in untracked {
+            let path = if color {
+                style::paint(path, Color::Green)
+            } else {
+                path.clone()
+            };
+            let _ = writeln!(out, "  {}", path);
+        }
+    }
+    Ok(())
+}
+
+/// Untracked files of a worktree, without ignored ones.
+fn untracked_files(worktree_path: &Path) -> Result<Vec<String>> {
+    let output = Command::new("git")
+        .current_dir(worktree_path)
+        .args(["ls-files", "--others", "--exclude-standard"])
+        .output()
+        .context("Failed to run git ls-files")?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|f| !f.starts_with(".claude/"))
+        .map(str::to_string)
+        .collect())
+}
+
+/// The commit a worktree's branch forked from `base_ref`, if there is one.
+fn merge_base(worktree_path: &Path, base_ref: &str) -> Option<String> {
+    let output = Command::new("git")
+        .current_dir(worktree_path)
+        .args(["merge-base", base_ref, "HEAD"])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Picks colors for the lines of `git diff` output, in order.
+#[derive(Default)]
+struct Highlighter {
+    /// Between a `diff --git` line and the first hunk of that file
+    in_header: bool,
+}
+
+impl Highlighter {
+    /// Color of the next line, None for context lines.
+    ///
+    /// File headers are only recognized outside hunks, so a removed line
+    /// starting with `--` isn't mistaken for one.
+    fn color(&mut self, line: &str) -> Option<Color> {
+        if line.starts_with("diff --git") {
+            self.in_header = true;
+        } else if line.starts_with("@@") {
+            self.in_header = false;
+            return Some(Color::Cyan);
+        }
+
+        if self.in_header {
+            Some(Color::White)
+        } else if line.starts_with('+') {
+            Some(Color::Green)
+        } else if line.starts_with('-') {
+            Some(Color::Red)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlighter() {
+        let diff = [
+            ("diff --git a/x b/x", Some(Color::White)),
+            ("index 1234567..89abcde 100644", Some(Color::White)),
+            ("--- a/x", Some(Color::White)),
+            ("+++ b/x", Some(Color::White)),
+            ("@@ -1,3 +1,3 @@ fn main()", Some(Color::Cyan)),
+            (" context", None),
+            ("-removed", Some(Color::Red)),
+            // A removed "-- comment" line, not a file header
+            ("--- comment", Some(Color::Red)),
+            ("+added", Some(Color::Green)),
+            ("diff --git a/y b/y", Some(Color::White)),
+            ("new file mode 100644", Some(Color::White)),
+        ];
+
+        let mut highlighter = Highlighter::default();
+        for (line, color) in diff {
+            assert_eq!(highlighter.color(line), color, "{}", line);
+        }
+    }
+}
@@ -0,0 +1,320 @@
+//! Diagnose common setup problems and optionally repair them.
+
+use anyhow::{Context, Result, bail};
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::{config, docker, git, lock, style, tui};
+
+/// Contents of the `.dockerignore` created by `--fix`
+const DOCKERIGNORE: &str = "\
+# Keep the git store and session worktrees out of the build context
+.bare
+.git
+";
+
+/// Header of the `.vibes.toml` created by `--fix`
+const REPO_CONFIG: &str = "\
+# Workspace settings for vibe
+# Image sessions use when there is no Dockerfile.vibes
+";
+
+/// A repair that `--fix` knows how to perform
+enum Fix {
+    /// Remove stale worktree entries with `git worktree prune`
+    PruneWorktrees,
+    /// Pull this image
+    PullImage(String),
+    /// Create a `.dockerignore` at this path
+    CreateDockerignore(PathBuf),
+    /// Create a `.vibes.toml` with commented-out settings at this path
+    CreateRepoConfig(PathBuf),
+}
+
+impl Fix {
+    /// Question asked before applying the fix
+    fn prompt(&self) -> String {
+        match self {
+            Fix::PruneWorktrees => "Prune stale worktree entries?".to_string(),
+            Fix::PullImage(image) => format!("Pull {}?", image),
+            Fix::CreateDockerignore(path) | Fix::CreateRepoConfig(path) => {
+                format!("Create {}?", path.display())
+            }
+        }
+    }
+
+    /// Apply the fix, returning a short description of what was done.
+    fn apply(&self) -> Result<String> {
+        match self {
+            Fix::PruneWorktrees => {
+                let status = Command::new("git")
+                    .args(["worktree", "prune"])
+                    .status()
+                    .context("Failed to run git worktree prune")?;
+                if !status.success() {
+                    bail!("git worktree prune failed");
+                }
+                Ok("Pruned stale worktree entries".to_string())
+            }
+            Fix::PullImage(image) => {
+                let status = Command::new(docker::container_runtime())
+                    .args(["pull", image])
+                    .status()
+                    .context("Failed to run docker pull")?;
+                if !status.success() {
+                    bail!("docker pull failed");
+                }
+                Ok(format!("Pulled {}", image))
+            }
+            Fix::CreateDockerignore(path) => {
+                let contents = format!("{}{}\n", DOCKERIGNORE, config::worktree_prefix());
+                fs::write(path, contents).context("Failed to write .dockerignore")?;
+                Ok(format!("Created {}", path.display()))
+            }
+            Fix::CreateRepoConfig(path) => {
+                let contents = format!(
+                    "{}# base_image = \"{}\"\n",
+                    REPO_CONFIG,
+                    config::default_image()
+                );
+                fs::write(path, contents)
+                    .with_context(|| format!("Failed to write {}", config::REPO_CONFIG_FILE))?;
+                Ok(format!("Created {}", path.display()))
+            }
+        }
+    }
+}
+
+/// A problem found by one of the checks
+struct Problem {
+    description: String,
+    fix: Option<Fix>,
+}
+
+/// Run the `doctor` command: report problems and, with `fix`, repair them.
+///
+/// Each repair is confirmed interactively unless `yes` is set.
+pub fn run(fix: bool, yes: bool) -> Result<()> {
+    let mut problems = Vec::new();
+
+    let docker_ok = check_tools(&mut problems);
+
+    let repo_info = git::get_bare_repo_info()?;
+    match &repo_info {
+        Some(repo_info) => {
+            check_stale_worktrees(&mut problems);
+            check_dockerignore(&repo_info.workspace_root, &mut problems);
+            check_repo_config(&repo_info.workspace_root, &mut problems);
+            if docker_ok {
+                check_default_image(&repo_info.workspace_root, &mut problems);
+            }
+        }
+        None => report_problem(
+            &mut problems,
+            "Not in a bare repository (use 'vibe clone' to set one up)".to_string(),
+            None,
+        ),
+    }
+
+    if problems.is_empty() {
+        println!();
+        style::println_colored("No problems found", style::theme().clean);
+        return Ok(());
+    }
+
+    println!();
+    if !fix {
+        let fixable = problems.iter().filter(|p| p.fix.is_some()).count();
+        if fixable > 0 {
+            println!("Run 'vibe doctor --fix' to repair {} of them", fixable);
+        }
+        bail!("{} problem(s) found", problems.len());
+    }
+
+    let _lock = match &repo_info {
+        Some(repo_info) => Some(lock::acquire(&repo_info.bare_path)?),
+        None => None,
+    };
+    let interactive = io::stdin().is_terminal();
+    let mut remaining = 0;
+
+    for problem in &problems {
+        let Some(fix) = &problem.fix else {
+            remaining += 1;
+            continue;
+        };
+
+        if !yes {
+            if !interactive {
+                println!("Skipping: {} (use --yes to apply)", fix.prompt());
+                remaining += 1;
+                continue;
+            }
+            if !tui::confirm(&fix.prompt())? {
+                remaining += 1;
+                continue;
+            }
+        }
+
+        match fix.apply() {
+            Ok(done) => {
+                style::print_colored("Fixed:", style::theme().clean);
+                println!(" {}", done);
+            }
+            Err(e) => {
+                style::print_colored("Failed:", style::theme().danger);
+                println!(" {}: {}", problem.description, e);
+                remaining += 1;
+            }
+        }
+    }
+
+    if remaining > 0 {
+        bail!("{} problem(s) remaining", remaining);
+    }
+
+    Ok(())
+}
+
+/// Print a passing check.
+fn report_ok(description: &str) {
+    style::print_colored(style::theme().ok, style::theme().clean);
+    println!(" {}", description);
+}
+
+/// Print a failing check and record it.
+fn report_problem(problems: &mut Vec<Problem>, description: String, fix: Option<Fix>) {
+    style::print_colored(style::theme().fail, style::theme().danger);
+    println!(" {}", description);
+    problems.push(Problem { description, fix });
+}
+
+/// Check that git and the container runtime are installed, and that the
+/// runtime's daemon is reachable.
+///
+/// Returns whether docker is usable, so image checks can be skipped otherwise.
+fn check_tools(problems: &mut Vec<Problem>) -> bool {
+    if command_succeeds("git", &["--version"]) {
+        report_ok("git is installed");
+    } else {
+        report_problem(problems, "git is not installed".to_string(), None);
+    }
+
+    let runtime = docker::container_runtime();
+    if !command_succeeds(runtime, &["--version"]) {
+        report_problem(problems, format!("{} is not installed", runtime), None);
+        return false;
+    }
+
+    if command_succeeds(runtime, &["info"]) {
+        report_ok(&format!("{} daemon is reachable", runtime));
+        true
+    } else {
+        report_problem(
+            problems,
+            format!("{} daemon is not reachable (is it running?)", runtime),
+            None,
+        );
+        false
+    }
+}
+
+/// Check for worktree entries whose directories no longer exist.
+fn check_stale_worktrees(problems: &mut Vec<Problem>) {
+    let Ok(output) = Command::new("git")
+        .args(["worktree", "prune", "--dry-run"])
+        .output()
+    else {
+        return;
+    };
+
+    let stale = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .chain(String::from_utf8_lossy(&output.stderr).lines())
+        .filter(|line| line.starts_with("Removing "))
+        .count();
+
+    if stale == 0 {
+        report_ok("No stale worktree entries");
+    } else {
+        report_problem(
+            problems,
+            format!("{} stale worktree entry(s)", stale),
+            Some(Fix::PruneWorktrees),
+        );
+    }
+}
+
+/// Check that a workspace Dockerfile.vibes comes with a `.dockerignore`.
+///
+/// Without one, building from the workspace root sends the whole bare repo
+/// and every session worktree to the docker daemon.
+fn check_dockerignore(workspace_root: &Path, problems: &mut Vec<Problem>) {
+    if !workspace_root.join("Dockerfile.vibes").exists() {
+        return;
+    }
+
+    let path = workspace_root.join(".dockerignore");
+    if path.exists() {
+        report_ok(".dockerignore is present");
+    } else {
+        report_problem(
+            problems,
+            "Dockerfile.vibes has no .dockerignore next to it".to_string(),
+            Some(Fix::CreateDockerignore(path)),
+        );
+    }
+}
+
+/// Check that the workspace has a `.vibes.toml` to pin its sessions' image.
+fn check_repo_config(workspace_root: &Path, problems: &mut Vec<Problem>) {
+    let path = workspace_root.join(config::REPO_CONFIG_FILE);
+    if path.exists() {
+        report_ok(&format!("{} is present", config::REPO_CONFIG_FILE));
+    } else {
+        report_problem(
+            problems,
+            format!(
+                "No {} (its base_image sets the image sessions use)",
+                config::REPO_CONFIG_FILE
+            ),
+            Some(Fix::CreateRepoConfig(path)),
+        );
+    }
+}
+
+/// Check that the image sessions run in is available when it isn't built
+/// from a Dockerfile.vibes: the `.vibes.toml` base image, or the default one.
+fn check_default_image(workspace_root: &Path, problems: &mut Vec<Problem>) {
+    let image = match docker::find_image_source(workspace_root) {
+        Ok(docker::ImageSource::BuildFrom { .. }) => return,
+        Ok(docker::ImageSource::UseRepoImage { image, .. }) => image,
+        Ok(docker::ImageSource::UseDefault) => config::default_image().to_string(),
+        Err(e) => {
+            report_problem(problems, e.to_string(), None);
+            return;
+        }
+    };
+
+    if command_succeeds(docker::container_runtime(), &["image", "inspect", &image]) {
+        report_ok(&format!("{} is available", image));
+    } else {
+        report_problem(
+            problems,
+            format!("{} has not been pulled", image),
+            Some(Fix::PullImage(image)),
+        );
+    }
+}
+
+/// Run a command silently and report whether it succeeded.
+fn command_succeeds(program: &str, args: &[&str]) -> bool {
+    Command::new(program)
+        .args(args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
@@ -0,0 +1,29 @@
+//! Run a one-off command in a session's container.
+
+use anyhow::{Result, bail};
+
+use crate::{docker, git, meta};
+
+/// Run the `exec` command: run `command` in the container of a worktree.
+///
+/// The image is prepared as for `vibe continue`, and the session's recorded
+/// environment is passed in. Exits with the command's exit code.
+pub fn run(worktree_name: &str, command: &[String]) -> Result<()> {
+    git::require_bare_repo()?;
+
+    let Some(wt) = git::find_worktree(worktree_name)? else {
+        bail!("Worktree '{}' not found", worktree_name);
+    };
+
+    let image = docker::prepare_image(&wt.path, &docker::session_image(&wt.branch))?;
+    let options = docker::RunOptions {
+        env: meta::read_meta(&wt.path).map(|m| m.env).unwrap_or_default(),
+        ..Default::default()
+    };
+
+    let code = docker::run_command(&wt.path, &image, command, &options)?;
+    if code != 0 {
+        std::process::exit(code);
+    }
+    Ok(())
+}
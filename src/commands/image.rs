@@ -0,0 +1,59 @@
+//! Show which image a session would use, without building or running anything.
+
+use anyhow::{Result, bail};
+use std::path::PathBuf;
+
+use crate::{config, docker, git, style};
+
+/// Run the `image` command: report the image resolution for a worktree.
+///
+/// Without a name, resolves for the worktree containing the current
+/// directory, or the workspace root outside of any worktree.
+pub fn run(worktree_name: Option<&str>) -> Result<()> {
+    let repo_info = git::require_bare_repo()?;
+
+    let path: PathBuf = match worktree_name {
+        Some(name) => match git::find_worktree(name)? {
+            Some(wt) => wt.path,
+            None => bail!("Worktree '{}' not found", name),
+        },
+        None => git::list_worktrees()?
+            .into_iter()
+            .find(|wt| git::contains_current_dir(&wt.path))
+            .map(|wt| wt.path)
+            .unwrap_or(repo_info.workspace_root),
+    };
+
+    println!("Resolving image for {}", path.display());
+    for candidate in docker::dockerfile_candidates(&path)? {
+        if candidate.exists() {
+            style::print_colored(style::theme().ok, style::theme().clean);
+        } else {
+            style::print_colored(style::theme().fail, style::theme().dim);
+        }
+        println!(" {}", candidate.display());
+    }
+    println!();
+
+    match docker::find_image_source(&path)? {
+        docker::ImageSource::BuildFrom {
+            dockerfile,
+            context,
+        } => {
+            println!("Would build from {}", dockerfile.display());
+            println!("Build context: {}", context.display());
+        }
+        docker::ImageSource::UseRepoImage { image, config } => {
+            println!(
+                "Would use {}, the base_image set in {}",
+                image,
+                config.display()
+            );
+        }
+        docker::ImageSource::UseDefault => {
+            println!("Would use the default image: {}", config::default_image());
+        }
+    }
+
+    Ok(())
+}
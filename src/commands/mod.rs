@@ -1,8 +1,23 @@
 //! Command implementations for the vibe CLI.
 
+pub mod archive;
 pub mod cleanup;
 pub mod clone;
+pub mod config;
 pub mod continue_session;
+pub mod diff;
+pub mod doctor;
+pub mod exec;
+pub mod image;
 pub mod new;
+pub mod open;
+pub mod push;
+pub mod relaunch;
+pub mod rename;
+pub mod repair;
+pub mod rm;
 pub mod setup;
+pub mod stack;
 pub mod status;
+pub mod top;
+pub mod undo;
@@ -1,9 +1,14 @@
 //! Create a new Claude Code session with a fresh git worktree.
 
-use anyhow::Result;
+use anyhow::{Context, Result, bail};
 use rand::Rng;
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
 
-use crate::{WORKTREE_PREFIX, docker, git};
+use crate::commands::push;
+use crate::{config, docker, git, lock, meta, style, summary, tui};
 
 /// Generate a random alphanumeric string for worktree naming.
 fn generate_random_name(length: usize) -> String {
@@ -18,19 +23,511 @@ fn generate_random_name(length: usize) -> String {
         .collect()
 }
 
+/// Turn a user-given session name into a branch-safe one.
+///
+/// Lowercases the name and replaces anything but letters, digits, `-` and
+/// `_` with `-`, collapsing runs of them. A leading worktree prefix is
+/// dropped. Returns `None` if nothing usable is left.
+fn sanitize_name(name: &str) -> Option<String> {
+    let name = name.strip_prefix(config::worktree_prefix()).unwrap_or(name);
+
+    let mut sanitized = String::new();
+    for c in name.trim().chars().flat_map(char::to_lowercase) {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            sanitized.push(c);
+        } else if !sanitized.is_empty() && !sanitized.ends_with('-') {
+            sanitized.push('-');
+        }
+    }
+    let sanitized = sanitized.trim_end_matches('-');
+
+    (!sanitized.is_empty()).then(|| sanitized.to_string())
+}
+
+/// Options for the `new` command
+#[derive(Default)]
+pub struct NewOptions {
+    /// Session name, random if not given
+    pub name: Option<String>,
+    /// Reset a leftover branch of the same name instead of reusing it
+    pub force: bool,
+    /// Run the prompt non-interactively, then exit
+    pub headless: bool,
+    /// Prompt to start the session with
+    pub prompt: Option<String>,
+    /// How to show Claude's output in headless mode
+    pub format: docker::OutputFormat,
+    /// Files mounted read-only into the container
+    pub secrets: Vec<docker::SecretMount>,
+    /// `KEY=VALUE` variables recorded for the session, or a bare `KEY` to
+    /// take from the host
+    pub env: Vec<String>,
+    /// Session whose recorded variables are inherited
+    pub env_from: Option<String>,
+    /// Ref to start the branch from instead of the current HEAD
+    pub base: Option<String>,
+    /// Worktree whose uncommitted changes the session starts with
+    pub copy_from: Option<String>,
+    /// Worktree whose built image is used instead of building one
+    pub image_from: Option<String>,
+    /// Extra arguments passed to `docker run`
+    pub docker_args: Vec<String>,
+    /// Offer to commit and push unsaved work once the session exits
+    pub keep_on_exit: bool,
+}
+
+/// Branch name to record as the base for a `--base` ref.
+///
+/// Remote-tracking refs are recorded as the branch name, since comparisons
+/// prefer the remote ref anyway (see `git::resolve_base_ref`).
+fn base_branch_name(base: &str) -> String {
+    let base = base.strip_prefix("refs/remotes/").unwrap_or(base);
+    base.strip_prefix("origin/").unwrap_or(base).to_string()
+}
+
+/// Load the environment recorded for another session.
+///
+/// Fails if the session doesn't exist or has no recorded environment.
+pub fn recorded_env(name: &str) -> Result<Vec<String>> {
+    let Some(wt) = git::find_worktree(name)? else {
+        bail!("Worktree '{}' not found", name);
+    };
+    match meta::read_meta(&wt.path).map(|m| m.env) {
+        Some(env) if !env.is_empty() => Ok(env),
+        _ => bail!("Session '{}' has no recorded environment", wt.branch),
+    }
+}
+
+/// Image built for another session, to be used as-is by this one.
+///
+/// Fails if the session doesn't exist or its image hasn't been built.
+pub fn shared_image(name: &str) -> Result<String> {
+    let Some(wt) = git::find_worktree(name)? else {
+        bail!("Worktree '{}' not found", name);
+    };
+    let image = docker::session_image(&wt.branch);
+    if !docker::image_exists(&image)? {
+        bail!(
+            "Session '{}' has no built image ({} does not exist)",
+            wt.branch,
+            image
+        );
+    }
+    Ok(image)
+}
+
 /// Run the `new` command: create worktree, build image, start session.
-pub fn run() -> Result<()> {
+///
+/// The session is named after `name` (sanitized), or randomly if there is
+/// none; a name already used by a worktree is rejected.
+/// With `force`, a leftover branch of the same name is reset instead of reused.
+/// In `headless` mode the prompt is run non-interactively with streamed output
+/// and the command exits once Claude is done, shown according to `format`.
+/// `secrets` are mounted read-only into the container. The session's `env`,
+/// on top of the one inherited with `env_from`, is recorded in its metadata
+/// so later `continue`s get it too, along with the launch for `relaunch`.
+/// With `copy_from`, that worktree's uncommitted changes are copied over.
+/// With `image_from`, that session's image is used instead of building one.
+/// `docker_args` are passed to `docker run` before the image name. With
+/// `keep_on_exit`, committing and pushing unsaved work is offered once the
+/// session exits.
+pub fn run(options: NewOptions) -> Result<()> {
     let repo_info = git::require_bare_repo()?;
+    docker::require_credentials()?;
+    let NewOptions {
+        name,
+        force,
+        headless,
+        prompt,
+        format,
+        secrets,
+        env,
+        env_from,
+        base,
+        copy_from,
+        image_from,
+        docker_args,
+        keep_on_exit,
+    } = options;
+    let env = match &env_from {
+        Some(name) => meta::merge_env(&recorded_env(name)?, &env),
+        None => env,
+    };
+    let copy_source = match &copy_from {
+        Some(name) => match git::find_worktree(name)? {
+            Some(wt) => Some(wt.path),
+            None => bail!("Worktree '{}' not found", name),
+        },
+        None => None,
+    };
+    let shared_image = image_from.as_deref().map(shared_image).transpose()?;
+
+    let name = match name {
+        Some(name) => {
+            let Some(sanitized) = sanitize_name(&name) else {
+                bail!("'{}' is not a usable session name", name);
+            };
+            let branch = format!("{}{}", config::worktree_prefix(), sanitized);
+            if git::list_worktrees()?.iter().any(|wt| wt.branch == branch) {
+                bail!(
+                    "A worktree for '{}' already exists\n\
+                     Use 'vibe continue {}' to resume it, or pick another name",
+                    branch,
+                    sanitized
+                );
+            }
+            sanitized
+        }
+        None => generate_random_name(8),
+    };
+    let worktree_name = format!("{}{}", config::worktree_prefix(), name);
+    let image_name = docker::session_image(&worktree_name);
 
-    let random_name = generate_random_name(8);
-    let worktree_name = format!("{}{}", WORKTREE_PREFIX, random_name);
-    let image_name = format!("claude-vibe-{}", random_name);
+    // The new branch starts from the given base or the current HEAD
+    let base_branch = match &base {
+        Some(base) => Some(base_branch_name(base)),
+        None => git::get_worktree_branch(Path::new("."))
+            .ok()
+            .filter(|b| !b.is_empty() && b != "HEAD"),
+    };
 
     println!("Creating new worktree: {}", worktree_name);
-    let worktree_path = git::create_worktree(&repo_info.workspace_root, &worktree_name)?;
+    let lock = lock::acquire(&repo_info.bare_path)?;
+    let worktree_path = git::create_worktree(
+        &repo_info.workspace_root,
+        &worktree_name,
+        force,
+        base.as_deref(),
+    )?;
+
+    if let Some(source) = &copy_source {
+        match copy_changes(source, &worktree_path) {
+            Ok(conflicts) if conflicts.is_empty() => {}
+            Ok(conflicts) => {
+                style::print_colored("Warning:", style::theme().uncommitted);
+                println!(" some changes did not apply cleanly, resolve them in:");
+                for path in conflicts {
+                    println!("  {}", path);
+                }
+            }
+            Err(e) => {
+                style::print_colored("Warning:", style::theme().uncommitted);
+                println!(" could not copy changes: {:#}", e);
+            }
+        }
+    }
+
+    let meta = meta::Meta {
+        prompt: prompt.clone(),
+        base_branch,
+        env: meta::redact_env(&env),
+        ..meta::Meta::new()
+    };
+    if let Err(e) = meta::write_meta(&worktree_path, &meta) {
+        style::print_colored("Warning:", style::theme().uncommitted);
+        println!(" {}", e);
+    }
+    drop(lock);
+
+    let image = match shared_image {
+        Some(image) => {
+            println!("Using image: {}", image);
+            image
+        }
+        None => docker::prepare_image(&worktree_path, &image_name)?,
+    };
+
+    let launch = meta::Launch {
+        image,
+        prompt,
+        headless,
+        format,
+        secrets,
+        docker_args,
+        keep_on_exit,
+    };
+    if let Err(e) = meta::record_launch(&worktree_path, launch.clone()) {
+        style::print_colored("Warning:", style::theme().uncommitted);
+        println!(" {}", e);
+    }
+
+    launch_container(&worktree_path, &launch, env)?;
+    if headless {
+        print!("Worktree: ");
+        style::println_colored(&worktree_name, style::theme().unpushed);
+    }
+    Ok(())
+}
+
+/// Copy the uncommitted changes of worktree `source` into `target`.
+///
+/// Changes to tracked files are applied as a patch, falling back to a
+/// three-way merge that leaves conflict markers behind when the worktrees
+/// are on different commits. Untracked files that aren't ignored are copied
+/// unless `target` already has them. Returns the paths that need resolving.
+fn copy_changes(source: &Path, target: &Path) -> Result<Vec<String>> {
+    let git = |dir: &Path, args: &[&str]| -> Result<Vec<u8>> {
+        let output = Command::new("git")
+            .current_dir(dir)
+            .args(args)
+            .output()
+            .context("Failed to run git")?;
+        if !output.status.success() {
+            bail!(
+                "git {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(output.stdout)
+    };
+
+    let mut conflicts = Vec::new();
+
+    let patch = git(source, &["diff", "--binary", "HEAD"])?;
+    if !patch.is_empty() {
+        let mut apply = Command::new("git")
+            .current_dir(target)
+            .args(["apply", "--3way", "--whitespace=nowarn", "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to run git apply")?;
+        apply
+            .stdin
+            .take()
+            .context("Failed to write patch")?
+            .write_all(&patch)
+            .context("Failed to write patch")?;
+        let output = apply
+            .wait_with_output()
+            .context("Failed to run git apply")?;
 
-    let image = docker::prepare_image(&worktree_path, &image_name)?;
+        let unmerged = git(target, &["diff", "--name-only", "--diff-filter=U"])?;
+        conflicts.extend(
+            String::from_utf8_lossy(&unmerged)
+                .lines()
+                .map(str::to_string),
+        );
+        if !output.status.success() && conflicts.is_empty() {
+            bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+        // --3way stages what it applied; the copy should look uncommitted
+        git(target, &["reset", "--quiet"])?;
+    }
+
+    let untracked = git(
+        source,
+        &["ls-files", "--others", "--exclude-standard", "-z"],
+    )?;
+    for path in untracked.split(|&b| b == 0).filter(|p| !p.is_empty()) {
+        let path = PathBuf::from(String::from_utf8_lossy(path).into_owned());
+        let destination = target.join(&path);
+        if destination.exists() {
+            conflicts.push(path.display().to_string());
+            continue;
+        }
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(source.join(&path), &destination)
+            .with_context(|| format!("Failed to copy {}", path.display()))?;
+    }
+
+    Ok(conflicts)
+}
+
+/// Start a container for a session as described by `launch`.
+///
+/// Shared with `relaunch`, which repeats a recorded launch.
+pub fn launch_container(
+    worktree_path: &Path,
+    launch: &meta::Launch,
+    env: Vec<String>,
+) -> Result<()> {
+    let options = docker::RunOptions {
+        env,
+        secrets: launch.secrets.clone(),
+        docker_args: launch.docker_args.clone(),
+    };
+
+    if launch.headless {
+        let prompt = launch
+            .prompt
+            .as_deref()
+            .context("--headless requires --prompt")?;
+
+        println!("Running Claude Code headless...");
+        let labels = docker::StreamLabels {
+            running: "Claude is working on your prompt...",
+            done: "Claude finished your prompt",
+        };
+        return docker::run_container_with_output(
+            worktree_path,
+            &launch.image,
+            prompt,
+            launch.format,
+            labels,
+            &options,
+        );
+    }
 
     println!("Starting Claude Code session...");
-    docker::run_container(&worktree_path, &image, None)
+    let result = docker::run_container(
+        worktree_path,
+        &launch.image,
+        launch.prompt.as_deref(),
+        &options,
+    );
+    if config::get().exit_reminder {
+        remind_unsaved_work(worktree_path);
+        if launch.keep_on_exit && io::stdin().is_terminal() {
+            offer_quick_actions(worktree_path)?;
+        }
+    }
+    // Incremental summaries describe what changed after this session
+    let _ = summary::mark(worktree_path);
+    result
+}
+
+/// Point out work that only exists in the worktree once a session ended.
+fn remind_unsaved_work(worktree_path: &Path) {
+    let Ok(status) = git::get_worktree_status(worktree_path) else {
+        return;
+    };
+    let Ok(branch) = git::get_worktree_branch(worktree_path) else {
+        return;
+    };
+    let hints = unsaved_work_hints(&branch, &status);
+    if hints.is_empty() {
+        return;
+    }
+
+    println!();
+    style::print_colored("Note:", style::theme().uncommitted);
+    let changes = status.change_summary();
+    if changes.is_empty() {
+        println!(" {} has unsaved work", branch);
+    } else {
+        println!(" {} has unsaved work ({})", branch, changes);
+    }
+    for hint in hints {
+        println!("  {}", hint);
+    }
+}
+
+/// Offer to commit the uncommitted changes of a finished session, then to
+/// push its commits.
+fn offer_quick_actions(worktree_path: &Path) -> Result<()> {
+    let branch = git::get_worktree_branch(worktree_path)?;
+    let status = git::get_worktree_status(worktree_path)?;
+
+    if status.has_uncommitted
+        && tui::confirm("Commit all changes now?")?
+        && let Some(message) = tui::ask("Commit message:")?
+    {
+        let committed = Command::new("git")
+            .current_dir(worktree_path)
+            .args(["add", "--all"])
+            .status()
+            .is_ok_and(|s| s.success())
+            && Command::new("git")
+                .current_dir(worktree_path)
+                .args(["commit", "--quiet", "-m", &message])
+                .status()
+                .is_ok_and(|s| s.success());
+        if !committed {
+            bail!("Failed to commit the changes of {}", branch);
+        }
+        println!("Committed the changes of {}", branch);
+    }
+
+    let status = git::get_worktree_status(worktree_path)?;
+    if status.commits_ahead > 0
+        && !git::is_offline()
+        && tui::confirm(&format!("Push {} to origin?", branch))?
+    {
+        push::push_branch(worktree_path, &branch, status.commits_ahead)?;
+    }
+    Ok(())
+}
+
+/// Commands for saving the unsaved work of a session, one per line.
+fn unsaved_work_hints(branch: &str, status: &git::WorktreeStatus) -> Vec<String> {
+    let name = branch
+        .strip_prefix(config::worktree_prefix())
+        .unwrap_or(branch);
+    let mut hints = Vec::new();
+    if status.has_uncommitted {
+        hints.push(format!(
+            "vibe diff {}      review the uncommitted changes",
+            name
+        ));
+        hints.push(format!("vibe continue {}  go back and commit them", name));
+    }
+    if status.has_unpushed {
+        hints.push(format!("vibe push {}      push the commits", name));
+    }
+    hints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_name() {
+        assert_eq!(
+            sanitize_name("fix-login-bug").as_deref(),
+            Some("fix-login-bug")
+        );
+        assert_eq!(
+            sanitize_name("Fix Login  Bug!").as_deref(),
+            Some("fix-login-bug")
+        );
+        assert_eq!(
+            sanitize_name("claude/feature_x").as_deref(),
+            Some("feature_x")
+        );
+        assert_eq!(
+            sanitize_name("../etc/passwd").as_deref(),
+            Some("etc-passwd")
+        );
+        assert_eq!(sanitize_name(" -- ").as_deref(), None);
+    }
+
+    #[test]
+    fn test_unsaved_work_hints() {
+        let clean = git::WorktreeStatus::default();
+        assert!(unsaved_work_hints("claude/abc", &clean).is_empty());
+
+        let unpushed = git::WorktreeStatus {
+            has_unpushed: true,
+            commits_ahead: 2,
+            ..Default::default()
+        };
+        let hints = unsaved_work_hints("claude/abc", &unpushed);
+        assert_eq!(hints.len(), 1);
+        assert!(hints[0].starts_with("vibe push abc "));
+
+        let uncommitted = git::WorktreeStatus {
+            has_uncommitted: true,
+            ..Default::default()
+        };
+        let hints = unsaved_work_hints("claude/abc", &uncommitted);
+        assert!(hints.iter().any(|h| h.starts_with("vibe continue abc ")));
+        assert!(!hints.iter().any(|h| h.starts_with("vibe push")));
+    }
+
+    #[test]
+    fn test_base_branch_name() {
+        assert_eq!(base_branch_name("origin/feature-x"), "feature-x");
+        assert_eq!(
+            base_branch_name("refs/remotes/origin/feature-x"),
+            "feature-x"
+        );
+        assert_eq!(base_branch_name("claude/abc"), "claude/abc");
+        assert_eq!(base_branch_name("v1.2.0"), "v1.2.0");
+    }
 }
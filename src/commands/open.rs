@@ -0,0 +1,73 @@
+//! Open a worktree in the host editor.
+
+use anyhow::{Context, Result, bail};
+use std::process::Command;
+
+use crate::commands::continue_session;
+use crate::{docker, git};
+
+/// Editor to open worktrees with: `EDITOR`, then `VISUAL`, then `code` if
+/// it is installed.
+fn editor_command(
+    editor: Option<String>,
+    visual: Option<String>,
+    has_code: bool,
+) -> Option<String> {
+    editor
+        .into_iter()
+        .chain(visual)
+        .find(|e| !e.trim().is_empty())
+        .or_else(|| has_code.then(|| "code".to_string()))
+}
+
+/// Run the `open` command: open a worktree in the host editor.
+///
+/// The worktree is resolved like for `vibe continue`, showing the picker
+/// if no name is given. The editor command may include arguments, e.g.
+/// `code --wait`.
+pub async fn run(worktree_name: Option<String>) -> Result<()> {
+    git::require_bare_repo()?;
+
+    let Some(editor) = editor_command(
+        std::env::var("EDITOR").ok(),
+        std::env::var("VISUAL").ok(),
+        docker::on_path("code"),
+    ) else {
+        bail!("No editor found\nSet EDITOR to the command to open worktrees with");
+    };
+
+    let Some(wt) = continue_session::choose_worktree(worktree_name, false).await? else {
+        return Ok(());
+    };
+    if !wt.path.exists() {
+        bail!("The directory of {} no longer exists", wt.branch);
+    }
+
+    println!("Opening {} in {}", wt.path.display(), editor);
+    // Through the shell, so the editor command can carry its own arguments
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$@\"", editor))
+        .arg(&editor)
+        .arg(&wt.path)
+        .status()
+        .context("Failed to run the editor")?;
+    if !status.success() {
+        bail!("{} exited with {}", editor, status);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_editor_command() {
+        let some = |s: &str| Some(s.to_string());
+        assert_eq!(editor_command(some("vim"), some("code"), true), some("vim"));
+        assert_eq!(editor_command(some(""), some("nano"), true), some("nano"));
+        assert_eq!(editor_command(None, None, true), some("code"));
+        assert_eq!(editor_command(None, None, false), None);
+    }
+}
@@ -0,0 +1,69 @@
+//! Push a session's branch to origin.
+
+use anyhow::{Context, Result, bail};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::commands::continue_session;
+use crate::{git, style};
+
+/// Check whether the branch checked out in a worktree tracks a remote branch.
+fn has_upstream(worktree_path: &Path) -> bool {
+    Command::new("git")
+        .current_dir(worktree_path)
+        .args([
+            "rev-parse",
+            "--abbrev-ref",
+            "--symbolic-full-name",
+            "@{upstream}",
+        ])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+/// Run the `push` command: push a worktree's branch to origin.
+///
+/// The worktree is resolved like for `vibe continue`, showing the picker
+/// if no name is given. A branch without an upstream is pushed with
+/// `--set-upstream`, so later pushes and `vibe status` can compare with
+/// it. Does nothing if there are no commits to push.
+pub async fn run(worktree_name: Option<String>) -> Result<()> {
+    git::require_bare_repo()?;
+    if git::is_offline() {
+        bail!("Can't push in offline mode");
+    }
+
+    let Some(wt) = continue_session::choose_worktree(worktree_name, false).await? else {
+        return Ok(());
+    };
+    let status = git::get_worktree_status(&wt.path)?;
+    if status.is_orphaned {
+        bail!("The directory of {} no longer exists", wt.branch);
+    }
+    if status.commits_ahead == 0 {
+        println!("Nothing to push, {} has no unpushed commits", wt.branch);
+        return Ok(());
+    }
+
+    push_branch(&wt.path, &wt.branch, status.commits_ahead)
+}
+
+/// Push the branch checked out in a worktree, setting its upstream if it
+/// has none, and report the `commits` pushed.
+pub fn push_branch(worktree_path: &Path, branch: &str, commits: usize) -> Result<()> {
+    let mut command = Command::new("git");
+    command.current_dir(worktree_path).arg("push");
+    if !has_upstream(worktree_path) {
+        command.args(["--set-upstream", "origin", branch]);
+    }
+    let pushed = command.status().context("Failed to run git push")?;
+    if !pushed.success() {
+        bail!("Failed to push {}", branch);
+    }
+
+    print!("Pushed {} commit(s) of ", commits);
+    style::println_colored(branch, style::theme().unpushed);
+    Ok(())
+}
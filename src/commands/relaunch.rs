@@ -0,0 +1,35 @@
+//! Start a session's container again exactly as it was last launched.
+
+use anyhow::{Result, bail};
+
+use crate::commands::new;
+use crate::{git, meta};
+
+/// Run the `relaunch` command: repeat the launch recorded for a worktree.
+///
+/// Uses the recorded image as-is, without rebuilding it. Variables whose
+/// values weren't stored are taken from the current environment.
+pub fn run(worktree_name: &str) -> Result<()> {
+    git::require_bare_repo()?;
+
+    let Some(wt) = git::find_worktree(worktree_name)? else {
+        bail!("Worktree '{}' not found", worktree_name);
+    };
+    let meta = meta::read_meta(&wt.path).unwrap_or_default();
+    let Some(launch) = meta.launch else {
+        bail!(
+            "No launch recorded for '{}'\n\
+             Use 'vibe continue {}' to start it",
+            wt.branch,
+            worktree_name
+        );
+    };
+
+    // Docker would create a directory in place of a missing file
+    if let Some(missing) = launch.secrets.iter().find(|s| !s.host.is_file()) {
+        bail!("Secret file {} no longer exists", missing.host.display());
+    }
+
+    println!("Relaunching session in: {}", wt.path.display());
+    new::launch_container(&wt.path, &launch, meta.env)
+}
@@ -0,0 +1,168 @@
+//! Rename a worktree together with its branch.
+
+use anyhow::{Context, Result, bail};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::{config, docker, git, lock, meta, style};
+
+/// Branch name for a new session name, adding the `claude/` prefix if missing.
+fn target_branch(new_name: &str) -> String {
+    let name = new_name
+        .strip_prefix(config::worktree_prefix())
+        .unwrap_or(new_name);
+    format!("{}{}", config::worktree_prefix(), name)
+}
+
+/// Run the `rename` command: rename a worktree's branch, directory and image.
+///
+/// `old` may be a unique prefix of the name, but not any other part of it.
+/// Sessions based on the renamed branch are updated to its new name. Refuses
+/// while a session is running in the worktree, and moves the worktree back
+/// if the branch can't be renamed.
+pub fn run(old: &str, new_name: &str) -> Result<()> {
+    let repo_info = git::require_bare_repo()?;
+    let _lock = lock::acquire(&repo_info.bare_path)?;
+
+    let Some(wt) = git::find_worktree_strict(old)? else {
+        bail!("Worktree '{}' not found", old);
+    };
+    if git::is_main_worktree(&wt) {
+        bail!("Refusing to rename the main branch '{}'", wt.branch);
+    }
+    if !wt.branch.starts_with(config::worktree_prefix()) {
+        bail!("'{}' is not managed by vibe", wt.branch);
+    }
+    if git::contains_current_dir(&wt.path) {
+        bail!(
+            "The current directory is inside {} (cd out of it first)",
+            wt.branch
+        );
+    }
+    if docker::session_running(&repo_info.workspace_root, &wt.branch) {
+        bail!(
+            "A session is running in {} (exit it before renaming)",
+            wt.branch
+        );
+    }
+
+    let branch = target_branch(new_name);
+    if branch == config::worktree_prefix() {
+        bail!("The new name must not be empty");
+    }
+    let valid = Command::new("git")
+        .args(["check-ref-format", "--branch", &branch])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success());
+    if !valid {
+        bail!("'{}' is not a valid branch name", branch);
+    }
+    if git::branch_exists(&branch) {
+        bail!("Branch '{}' already exists", branch);
+    }
+
+    let new_path = repo_info.workspace_root.join(&branch);
+    if new_path.exists() {
+        bail!("Directory '{}' already exists", new_path.display());
+    }
+
+    let moved = wt.path.exists();
+    if moved && !move_worktree(&wt.path, &new_path)? {
+        bail!("Failed to move worktree to {}", new_path.display());
+    }
+
+    let status = Command::new("git")
+        .args(["branch", "-m", &wt.branch, &branch])
+        .status()
+        .context("Failed to run git branch")?;
+    if !status.success() {
+        // Put the worktree back so the rename can simply be retried
+        if moved && !move_worktree(&new_path, &wt.path)? {
+            bail!(
+                "Failed to rename branch (the worktree was already moved to {})",
+                new_path.display()
+            );
+        }
+        bail!("Failed to rename branch '{}'", wt.branch);
+    }
+
+    rename_image(&wt.branch, &branch);
+    update_bases(&wt.branch, &branch)?;
+
+    print!("Renamed {} to ", wt.branch);
+    style::println_colored(&branch, style::theme().unpushed);
+    println!("{}", new_path.display());
+    Ok(())
+}
+
+/// Move a worktree with `git worktree move`, returning whether it worked.
+fn move_worktree(from: &Path, to: &Path) -> Result<bool> {
+    let status = Command::new("git")
+        .args([
+            "worktree",
+            "move",
+            from.to_str().unwrap(),
+            to.to_str().unwrap(),
+        ])
+        .status()
+        .context("Failed to run git worktree move")?;
+    Ok(status.success())
+}
+
+/// Point sessions that were based on the renamed branch at its new name.
+fn update_bases(old_branch: &str, new_branch: &str) -> Result<()> {
+    for wt in git::list_claude_worktrees()? {
+        let Some(mut meta) = meta::read_meta(&wt.path) else {
+            continue;
+        };
+        if meta.base_branch.as_deref() == Some(old_branch) {
+            meta.base_branch = Some(new_branch.to_string());
+            meta::write_meta(&wt.path, &meta)?;
+        }
+    }
+    Ok(())
+}
+
+/// Move the session's image tag along with the branch, if it was built.
+fn rename_image(old_branch: &str, new_branch: &str) {
+    let old_image = docker::session_image(old_branch);
+    let new_image = docker::session_image(new_branch);
+
+    let exists = Command::new(docker::container_runtime())
+        .args(["image", "inspect", &old_image])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success());
+    if !exists {
+        return;
+    }
+
+    let tagged = Command::new(docker::container_runtime())
+        .args(["tag", &old_image, &new_image])
+        .status()
+        .is_ok_and(|s| s.success());
+    if tagged {
+        // Only drops the old tag, the image itself is kept under the new one
+        let _ = Command::new(docker::container_runtime())
+            .args(["rmi", &old_image])
+            .stdout(Stdio::null())
+            .status();
+    } else {
+        style::print_colored("Warning:", style::theme().uncommitted);
+        println!(" could not retag image {} as {}", old_image, new_image);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_target_branch() {
+        assert_eq!(target_branch("fix-login"), "claude/fix-login");
+        assert_eq!(target_branch("claude/fix-login"), "claude/fix-login");
+    }
+}
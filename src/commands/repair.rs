@@ -0,0 +1,77 @@
+//! Reconnect worktrees with the repository after the workspace was moved.
+
+use anyhow::{Context, Result, bail};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::{git, lock};
+
+/// Contents of the workspace `.git` file written by `vibe clone`
+const GIT_FILE: &str = "gitdir: ./.bare\n";
+
+/// Run the `repair` command: fix the links between the repository and its
+/// worktrees.
+///
+/// Works from the workspace root or any worktree, even when git no longer
+/// finds the repository there. The workspace `.git` file is rewritten if it
+/// doesn't point to `.bare`, and worktrees are found at their place in the
+/// moved workspace before `git worktree repair` updates the links both ways.
+///
+/// Entries of worktrees that are not found in the moved workspace are
+/// pruned, as there is nothing left to link them to.
+pub fn run() -> Result<()> {
+    let cwd = std::env::current_dir().context("Failed to get the current directory")?;
+    let Some(root) = git::find_workspace_root(&cwd) else {
+        bail!("No vibe workspace found (there is no .bare directory here or above)");
+    };
+    let bare_path = root.join(".bare");
+    let _lock = lock::acquire(&bare_path)?;
+
+    let git_file = root.join(".git");
+    if git_file.is_dir() {
+        bail!("{} is a directory, not a link to .bare", git_file.display());
+    }
+    if fs::read_to_string(&git_file).ok().as_deref() != Some(GIT_FILE) {
+        fs::write(&git_file, GIT_FILE)
+            .with_context(|| format!("Failed to write {}", git_file.display()))?;
+        println!("Pointed {} at .bare", git_file.display());
+    }
+
+    let mut worktrees: Vec<PathBuf> = Vec::new();
+    for (entry, link) in git::recorded_worktree_links(&bare_path) {
+        let found = if link.exists() {
+            Some(link.clone())
+        } else {
+            git::relocated_link(&root, &link, Path::is_file)
+        };
+        match found.as_deref().and_then(Path::parent) {
+            Some(dir) => worktrees.push(dir.to_path_buf()),
+            None => {
+                fs::remove_dir_all(&entry)
+                    .with_context(|| format!("Failed to remove {}", entry.display()))?;
+                println!(
+                    "Pruned {}: it was not found in the workspace",
+                    link.parent().unwrap_or(&link).display()
+                );
+            }
+        }
+    }
+
+    let status = Command::new("git")
+        .current_dir(&root)
+        .args(["worktree", "repair"])
+        .args(&worktrees)
+        .status()
+        .context("Failed to run git worktree repair")?;
+    if !status.success() {
+        bail!("git worktree repair failed");
+    }
+
+    println!(
+        "Checked {} worktree(s) in {}",
+        worktrees.len(),
+        root.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,67 @@
+//! Remove a single worktree by name.
+
+use anyhow::{Result, bail};
+
+use crate::{git, lock, style, trash, tui};
+
+/// Run the `rm` command: remove a worktree and its branch.
+///
+/// Worktrees with local changes or an unfinished rebase or merge are only
+/// removed after confirmation, or right away with `force`. Like cleanup,
+/// the removal can be reverted with `vibe undo`.
+pub fn run(worktree_name: &str, force: bool) -> Result<()> {
+    let repo_info = git::require_bare_repo()?;
+    let _lock = lock::acquire(&repo_info.bare_path)?;
+
+    let Some(wt) = git::find_worktree_strict(worktree_name)? else {
+        bail!("Worktree '{}' not found", worktree_name);
+    };
+    if git::is_main_worktree(&wt) {
+        bail!("Refusing to remove the main branch '{}'", wt.branch);
+    }
+    if git::contains_current_dir(&wt.path) {
+        bail!(
+            "The current directory is inside {} (cd out of it first)",
+            wt.branch
+        );
+    }
+
+    let status = git::get_worktree_status(&wt.path).unwrap_or_default();
+    let mut details = status.change_summary();
+    if let Some(op) = &status.in_progress_op {
+        if !details.is_empty() {
+            details.push_str(", ");
+        }
+        details.push_str(&format!("{} in progress", op));
+    }
+
+    let has_changes = status.has_local_changes() || status.in_progress_op.is_some();
+    if has_changes && !force {
+        style::print_colored("Warning:", style::theme().uncommitted);
+        print!(" {} has uncommitted or unpushed changes", wt.branch);
+        if details.is_empty() {
+            println!();
+        } else {
+            println!(" ({})", details);
+        }
+        if !tui::confirm("Remove it anyway?")? {
+            println!("Cancelled.");
+            return Ok(());
+        }
+    }
+
+    let saved = trash::preserve(&repo_info.workspace_root, &wt)?;
+    git::remove_worktree_with_branch(&wt.path, &wt.branch, true)?;
+
+    print!("Removed ");
+    style::println_colored(&wt.branch, style::theme().clean);
+
+    match trash::record(&repo_info.workspace_root, vec![saved]) {
+        Ok(()) => println!("Run 'vibe undo' to restore it"),
+        Err(e) => {
+            style::print_colored("Warning:", style::theme().uncommitted);
+            println!(" failed to record removal for undo: {}", e);
+        }
+    }
+    Ok(())
+}
@@ -1,25 +1,144 @@
 //! Initialize Dockerfile.vibes for a project by analyzing it with Claude.
 
-use anyhow::Result;
+use anyhow::{Context, Result, bail};
+use std::fs;
+use std::path::PathBuf;
 
-use crate::{docker, git};
+use crate::{config, docker, git, style};
 
-const SETUP_PROMPT: &str = "\
-Analyze this project and create a Dockerfile.vibes file that includes all necessary \
-dependencies and tools for development. The Dockerfile should be based on sirsedev/claude-vibe \
-as the base image (which already includes Claude Code). Add any project-specific dependencies \
-needed to build and run this project. Please examine the project structure, dependencies, \
-and build system to determine the requirements.";
+/// Dockerfile.vibes written by `setup --template`; `{image}` is replaced
+/// with the default image
+const TEMPLATE: &str = "\
+FROM {image}
 
-/// Run the `setup` command: analyze project and create Dockerfile.vibes.
-pub fn run() -> Result<()> {
+# System packages are installed as root
+USER root
+RUN apt-get update && apt-get install -y --no-install-recommends \\
+    build-essential \\
+    pkg-config \\
+    && rm -rf /var/lib/apt/lists/*
+
+# Toolchains for the session user go below, for example:
+USER claude
+# RUN curl --proto '=https' -sSf https://sh.rustup.rs | sh -s -- -y
+# ENV PATH=\"/home/claude/.cargo/bin:$PATH\"
+# RUN curl -fsSL https://deb.nodesource.com/setup_lts.x | sudo bash - && sudo apt-get install -y nodejs
+";
+
+/// Setup prompt given on the command line, if any
+#[derive(Default)]
+pub struct PromptSource {
+    /// Prompt given inline
+    pub prompt: Option<String>,
+    /// File to read the prompt from
+    pub file: Option<PathBuf>,
+}
+
+impl PromptSource {
+    /// The prompt to run setup with.
+    ///
+    /// An inline prompt wins over a prompt file, which wins over the
+    /// configured `setup_prompt` (or the built-in default). Fails if the
+    /// file can't be read or is empty.
+    pub fn resolve(self) -> Result<String> {
+        if let Some(prompt) = self.prompt {
+            return Ok(prompt);
+        }
+        let Some(file) = self.file else {
+            return Ok(config::get().setup_prompt.clone());
+        };
+
+        let prompt = fs::read_to_string(&file)
+            .with_context(|| format!("Failed to read prompt file {}", file.display()))?;
+        if prompt.trim().is_empty() {
+            bail!("Prompt file {} is empty", file.display());
+        }
+        Ok(prompt)
+    }
+}
+
+/// Run `setup --template`: write a starting Dockerfile.vibes without
+/// running Claude or Docker.
+///
+/// An existing Dockerfile.vibes is only replaced with `force`.
+pub fn run_template(force: bool) -> Result<()> {
     let repo_info = git::require_bare_repo()?;
 
-    let image_name = "claude-vibe-setup";
+    let path = repo_info.workspace_root.join("Dockerfile.vibes");
+    if path.exists() && !force {
+        bail!(
+            "{} already exists (use --force to overwrite it)",
+            path.display()
+        );
+    }
+
+    let contents = TEMPLATE.replace("{image}", config::default_image());
+    fs::write(&path, contents).with_context(|| format!("Failed to write {}", path.display()))?;
+
+    print!("Created ");
+    style::println_colored(&path.display().to_string(), style::theme().clean);
+    println!("Add your project's dependencies to it before starting a session");
+    Ok(())
+}
+
+/// Run the `setup` command: analyze project and create Dockerfile.vibes.
+pub fn run(format: docker::OutputFormat, prompt: PromptSource) -> Result<()> {
+    let repo_info = git::require_bare_repo()?;
+    let prompt = prompt.resolve()?;
 
     // For setup, we use the workspace root (where Dockerfile.vibes will be created)
-    let image = docker::prepare_image(&repo_info.workspace_root, image_name)?;
+    let image = docker::prepare_image(&repo_info.workspace_root, docker::SETUP_IMAGE)?;
 
     println!("Starting Claude Code for project setup...");
-    docker::run_container_with_output(&repo_info.workspace_root, &image, SETUP_PROMPT)
+    docker::run_container_with_output(
+        &repo_info.workspace_root,
+        &image,
+        &prompt,
+        format,
+        Default::default(),
+        &Default::default(),
+    )?;
+
+    style::println_colored("Setup complete!", style::theme().clean);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prompt_source_resolve() {
+        let dir = std::env::temp_dir().join(format!("vibe-prompt-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("setup.md");
+        fs::write(&file, "Write a Dockerfile\n").unwrap();
+
+        let inline = PromptSource {
+            prompt: Some("inline".to_string()),
+            file: Some(file.clone()),
+        };
+        assert_eq!(inline.resolve().unwrap(), "inline");
+
+        let from_file = PromptSource {
+            prompt: None,
+            file: Some(file.clone()),
+        };
+        assert_eq!(from_file.resolve().unwrap(), "Write a Dockerfile\n");
+
+        fs::write(&file, " \n").unwrap();
+        let empty = PromptSource {
+            prompt: None,
+            file: Some(file.clone()),
+        };
+        assert!(empty.resolve().is_err());
+
+        let missing = PromptSource {
+            prompt: None,
+            file: Some(dir.join("missing.md")),
+        };
+        assert!(missing.resolve().is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -0,0 +1,190 @@
+//! Show how Claude worktrees branch off one another.
+
+use anyhow::Result;
+
+use crate::{git, meta, style};
+
+/// A branch and the worktree branches stacked on top of it.
+struct StackNode {
+    branch: String,
+    children: Vec<StackNode>,
+}
+
+/// Run the `stack` command: print a tree of worktrees grouped by base branch.
+///
+/// Each worktree is placed under the branch recorded as its base in
+/// `.vibe/meta.json`. Worktrees without recorded metadata are rooted on the
+/// main branch.
+pub fn run() -> Result<()> {
+    git::require_bare_repo()?;
+
+    let worktrees = git::list_claude_worktrees()?;
+
+    if worktrees.is_empty() {
+        println!("No claude worktrees found");
+        println!("Use 'vibe new' to create a new session");
+        return Ok(());
+    }
+
+    let main_branch = git::get_main_branch().unwrap_or_else(|_| "main".to_string());
+
+    let entries: Vec<(String, String)> = worktrees
+        .iter()
+        .map(|wt| {
+            let base = meta::read_meta(&wt.path)
+                .and_then(|m| m.base_branch)
+                .unwrap_or_else(|| main_branch.clone());
+            (wt.branch.clone(), base)
+        })
+        .collect();
+
+    for root in build_stack(&entries, &main_branch) {
+        println!("{}", root.branch);
+        let base_ref = git::resolve_base_ref(&root.branch);
+        print_children(&root, &base_ref, "");
+    }
+
+    Ok(())
+}
+
+/// Build the stack trees from `(branch, base)` pairs.
+///
+/// Bases that aren't worktree branches themselves become roots. Entries whose
+/// bases form a cycle are never reached from a root, so they are attached to
+/// `fallback_root` instead of being dropped.
+fn build_stack(entries: &[(String, String)], fallback_root: &str) -> Vec<StackNode> {
+    let is_branch = |name: &str| entries.iter().any(|(branch, _)| branch == name);
+
+    let mut root_names: Vec<&str> = Vec::new();
+    for (_, base) in entries {
+        if !is_branch(base) && !root_names.contains(&base.as_str()) {
+            root_names.push(base);
+        }
+    }
+
+    let mut visited = vec![false; entries.len()];
+    let mut roots: Vec<StackNode> = root_names
+        .iter()
+        .map(|name| build_node(name, entries, &mut visited))
+        .collect();
+
+    for i in 0..entries.len() {
+        if visited[i] {
+            continue;
+        }
+        visited[i] = true;
+        let node = build_node(&entries[i].0, entries, &mut visited);
+
+        match roots.iter_mut().find(|r| r.branch == fallback_root) {
+            Some(root) => root.children.push(node),
+            None => roots.push(StackNode {
+                branch: fallback_root.to_string(),
+                children: vec![node],
+            }),
+        }
+    }
+
+    roots
+}
+
+/// Build a node for `name`, recursively collecting entries based on it.
+fn build_node(name: &str, entries: &[(String, String)], visited: &mut [bool]) -> StackNode {
+    let mut children = Vec::new();
+
+    for (i, (branch, base)) in entries.iter().enumerate() {
+        if base == name && !visited[i] {
+            visited[i] = true;
+            children.push(build_node(branch, entries, visited));
+        }
+    }
+
+    StackNode {
+        branch: name.to_string(),
+        children,
+    }
+}
+
+/// Print the children of a node with box-drawing connectors.
+fn print_children(node: &StackNode, parent_ref: &str, prefix: &str) {
+    let theme = style::theme();
+    for (i, child) in node.children.iter().enumerate() {
+        let is_last = i == node.children.len() - 1;
+        let connector = if is_last {
+            theme.tree_last
+        } else {
+            theme.tree_branch
+        };
+
+        style::print_colored(&format!("{}{}", prefix, connector), theme.dim);
+        print!("{}", child.branch);
+        print_divergence(parent_ref, &child.branch);
+        println!();
+
+        let continuation = if is_last {
+            " ".repeat(theme.tree_last.chars().count())
+        } else {
+            theme.tree_pipe.to_string()
+        };
+        print_children(child, &child.branch, &format!("{}{}", prefix, continuation));
+    }
+}
+
+/// Print ahead/behind counts of `branch` relative to its parent.
+fn print_divergence(parent_ref: &str, branch: &str) {
+    let theme = style::theme();
+    match git::get_ahead_behind(parent_ref, branch) {
+        Some((0, 0)) => style::print_colored("  up to date", theme.dim),
+        Some((ahead, behind)) => {
+            if ahead > 0 {
+                style::print_colored(&format!("  {}{}", theme.ahead, ahead), theme.unpushed);
+            }
+            if behind > 0 {
+                style::print_colored(&format!("  {}{}", theme.behind, behind), theme.uncommitted);
+            }
+        }
+        None => style::print_colored("  unknown", theme.dim),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(branch: &str, base: &str) -> (String, String) {
+        (branch.to_string(), base.to_string())
+    }
+
+    #[test]
+    fn test_build_stack() {
+        let entries = vec![
+            entry("claude/a", "main"),
+            entry("claude/b", "claude/a"),
+            entry("claude/c", "feature"),
+            entry("claude/d", "main"),
+        ];
+
+        let roots = build_stack(&entries, "main");
+        let names: Vec<_> = roots.iter().map(|r| r.branch.as_str()).collect();
+        assert_eq!(names, ["main", "feature"]);
+
+        let main_children: Vec<_> = roots[0]
+            .children
+            .iter()
+            .map(|c| c.branch.as_str())
+            .collect();
+        assert_eq!(main_children, ["claude/a", "claude/d"]);
+        assert_eq!(roots[0].children[0].children[0].branch, "claude/b");
+        assert_eq!(roots[1].children[0].branch, "claude/c");
+    }
+
+    #[test]
+    fn test_build_stack_cycle_falls_back_to_main() {
+        let entries = vec![entry("claude/a", "claude/b"), entry("claude/b", "claude/a")];
+
+        let roots = build_stack(&entries, "main");
+        assert_eq!(roots.len(), 1);
+        assert_eq!(roots[0].branch, "main");
+        assert_eq!(roots[0].children[0].branch, "claude/a");
+        assert_eq!(roots[0].children[0].children[0].branch, "claude/b");
+    }
+}
@@ -1,18 +1,208 @@
 //! Show status of all Claude worktrees.
 
 use anyhow::Result;
+use serde::Serialize;
+use std::cmp::Reverse;
 use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use crate::{git, style};
+use crate::{config, du, git, meta, style, summary, util};
+
+/// Options for the `status` command
+pub struct StatusOptions {
+    /// Only show worktrees whose branch matches this glob
+    pub pattern: Option<String>,
+    /// Include worktrees not managed by vibe
+    pub all: bool,
+    /// Print stable tab-separated lines instead
+    pub porcelain: bool,
+    /// Print a JSON array instead
+    pub json: bool,
+    /// Order worktrees by this instead of `git worktree list` order
+    pub sort: Option<SortKey>,
+    /// Reverse the order
+    pub reverse: bool,
+    /// Show each worktree's disk usage
+    pub size: bool,
+    /// Directory depth the disk usage is counted to
+    pub depth: Option<usize>,
+    /// Generate new summaries instead of using cached ones
+    pub refresh: bool,
+    /// Only list worktrees behind their base branch, after fetching once
+    pub behind_only: bool,
+    /// Summarize only what changed since the last incremental status or session
+    pub incremental: bool,
+}
+
+impl StatusOptions {
+    /// Limits for the disk usage walk, None unless sizes are shown.
+    ///
+    /// Worktrees are measured in parallel, each for at most `SIZE_TIMEOUT`.
+    fn size_limits(&self) -> Option<du::Limits> {
+        self.size.then(|| du::Limits {
+            max_depth: self.depth,
+            deadline: Some(Instant::now() + SIZE_TIMEOUT),
+        })
+    }
+}
+
+/// Time spent measuring a worktree's size before showing a lower bound
+const SIZE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// What to order the status listing by
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum SortKey {
+    /// Branch name, alphabetically
+    Name,
+    /// Most recently active first
+    Activity,
+    /// Most changed lines first
+    Changes,
+}
+
+/// Status of a worktree, with what's needed to sort it
+struct Entry {
+    branch: String,
+    path: PathBuf,
+    status: git::WorktreeStatus,
+    /// Only looked up when sorting by activity
+    activity: Option<SystemTime>,
+    /// Creation time recorded by `vibe new`
+    created: Option<SystemTime>,
+    /// Disk usage, only measured with `--size`
+    usage: Option<du::Usage>,
+}
+
+impl Entry {
+    fn load(wt: &git::Worktree, sort: Option<SortKey>, size: Option<du::Limits>) -> Self {
+        let status = git::get_worktree_status(&wt.path).unwrap_or_default();
+        let activity = match sort {
+            Some(SortKey::Activity) => git::last_activity(&wt.path),
+            _ => None,
+        };
+        Self {
+            branch: wt.branch.clone(),
+            path: wt.path.clone(),
+            status,
+            activity,
+            created: meta::read_meta(&wt.path).and_then(|m| m.created()),
+            usage: size.map(|limits| du::usage(&wt.path, limits)),
+        }
+    }
+
+    fn changed_lines(&self) -> usize {
+        self.status.lines_added + self.status.untracked_files + self.status.lines_deleted
+    }
+}
+
+/// Order `entries` by `sort`, keeping the current order among equals.
+fn sort_entries<T>(entries: &mut [(Entry, T)], sort: Option<SortKey>, reverse: bool) {
+    match sort {
+        Some(SortKey::Name) => entries.sort_by(|(a, _), (b, _)| a.branch.cmp(&b.branch)),
+        Some(SortKey::Activity) => entries.sort_by_key(|(e, _)| Reverse(e.activity)),
+        Some(SortKey::Changes) => entries.sort_by_key(|(e, _)| Reverse(e.changed_lines())),
+        None => {}
+    }
+    if reverse {
+        entries.reverse();
+    }
+}
+
+/// A worktree's status as printed by `status --json`.
+///
+/// Scripts depend on this shape, so fields may be added but never changed.
+#[derive(Serialize)]
+struct WorktreeStatusReport<'a> {
+    branch: &'a str,
+    path: &'a PathBuf,
+    is_orphaned: bool,
+    has_uncommitted: bool,
+    has_unpushed: bool,
+    lines_added: usize,
+    lines_deleted: usize,
+    untracked_files: usize,
+    commits_ahead: usize,
+    in_progress_op: Option<&'a str>,
+    summary: Option<&'a str>,
+    /// Seconds since the Unix epoch, null for worktrees created before
+    /// creation times were recorded
+    created_at: Option<u64>,
+    /// Only present with `--size`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size_bytes: Option<u64>,
+    /// Whether `size_bytes` is a lower bound because counting was cut short
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size_capped: Option<bool>,
+}
+
+impl<'a> WorktreeStatusReport<'a> {
+    fn new(entry: &'a Entry, summary: Option<&'a str>) -> Self {
+        let status = &entry.status;
+        Self {
+            branch: &entry.branch,
+            path: &entry.path,
+            is_orphaned: status.is_orphaned,
+            has_uncommitted: status.has_uncommitted,
+            has_unpushed: status.has_unpushed,
+            lines_added: status.lines_added,
+            lines_deleted: status.lines_deleted,
+            untracked_files: status.untracked_files,
+            commits_ahead: status.commits_ahead,
+            in_progress_op: status.in_progress_op.as_deref(),
+            summary,
+            created_at: entry
+                .created
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs()),
+            size_bytes: entry.usage.map(|u| u.bytes),
+            size_capped: entry.usage.map(|u| u.capped),
+        }
+    }
+}
 
 /// Run the `status` command: show all worktrees with their status.
-pub async fn run() -> Result<()> {
+///
+/// With `pattern`, only worktrees whose branch matches the glob are shown.
+/// With `all`, worktrees not managed by vibe are included too. With
+/// `porcelain`, a stable tab-separated line is printed per worktree instead,
+/// and with `json` a JSON array of `WorktreeStatusReport`s. With
+/// `behind_only`, only worktrees that need rebasing onto their base are listed.
+/// Sessions are numbered by the position `vibe continue` accepts.
+pub async fn run(options: StatusOptions) -> Result<()> {
     git::require_bare_repo()?;
+    let all = options.all;
 
-    let worktrees = git::list_claude_worktrees()?;
+    let mut worktrees = if all {
+        git::list_worktrees()?
+    } else {
+        git::list_claude_worktrees()?
+    };
+
+    if let Some(pattern) = &options.pattern {
+        worktrees = git::filter_by_glob(&worktrees, pattern)?;
+    }
+
+    if options.porcelain {
+        return print_porcelain(worktrees, &options).await;
+    }
+
+    if options.behind_only {
+        return print_behind(worktrees).await;
+    }
+
+    if options.json {
+        let results = load_with_summaries(worktrees, &options).await?;
+        let reports: Vec<_> = results
+            .iter()
+            .map(|(entry, summary)| WorktreeStatusReport::new(entry, summary.as_deref()))
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&reports)?);
+        return Ok(());
+    }
 
     if worktrees.is_empty() {
-        println!("No claude worktrees found");
+        println!("No {}worktrees found", if all { "" } else { "claude " });
         println!("Use 'vibe new' to create a new session");
         return Ok(());
     }
@@ -21,82 +211,78 @@ pub async fn run() -> Result<()> {
     print!("Loading worktree status...");
     io::stdout().flush()?;
 
-    // Fetch statuses and summaries in parallel
-    let mut handles = Vec::new();
-    for wt in &worktrees {
-        let path = wt.path.clone();
-        let branch = wt.branch.clone();
-        handles.push(tokio::task::spawn_blocking(move || {
-            let status = git::get_worktree_status(&path).unwrap_or_default();
-            let summary = if status.has_uncommitted && !status.is_orphaned {
-                git::get_ai_summary(&path)
-            } else {
-                None
-            };
-            (branch, status, summary)
-        }));
-    }
-
-    // Collect results
-    let mut results = Vec::with_capacity(handles.len());
-    for handle in handles {
-        results.push(handle.await?);
-    }
+    let results = load_with_summaries(worktrees, &options).await?;
 
     // Clear loading message
     style::clear_line();
 
-    println!("Claude worktrees:\n");
+    style::print_offline_notice();
+    println!("{}:\n", if all { "Worktrees" } else { "Claude worktrees" });
 
-    for (branch, status, summary) in &results {
-        // Status indicator
-        let (icon, color) = if status.is_orphaned {
-            ("✗", style::indicators::DANGER)
-        } else if status.has_uncommitted && status.has_unpushed {
-            ("●", style::indicators::DANGER)
-        } else if status.has_uncommitted {
-            ("●", style::indicators::UNCOMMITTED)
-        } else if status.has_unpushed {
-            ("●", style::indicators::UNPUSHED)
-        } else {
-            ("●", style::indicators::CLEAN)
-        };
+    // Positions `vibe continue <n>` accepts, kept across filters and sorting
+    let sessions = git::list_session_worktrees()?;
+    let width = sessions.len().to_string().len();
+
+    for (
+        Entry {
+            branch,
+            path,
+            status,
+            created,
+            usage,
+            ..
+        },
+        summary,
+    ) in &results
+    {
+        match sessions.iter().position(|wt| wt.path == *path) {
+            Some(i) => print!("{:>width$}. ", i + 1),
+            None => print!("{:width$}  ", ""),
+        }
 
+        // Status indicator
+        let (icon, color) = style::status_indicator(status);
         style::print_colored(icon, color);
-        println!(" {}", branch);
+        print!(" {}", branch);
+        if branch == git::main_branch() {
+            style::print_colored(" (main)", style::theme().dim);
+        } else if !branch.starts_with(config::worktree_prefix()) {
+            style::print_colored(" (not managed by vibe)", style::theme().dim);
+        }
+        if let Some(created) = created {
+            style::print_colored(
+                &format!(" created {} ago", util::format_relative_age(*created)),
+                style::theme().dim,
+            );
+        }
+        println!();
 
         // Build status details
         if status.is_orphaned {
             print!("  ");
-            style::println_colored("Orphaned - directory missing", style::indicators::DANGER);
+            style::println_colored("Orphaned - directory missing", style::theme().danger);
         } else {
+            if let Some(op) = &status.in_progress_op {
+                print!("  ");
+                style::println_colored(&format!("{} in progress", op), style::theme().uncommitted);
+            }
+
             // Show AI summary first if available
             if let Some(summary) = summary {
                 print!("  ");
-                style::println_colored(summary, style::indicators::DIM);
+                style::println_colored(summary, style::theme().dim);
             }
 
-            // Build compact status line (untracked files count as added)
-            let total_added = status.lines_added + status.untracked_files;
-            let has_changes = total_added > 0 || status.lines_deleted > 0;
-            let has_unpushed = status.commits_ahead > 0;
-
-            print!("  ");
-            if !has_changes && !has_unpushed {
-                style::println_colored("Clean", style::indicators::DIM);
-            } else {
-                let mut parts = Vec::new();
-                if total_added > 0 {
-                    parts.push(format!("+{}", total_added));
-                }
-                if status.lines_deleted > 0 {
-                    parts.push(format!("-{}", status.lines_deleted));
-                }
-                if has_unpushed {
-                    parts.push(format!("↑{}", status.commits_ahead));
-                }
-                style::println_colored(&parts.join(" "), style::indicators::DIM);
+            // Compact status line
+            let mut changes = status.change_summary();
+            if changes.is_empty() {
+                changes.push_str("Clean");
             }
+            if let Some(usage) = usage {
+                changes.push_str(&format!(", {} on disk", format_usage(usage)));
+            }
+            print!("  ");
+            style::println_colored(&changes, style::theme().dim);
         }
 
         println!();
@@ -108,17 +294,319 @@ pub async fn run() -> Result<()> {
     Ok(())
 }
 
+/// Format a disk usage, marking sizes that are only a lower bound.
+fn format_usage(usage: &du::Usage) -> String {
+    let prefix = if usage.capped {
+        style::theme().at_least
+    } else {
+        ""
+    };
+    format!("{}{}", prefix, util::format_bytes(usage.bytes))
+}
+
+/// Fetch statuses and summaries in parallel, in the requested order.
+async fn load_with_summaries(
+    worktrees: Vec<git::Worktree>,
+    options: &StatusOptions,
+) -> Result<Vec<(Entry, Option<String>)>> {
+    let sort = options.sort;
+    let size = options.size_limits();
+    let refresh = options.refresh;
+    let incremental = options.incremental;
+    let mut handles = Vec::new();
+    for wt in worktrees {
+        handles.push(tokio::task::spawn_blocking(move || {
+            let entry = Entry::load(&wt, sort, size);
+            let summary = if !summary::needed(&entry.status) {
+                None
+            } else if incremental {
+                summary::incremental(&wt.path).ok()
+            } else {
+                summary::get(&wt.path, refresh).ok()
+            };
+            // The next incremental summary starts from what was shown now
+            if incremental && !entry.status.is_orphaned {
+                let _ = summary::mark(&wt.path);
+            }
+            (entry, summary)
+        }));
+    }
+
+    let mut results = Vec::with_capacity(handles.len());
+    for handle in handles {
+        results.push(handle.await?);
+    }
+    sort_entries(&mut results, sort, options.reverse);
+    Ok(results)
+}
+
+/// A worktree's base branch, with the commits it is ahead of and behind it.
+///
+/// The counts are None for orphaned worktrees and the base branch itself.
+fn divergence(
+    wt: &git::Worktree,
+    status: &git::WorktreeStatus,
+) -> (String, Option<(usize, usize)>) {
+    let base = meta::read_meta(&wt.path)
+        .and_then(|m| m.base_branch)
+        .unwrap_or_else(|| git::main_branch().to_string());
+    let ahead_behind = if status.is_orphaned || wt.branch == base {
+        None
+    } else {
+        git::get_ahead_behind(&git::resolve_base_ref(&base), &wt.branch)
+    };
+    (base, ahead_behind)
+}
+
+/// Print the worktrees that are behind their base branch, furthest first.
+///
+/// Fetches once beforehand (unless offline), so the counts reflect the
+/// remote base branches.
+async fn print_behind(worktrees: Vec<git::Worktree>) -> Result<()> {
+    if !git::is_offline() {
+        print!("Fetching...");
+        io::stdout().flush()?;
+        let fetched = git::fetch_origin();
+        style::clear_line();
+        if let Err(e) = fetched {
+            style::print_colored("Warning:", style::theme().uncommitted);
+            println!(" {}", e);
+        }
+    }
+
+    let mut handles = Vec::new();
+    for wt in worktrees {
+        handles.push(tokio::task::spawn_blocking(move || {
+            let status = git::get_worktree_status(&wt.path).unwrap_or_default();
+            let (base, ahead_behind) = divergence(&wt, &status);
+            (wt.branch, base, ahead_behind.unwrap_or_default())
+        }));
+    }
+
+    let mut behind = Vec::new();
+    for handle in handles {
+        let (branch, base, (ahead, commits_behind)) = handle.await?;
+        if commits_behind > 0 {
+            behind.push((branch, base, (ahead, commits_behind)));
+        }
+    }
+    behind.sort_by_key(|(_, _, (_, behind))| Reverse(*behind));
+
+    style::print_offline_notice();
+    if behind.is_empty() {
+        println!("No worktrees are behind their base branch");
+        return Ok(());
+    }
+
+    println!("Behind their base branch:\n");
+    for (branch, base, (ahead, commits_behind)) in &behind {
+        print!("  {} ", branch);
+        style::print_colored(
+            &format!("{}{}", style::theme().behind, commits_behind),
+            style::theme().uncommitted,
+        );
+        let mut details = format!(" behind {}", base);
+        if *ahead > 0 {
+            details.push_str(&format!(", {} ahead", ahead));
+        }
+        style::println_colored(&details, style::theme().dim);
+    }
+    Ok(())
+}
+
+/// Print one line per worktree in the `--porcelain` format.
+///
+/// The fields are tab-separated: branch, state, lines added (including
+/// untracked files), lines deleted, and commits ahead of and behind the base
+/// branch. This format must stay stable, so only ever append new fields.
+async fn print_porcelain(worktrees: Vec<git::Worktree>, options: &StatusOptions) -> Result<()> {
+    let sort = options.sort;
+    let mut handles = Vec::new();
+    for wt in worktrees {
+        handles.push(tokio::task::spawn_blocking(move || {
+            let entry = Entry::load(&wt, sort, None);
+            let (_, ahead_behind) = divergence(&wt, &entry.status);
+            (entry, ahead_behind)
+        }));
+    }
+
+    let mut results = Vec::with_capacity(handles.len());
+    for handle in handles {
+        results.push(handle.await?);
+    }
+    sort_entries(&mut results, sort, options.reverse);
+
+    let mut stdout = io::stdout().lock();
+    for (entry, ahead_behind) in &results {
+        writeln!(
+            stdout,
+            "{}",
+            porcelain_line(&entry.branch, &entry.status, *ahead_behind)
+        )?;
+    }
+    Ok(())
+}
+
+/// Format a worktree's `--porcelain` line.
+fn porcelain_line(
+    branch: &str,
+    status: &git::WorktreeStatus,
+    ahead_behind: Option<(usize, usize)>,
+) -> String {
+    let (ahead, behind) = ahead_behind.unwrap_or_default();
+    format!(
+        "{}\t{}\t{}\t{}\t{}\t{}",
+        branch,
+        state_name(status),
+        status.lines_added + status.untracked_files,
+        status.lines_deleted,
+        ahead,
+        behind
+    )
+}
+
+/// Name of a worktree's state, as printed by `--porcelain` and the legend.
+fn state_name(status: &git::WorktreeStatus) -> &'static str {
+    if status.is_orphaned {
+        "orphaned"
+    } else if status.in_progress_op.is_some() {
+        "conflicted"
+    } else {
+        match (status.has_uncommitted, status.has_unpushed) {
+            (false, false) => "clean",
+            (true, false) => "uncommitted",
+            (false, true) => "unpushed",
+            (true, true) => "both",
+        }
+    }
+}
+
 /// Print the color legend.
+///
+/// Icons come from `style::status_indicator` so the legend always matches
+/// what the listing shows.
 fn print_legend() {
-    style::print_colored("Legend: ", style::indicators::DIM);
-    style::print_colored("●", style::indicators::CLEAN);
-    style::print_colored(" clean  ", style::indicators::DIM);
-    style::print_colored("●", style::indicators::UNCOMMITTED);
-    style::print_colored(" uncommitted  ", style::indicators::DIM);
-    style::print_colored("●", style::indicators::UNPUSHED);
-    style::print_colored(" unpushed  ", style::indicators::DIM);
-    style::print_colored("●", style::indicators::DANGER);
-    style::print_colored(" both  ", style::indicators::DIM);
-    style::print_colored("✗", style::indicators::DANGER);
-    style::println_colored(" orphaned", style::indicators::DIM);
+    let status = |has_uncommitted, has_unpushed, is_orphaned| git::WorktreeStatus {
+        has_uncommitted,
+        has_unpushed,
+        is_orphaned,
+        ..Default::default()
+    };
+    let entries = [
+        status(false, false, false),
+        status(true, false, false),
+        status(false, true, false),
+        status(true, true, false),
+        status(false, false, true),
+        git::WorktreeStatus {
+            in_progress_op: Some("rebase".to_string()),
+            ..Default::default()
+        },
+    ];
+
+    let dim = style::theme().dim;
+    style::print_colored("Legend:", dim);
+    for (i, status) in entries.iter().enumerate() {
+        let (icon, color) = style::status_indicator(status);
+        style::print_colored(if i == 0 { " " } else { "  " }, dim);
+        style::print_colored(icon, color);
+        style::print_colored(&format!(" {}", state_name(status)), dim);
+    }
+    println!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_porcelain_line() {
+        let status = git::WorktreeStatus {
+            has_uncommitted: true,
+            has_unpushed: true,
+            commits_ahead: 2,
+            lines_added: 5,
+            lines_deleted: 4,
+            untracked_files: 1,
+            ..Default::default()
+        };
+        assert_eq!(
+            porcelain_line("claude/x", &status, Some((3, 1))),
+            "claude/x\tboth\t6\t4\t3\t1"
+        );
+
+        let orphaned = git::WorktreeStatus {
+            is_orphaned: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            porcelain_line("claude/y", &orphaned, None),
+            "claude/y\torphaned\t0\t0\t0\t0"
+        );
+    }
+
+    fn entry(branch: &str, lines_added: usize, activity: u64) -> (Entry, ()) {
+        let status = git::WorktreeStatus {
+            lines_added,
+            ..Default::default()
+        };
+        let activity = Some(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(activity));
+        let branch = branch.to_string();
+        (
+            Entry {
+                branch,
+                path: PathBuf::new(),
+                status,
+                activity,
+                created: None,
+                usage: None,
+            },
+            (),
+        )
+    }
+
+    #[test]
+    fn test_json_report_shape() {
+        let (entry, _) = entry("claude/a", 3, 0);
+        let json =
+            serde_json::to_value(WorktreeStatusReport::new(&entry, Some("Fix login"))).unwrap();
+        assert_eq!(json["branch"], "claude/a");
+        assert_eq!(json["lines_added"], 3);
+        assert_eq!(json["summary"], "Fix login");
+        assert!(json["in_progress_op"].is_null());
+        assert!(json["created_at"].is_null());
+        assert!(json.get("size_bytes").is_none());
+    }
+
+    fn sorted(sort: Option<SortKey>, reverse: bool) -> Vec<String> {
+        let mut entries = vec![
+            entry("claude/b", 1, 30),
+            entry("claude/c", 9, 10),
+            entry("claude/a", 5, 20),
+        ];
+        sort_entries(&mut entries, sort, reverse);
+        entries.into_iter().map(|(e, _)| e.branch).collect()
+    }
+
+    #[test]
+    fn test_sort_entries() {
+        assert_eq!(sorted(None, false), ["claude/b", "claude/c", "claude/a"]);
+        assert_eq!(sorted(None, true), ["claude/a", "claude/c", "claude/b"]);
+        assert_eq!(
+            sorted(Some(SortKey::Name), false),
+            ["claude/a", "claude/b", "claude/c"]
+        );
+        assert_eq!(
+            sorted(Some(SortKey::Activity), false),
+            ["claude/b", "claude/a", "claude/c"]
+        );
+        assert_eq!(
+            sorted(Some(SortKey::Changes), false),
+            ["claude/c", "claude/a", "claude/b"]
+        );
+        assert_eq!(
+            sorted(Some(SortKey::Changes), true),
+            ["claude/b", "claude/a", "claude/c"]
+        );
+    }
 }
@@ -0,0 +1,54 @@
+//! Show live resource usage of running session containers.
+
+use anyhow::{Context, Result, bail};
+use std::io::{self, IsTerminal};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use crate::{docker, tui};
+
+/// Pause between two samples; `docker stats` itself takes about a second
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Run the `top` command: show CPU and memory of each session until `q`.
+///
+/// Without a terminal, prints a single sample instead.
+pub async fn run() -> Result<()> {
+    let stats = sample().await?;
+    if stats.is_empty() {
+        println!("No running sessions");
+        return Ok(());
+    }
+
+    if !io::stdout().is_terminal() {
+        for row in tui::stats_rows(&stats) {
+            println!("{}", row.join("\t"));
+        }
+        return Ok(());
+    }
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    let sampler = tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(REFRESH_INTERVAL).await;
+            // A failed sample keeps the last one on screen
+            if let Ok(stats) = sample().await
+                && tx.send(stats).is_err()
+            {
+                break;
+            }
+        }
+    });
+
+    let result = tui::run_stats_table(stats, rx).await;
+    sampler.abort();
+    result.context("Failed to show session stats")
+}
+
+/// Take one sample of all session containers.
+async fn sample() -> Result<Vec<docker::ContainerStats>> {
+    match tokio::task::spawn_blocking(docker::session_stats).await {
+        Ok(stats) => stats,
+        Err(e) => bail!("Failed to query docker: {}", e),
+    }
+}
@@ -0,0 +1,58 @@
+//! Undo the most recent cleanup.
+
+use anyhow::{Result, bail};
+use std::io::{self, Write};
+
+use crate::{git, lock, style, trash};
+
+/// Run the `undo` command: recreate the worktrees removed by the last cleanup.
+///
+/// Worktrees that can't be restored stay in the record so undo can be retried.
+pub fn run() -> Result<()> {
+    let repo_info = git::require_bare_repo()?;
+    let _lock = lock::acquire(&repo_info.bare_path)?;
+
+    let Some(mut record) = trash::load()? else {
+        println!("Nothing to undo");
+        return Ok(());
+    };
+
+    if record.workspace_root != repo_info.workspace_root {
+        bail!(
+            "The last cleanup was in {}, run 'vibe undo' there",
+            record.workspace_root.display()
+        );
+    }
+
+    let mut restored = 0;
+    let mut remaining = Vec::new();
+
+    for removed in record.removed {
+        print!("Restoring {}... ", removed.branch);
+        io::stdout().flush()?;
+        match trash::restore(&repo_info.workspace_root, &removed) {
+            Ok(_) => {
+                style::println_colored("done", style::theme().clean);
+                restored += 1;
+            }
+            Err(e) => {
+                style::print_colored("failed: ", style::theme().danger);
+                println!("{}", e);
+                remaining.push(removed);
+            }
+        }
+    }
+
+    println!();
+    println!("Restored {} worktree(s)", restored);
+
+    let failed = remaining.len();
+    record.removed = remaining;
+    trash::save(&record)?;
+
+    if failed > 0 {
+        bail!("{} worktree(s) could not be restored", failed);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,699 @@
+//! User configuration in `~/.config/vibe/config.toml`.
+//!
+//! Settings are read into `Config` with serde. `vibe config set` edits the
+//! file as a `toml_edit` document instead, so it changes only the value it
+//! sets, leaving comments and layout alone.
+
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use toml_edit::{DocumentMut, Item, TableLike, Value};
+
+use crate::{WORKTREE_PREFIX, git, paths, style};
+
+/// Type of a config value
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    String,
+    Integer,
+    Bool,
+    StringList,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Kind::String => "a string",
+            Kind::Integer => "an integer",
+            Kind::Bool => "a boolean",
+            Kind::StringList => "a list of strings",
+        })
+    }
+}
+
+/// A setting vibe knows about
+pub struct Key {
+    /// Full name, with a `table.` prefix for keys inside a table
+    pub name: &'static str,
+    pub kind: Kind,
+    pub description: &'static str,
+}
+
+/// All supported settings
+pub const KEYS: &[Key] = &[
+    Key {
+        name: "default_image",
+        kind: Kind::String,
+        description: "Image used when no Dockerfile.vibes is found",
+    },
+    Key {
+        name: "worktree_prefix",
+        kind: Kind::String,
+        description: "Directory and branch prefix for session worktrees",
+    },
+    Key {
+        name: "setup_prompt",
+        kind: Kind::String,
+        description: "Prompt used by 'vibe setup' to create Dockerfile.vibes",
+    },
+    Key {
+        name: "runtime",
+        kind: Kind::String,
+        description: "Container runtime command, e.g. podman (defaults to docker, then podman)",
+    },
+    Key {
+        name: "summaries",
+        kind: Kind::Bool,
+        description: "Summarize uncommitted changes with Claude in status and pickers",
+    },
+    Key {
+        name: "probe_image",
+        kind: Kind::Bool,
+        description: "Check that claude runs in an image before starting a session",
+    },
+    Key {
+        name: "exit_reminder",
+        kind: Kind::Bool,
+        description: "Remind about uncommitted or unpushed work when a session exits",
+    },
+    Key {
+        name: "pull_retries",
+        kind: Kind::Integer,
+        description: "How often a failed pull of a Dockerfile's base image is retried",
+    },
+    Key {
+        name: "forward_env",
+        kind: Kind::StringList,
+        description: "Host variables passed into containers when set, e.g. \"GITHUB_TOKEN\"",
+    },
+    Key {
+        name: "claude.allow",
+        kind: Kind::StringList,
+        description: "Tools Claude may use in containers (defaults to a broad set)",
+    },
+    Key {
+        name: "claude.bash_allow",
+        kind: Kind::StringList,
+        description: "Bash commands Claude may run, e.g. \"cargo *\" (defaults to any)",
+    },
+    Key {
+        name: "spinner.style",
+        kind: Kind::String,
+        description: "Spinner animation: braille, ascii or dots (defaults to braille, or ascii without UTF-8)",
+    },
+    Key {
+        name: "spinner.interval_ms",
+        kind: Kind::Integer,
+        description: "Time each spinner frame is shown, in milliseconds",
+    },
+    Key {
+        name: "theme.colors",
+        kind: Kind::String,
+        description: "Status colors, e.g. \"clean=green:danger=dark_red\" (VIBE_COLORS overrides it)",
+    },
+    Key {
+        name: "theme.icons",
+        kind: Kind::String,
+        description: "Glyph overrides, e.g. \"ok=v:fail=x:status=o\"",
+    },
+];
+
+/// Look up a supported setting by name.
+pub fn find_key(name: &str) -> Result<&'static Key> {
+    KEYS.iter().find(|key| key.name == name).with_context(|| {
+        let width = KEYS.iter().map(|key| key.name.len()).max().unwrap_or(0);
+        let known: Vec<_> = KEYS
+            .iter()
+            .map(|key| format!("  {:width$}  {}", key.name, key.description))
+            .collect();
+        format!(
+            "Unknown config key '{}', known keys:\n{}",
+            name,
+            known.join("\n")
+        )
+    })
+}
+
+impl Kind {
+    /// Check whether a value has this type.
+    fn matches(self, value: &Value) -> bool {
+        match (self, value) {
+            (Kind::String, Value::String(_))
+            | (Kind::Integer, Value::Integer(_))
+            | (Kind::Bool, Value::Boolean(_)) => true,
+            (Kind::StringList, Value::Array(items)) => items.iter().all(Value::is_str),
+            _ => false,
+        }
+    }
+}
+
+/// Parse a value given on the command line for a setting.
+///
+/// Strings are taken literally; lists may be comma-separated or written
+/// as a TOML array.
+pub fn parse_arg(key: &Key, text: &str) -> Result<Value> {
+    let invalid = || format!("'{}' must be {}", key.name, key.kind);
+    let value = match key.kind {
+        Kind::String => Value::from(text),
+        Kind::Integer => Value::from(text.trim().parse::<i64>().with_context(invalid)?),
+        Kind::Bool => match text.trim() {
+            "true" | "yes" | "on" | "1" => Value::from(true),
+            "false" | "no" | "off" | "0" => Value::from(false),
+            _ => bail!("{} (true or false)", invalid()),
+        },
+        Kind::StringList if text.trim_start().starts_with('[') => {
+            text.trim().parse().with_context(invalid)?
+        }
+        Kind::StringList => Value::Array(
+            text.split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .collect(),
+        ),
+    };
+
+    if !key.kind.matches(&value) {
+        bail!(invalid());
+    }
+    Ok(value)
+}
+
+/// The item of a setting in a config file, if it is set.
+pub fn lookup<'a>(document: &'a DocumentMut, name: &str) -> Option<&'a Item> {
+    name.split('.')
+        .try_fold(document.as_item(), |item, part| item.get(part))
+}
+
+/// Set a setting in a config file, adding its table if needed.
+///
+/// A replaced value keeps its place and trailing comment; everything else
+/// in the file is left as it was.
+pub fn set(document: &mut DocumentMut, name: &str, mut value: Value) -> Result<()> {
+    let (table, key) = name.rsplit_once('.').unwrap_or(("", name));
+    let table: &mut dyn TableLike = if table.is_empty() {
+        document.as_table_mut()
+    } else {
+        document
+            .entry(table)
+            .or_insert_with(toml_edit::table)
+            .as_table_like_mut()
+            .with_context(|| format!("'{}' is not a table", table))?
+    };
+
+    match table.get_mut(key) {
+        Some(Item::Value(old)) => {
+            *value.decor_mut() = old.decor().clone();
+            *old = value;
+        }
+        _ => {
+            table.insert(key, Item::Value(value));
+        }
+    }
+    Ok(())
+}
+
+/// Settings in a config file that vibe doesn't know about.
+pub fn unknown_keys(document: &DocumentMut) -> Vec<String> {
+    let mut names = Vec::new();
+    collect_keys(document.as_table(), "", &mut names);
+    names.retain(|name| find_key(name).is_err());
+    names
+}
+
+/// Add the full names of all values in `table` and the tables within it.
+fn collect_keys(table: &dyn TableLike, prefix: &str, names: &mut Vec<String>) {
+    for (key, item) in table.iter() {
+        let name = if prefix.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        match item.as_table_like() {
+            Some(inner) => collect_keys(inner, &name, names),
+            None => names.push(name),
+        }
+    }
+}
+
+/// Path of the user config file (`~/.config/vibe/config.toml`).
+pub fn config_path() -> Result<PathBuf> {
+    Ok(paths::config_dir()?.join("config.toml"))
+}
+
+/// Load the user config file; a missing file is an empty config.
+pub fn load_document() -> Result<DocumentMut> {
+    let path = config_path()?;
+    match fs::read_to_string(&path) {
+        Ok(text) => text
+            .parse()
+            .with_context(|| format!("Invalid config {}", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(DocumentMut::new()),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+/// Retries of a failed base image pull unless configured otherwise
+const DEFAULT_PULL_RETRIES: u32 = 2;
+
+/// Prompt `vibe setup` uses unless `setup_prompt` is set
+const DEFAULT_SETUP_PROMPT: &str = "\
+Analyze this project and create a Dockerfile.vibes file that includes all necessary \
+dependencies and tools for development. The Dockerfile should be based on sirsedev/claude-vibe \
+as the base image (which already includes Claude Code). Add any project-specific dependencies \
+needed to build and run this project. Please examine the project structure, dependencies, \
+and build system to determine the requirements.";
+
+/// Effective settings: the config file merged over the built-in defaults
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    pub default_image: String,
+    /// Always ends with `/`
+    pub worktree_prefix: String,
+    pub setup_prompt: String,
+    /// Container runtime command, None to autodetect
+    pub runtime: Option<String>,
+    /// Summarize uncommitted changes with Claude
+    pub summaries: bool,
+    /// Run `claude --version` in an image before the first session with it
+    pub probe_image: bool,
+    /// Point out unsaved work after an interactive session
+    pub exit_reminder: bool,
+    /// Retries of a failed base image pull before building
+    pub pull_retries: u32,
+    /// None to use the default tool allow-list
+    pub claude_allow: Option<Vec<String>>,
+    /// None to allow any Bash command
+    pub claude_bash_allow: Option<Vec<String>>,
+    /// Host variables passed into containers if they are set
+    pub forward_env: Vec<String>,
+    /// None to use the theme's spinner
+    pub spinner: Option<style::Spinner>,
+    pub spinner_interval_ms: u64,
+    /// Color overrides in the `VIBE_COLORS` format
+    pub theme_colors: Option<String>,
+    /// Glyph overrides, see `Theme::with_icons`
+    pub theme_icons: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            default_image: git::DEFAULT_IMAGE.to_string(),
+            worktree_prefix: WORKTREE_PREFIX.to_string(),
+            setup_prompt: DEFAULT_SETUP_PROMPT.to_string(),
+            runtime: None,
+            summaries: true,
+            probe_image: false,
+            exit_reminder: true,
+            pull_retries: DEFAULT_PULL_RETRIES,
+            claude_allow: None,
+            claude_bash_allow: None,
+            forward_env: Vec::new(),
+            spinner: None,
+            spinner_interval_ms: style::DEFAULT_SPINNER_INTERVAL_MS,
+            theme_colors: None,
+            theme_icons: None,
+        }
+    }
+}
+
+/// Settings as written in the config file, None where not set
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ConfigFile {
+    default_image: Option<String>,
+    worktree_prefix: Option<String>,
+    setup_prompt: Option<String>,
+    runtime: Option<String>,
+    summaries: Option<bool>,
+    probe_image: Option<bool>,
+    exit_reminder: Option<bool>,
+    pull_retries: Option<u32>,
+    forward_env: Option<Vec<String>>,
+    claude: ClaudeTable,
+    spinner: SpinnerTable,
+    theme: ThemeTable,
+}
+
+/// The `[claude]` table of the config file
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ClaudeTable {
+    allow: Option<Vec<String>>,
+    bash_allow: Option<Vec<String>>,
+}
+
+/// The `[spinner]` table of the config file
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct SpinnerTable {
+    style: Option<String>,
+    interval_ms: Option<u64>,
+}
+
+/// The `[theme]` table of the config file
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ThemeTable {
+    colors: Option<String>,
+    icons: Option<String>,
+}
+
+impl Config {
+    /// Apply the settings of a config file over the defaults.
+    pub fn from_document(document: &DocumentMut) -> Result<Config> {
+        let file: ConfigFile = toml::from_str(&document.to_string())?;
+        let mut config = Config::default();
+
+        if let Some(image) = file.default_image {
+            config.default_image = image;
+        }
+        if let Some(prefix) = file.worktree_prefix {
+            let prefix = prefix.trim_matches('/');
+            if prefix.is_empty() {
+                bail!("'worktree_prefix' must not be empty");
+            }
+            config.worktree_prefix = format!("{}/", prefix);
+        }
+        if let Some(prompt) = file.setup_prompt {
+            config.setup_prompt = prompt;
+        }
+        config.runtime = file.runtime.filter(|r| !r.trim().is_empty());
+        if let Some(summaries) = file.summaries {
+            config.summaries = summaries;
+        }
+        if let Some(probe) = file.probe_image {
+            config.probe_image = probe;
+        }
+        if let Some(remind) = file.exit_reminder {
+            config.exit_reminder = remind;
+        }
+        if let Some(retries) = file.pull_retries {
+            config.pull_retries = retries;
+        }
+        config.claude_allow = file.claude.allow;
+        config.claude_bash_allow = file.claude.bash_allow;
+        config.forward_env = file.forward_env.unwrap_or_default();
+        if let Some(name) = file.spinner.style {
+            let Some(spinner) = style::Spinner::from_name(name.trim()) else {
+                let names: Vec<_> = style::Spinner::ALL.iter().map(|s| s.name()).collect();
+                bail!("'spinner.style' must be one of {}", names.join(", "));
+            };
+            config.spinner = Some(spinner);
+        }
+        if let Some(interval) = file.spinner.interval_ms {
+            if interval == 0 {
+                bail!("'spinner.interval_ms' must be positive");
+            }
+            config.spinner_interval_ms = interval;
+        }
+        if let Some(colors) = file.theme.colors {
+            style::Theme::unicode()
+                .with_colors(&colors)
+                .context("Invalid 'theme.colors'")?;
+            config.theme_colors = Some(colors);
+        }
+        if let Some(icons) = file.theme.icons {
+            style::Theme::check_icons(&icons).context("Invalid 'theme.icons'")?;
+            config.theme_icons = Some(icons);
+        }
+
+        Ok(config)
+    }
+
+    /// Every setting with its effective value, in `KEYS` order.
+    pub fn values(&self) -> Vec<(&'static str, Option<Value>)> {
+        let string = |s: &str| Some(Value::from(s));
+        let list = |items: &Option<Vec<String>>| {
+            items
+                .as_ref()
+                .map(|items| Value::Array(items.iter().map(String::as_str).collect()))
+        };
+        vec![
+            ("default_image", string(&self.default_image)),
+            ("worktree_prefix", string(&self.worktree_prefix)),
+            ("setup_prompt", string(&self.setup_prompt)),
+            ("runtime", self.runtime.as_deref().and_then(string)),
+            ("summaries", Some(Value::from(self.summaries))),
+            ("probe_image", Some(Value::from(self.probe_image))),
+            ("exit_reminder", Some(Value::from(self.exit_reminder))),
+            (
+                "pull_retries",
+                Some(Value::from(i64::from(self.pull_retries))),
+            ),
+            ("forward_env", list(&Some(self.forward_env.clone()))),
+            ("claude.allow", list(&self.claude_allow)),
+            ("claude.bash_allow", list(&self.claude_bash_allow)),
+            (
+                "spinner.style",
+                self.spinner.map(|s| s.name()).and_then(string),
+            ),
+            (
+                "spinner.interval_ms",
+                Some(Value::from(self.spinner_interval_ms as i64)),
+            ),
+            (
+                "theme.colors",
+                self.theme_colors.as_deref().and_then(string),
+            ),
+            ("theme.icons", self.theme_icons.as_deref().and_then(string)),
+        ]
+    }
+}
+
+/// Settings loaded at startup
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Load the user config file. Call once at startup.
+pub fn load() -> Result<()> {
+    let path = config_path()?;
+    let config = Config::from_document(&load_document()?)
+        .with_context(|| format!("Invalid config {}", path.display()))?;
+    let _ = CONFIG.set(config);
+    Ok(())
+}
+
+/// The effective settings, or the defaults if `load` wasn't called.
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
+
+/// Directory and branch prefix of session worktrees, e.g. `claude/`.
+pub fn worktree_prefix() -> &'static str {
+    &get().worktree_prefix
+}
+
+/// Image used when no Dockerfile.vibes is found.
+pub fn default_image() -> &'static str {
+    &get().default_image
+}
+
+/// Per-workspace settings file at the workspace root
+pub const REPO_CONFIG_FILE: &str = ".vibes.toml";
+
+/// Settings of a workspace's `.vibes.toml`
+#[derive(Deserialize)]
+struct RepoConfig {
+    base_image: Option<String>,
+}
+
+/// The `base_image` set in a workspace's `.vibes.toml`, if any.
+///
+/// It replaces the default image for the workspace's sessions.
+pub fn repo_base_image(workspace_root: &Path) -> Result<Option<String>> {
+    let path = workspace_root.join(REPO_CONFIG_FILE);
+    let Ok(text) = fs::read_to_string(&path) else {
+        return Ok(None);
+    };
+    let config: RepoConfig =
+        toml::from_str(&text).with_context(|| format!("Invalid {}", path.display()))?;
+
+    Ok(config
+        .base_image
+        .map(|image| image.trim().to_string())
+        .filter(|image| !image.is_empty()))
+}
+
+/// Write the user config file, creating its directory if needed.
+pub fn save_document(document: &DocumentMut) -> Result<()> {
+    let path = config_path()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).context("Failed to create config directory")?;
+    }
+    fs::write(&path, document.to_string())
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(text: &str) -> DocumentMut {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        // Known keys are type-checked
+        assert!(Config::from_document(&document("default_image = 5")).is_err());
+        assert!(Config::from_document(&document("pull_retries = -1")).is_err());
+        // Theme specs are checked when the config is loaded
+        let theme = |text: &str| Config::from_document(&document(&format!("[theme]\n{}", text)));
+        assert!(theme("colors = \"clean=greenish\"").is_err());
+        assert!(theme("icons = \"okay=v\"").is_err());
+        let config = theme("icons = \"ok=v\"\ncolors = \"dim=grey\"").unwrap();
+        assert_eq!(config.theme_icons.as_deref(), Some("ok=v"));
+        assert_eq!(config.theme_colors.as_deref(), Some("dim=grey"));
+    }
+
+    #[test]
+    fn test_values_cover_keys() {
+        let names: Vec<_> = Config::default()
+            .values()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        let keys: Vec<_> = KEYS.iter().map(|key| key.name).collect();
+        assert_eq!(names, keys);
+    }
+
+    #[test]
+    fn test_set_preserves_other_lines() {
+        let mut doc = document(
+            "# My config\n\
+             default_image = \"old\" # pinned\n\
+             \n\
+             [claude]\n\
+             model = \"x\"\n",
+        );
+
+        set(&mut doc, "default_image", Value::from("new")).unwrap();
+        set(&mut doc, "worktree_prefix", Value::from("ai/")).unwrap();
+        set(&mut doc, "claude.effort", Value::from(2)).unwrap();
+        set(&mut doc, "docker.pull", Value::from(true)).unwrap();
+
+        assert_eq!(
+            doc.to_string(),
+            "# My config\n\
+             default_image = \"new\" # pinned\n\
+             worktree_prefix = \"ai/\"\n\
+             \n\
+             [claude]\n\
+             model = \"x\"\n\
+             effort = 2\n\
+             \n\
+             [docker]\n\
+             pull = true\n"
+        );
+        assert_eq!(
+            lookup(&doc, "claude.effort").and_then(Item::as_integer),
+            Some(2)
+        );
+        assert!(lookup(&doc, "claude.allow").is_none());
+    }
+
+    #[test]
+    fn test_unknown_keys() {
+        let doc = document(
+            "default_image = \"me/image\"\n\
+             colour = true\n\
+             [claude]\n\
+             allow = [\"Read\"]\n\
+             model = \"x\"\n",
+        );
+        assert_eq!(unknown_keys(&doc), ["colour", "claude.model"]);
+    }
+
+    #[test]
+    fn test_repo_base_image() {
+        let dir = std::env::temp_dir().join(format!("vibe-repo-config-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        assert_eq!(repo_base_image(&dir).unwrap(), None);
+
+        fs::write(
+            dir.join(REPO_CONFIG_FILE),
+            "base_image = \"acme/python:3.12\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            repo_base_image(&dir).unwrap().as_deref(),
+            Some("acme/python:3.12")
+        );
+
+        fs::write(dir.join(REPO_CONFIG_FILE), "base_image = 3\n").unwrap();
+        assert!(repo_base_image(&dir).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_config_from_document() {
+        let defaults = Config::from_document(&DocumentMut::new()).unwrap();
+        assert_eq!(defaults, Config::default());
+        assert_eq!(defaults.worktree_prefix, "claude/");
+
+        let doc = document(
+            "default_image = \"me/image\"\n\
+             worktree_prefix = \"ai\"\n\
+             probe_image = true\n\
+             forward_env = [\"GITHUB_TOKEN\", \"AWS_PROFILE\"]\n\
+             [claude]\n\
+             bash_allow = [\"cargo *\"]\n",
+        );
+        let config = Config::from_document(&doc).unwrap();
+        assert_eq!(config.default_image, "me/image");
+        assert_eq!(config.worktree_prefix, "ai/");
+        assert_eq!(config.setup_prompt, DEFAULT_SETUP_PROMPT);
+        assert!(config.probe_image);
+        assert_eq!(config.claude_allow, None);
+        assert_eq!(config.claude_bash_allow, Some(vec!["cargo *".to_string()]));
+        assert_eq!(config.forward_env, ["GITHUB_TOKEN", "AWS_PROFILE"]);
+
+        assert!(Config::from_document(&document("worktree_prefix = \"/\"")).is_err());
+
+        let doc = document("[spinner]\nstyle = \"dots\"\ninterval_ms = 120\n");
+        let config = Config::from_document(&doc).unwrap();
+        assert_eq!(config.spinner, Some(style::Spinner::Dots));
+        assert_eq!(config.spinner_interval_ms, 120);
+        for text in ["[spinner]\nstyle = \"moon\"", "[spinner]\ninterval_ms = 0"] {
+            assert!(Config::from_document(&document(text)).is_err());
+        }
+    }
+
+    #[test]
+    fn test_parse_arg() {
+        let key = Key {
+            name: "list",
+            kind: Kind::StringList,
+            description: "",
+        };
+        assert_eq!(
+            parse_arg(&key, "a, b").unwrap().to_string(),
+            "[\"a\", \"b\"]"
+        );
+        assert_eq!(parse_arg(&key, "[\"a\"]").unwrap().to_string(), "[\"a\"]");
+        assert!(parse_arg(&key, "[1]").is_err());
+
+        let key = Key {
+            name: "flag",
+            kind: Kind::Bool,
+            description: "",
+        };
+        assert_eq!(parse_arg(&key, "yes").unwrap().as_bool(), Some(true));
+        assert!(parse_arg(&key, "maybe").is_err());
+
+        let key = Key {
+            name: "name",
+            kind: Kind::String,
+            description: "",
+        };
+        let value = parse_arg(&key, "quote \" and \\ backslash").unwrap();
+        assert_eq!(
+            value.to_string().parse::<Value>().unwrap().as_str(),
+            value.as_str()
+        );
+    }
+}
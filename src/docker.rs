@@ -1,13 +1,33 @@
 //! Docker utility functions for building images and running containers.
-
-use anyhow::{Context, Result, bail};
-use nix::unistd::{Gid, Uid};
-use serde::Deserialize;
-use std::io::{BufRead, BufReader, Write};
+//!
+//! Besides Claude's own settings, containers get the branching context of the
+//! mounted worktree, so project scripts, git hooks and Claude itself can use it:
+//! - `VIBE_SESSION`: branch of the session, e.g. `claude/brave-otter`
+//! - `VIBE_SESSION_NAME`: session name without the prefix, e.g. `brave-otter`
+//! - `VIBE_BRANCH`: checked out branch, the same as `VIBE_SESSION`
+//! - `VIBE_BASE_BRANCH`: branch the session was created from, falling back
+//!   to the main branch
+//!
+//! Variables that can't be determined (e.g. on a detached HEAD) are left unset.
+//!
+//! The host's time zone (`TZ`) and locale (`LANG`, `LC_*`) are passed through
+//! as well, unless turned off with `--no-host-locale`, followed by the host
+//! variables named in the `forward_env` setting that are set, and the
+//! session's own variables (see `RunOptions`).
+
+use anyhow::{Context, Result, anyhow, bail};
+use crossterm::style::Color;
+use globset::{Glob, GlobMatcher};
+use nix::sys::signal::{self, Signal};
+use nix::unistd::{Gid, Pid, Uid};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::{BufRead, BufReader, IsTerminal, Write};
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, Mutex};
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::Duration;
 
 /// Get the current user's UID and GID
@@ -15,19 +35,11 @@ fn get_host_uid_gid() -> (u32, u32) {
     (Uid::current().as_raw(), Gid::current().as_raw())
 }
 
-use crate::git;
+use crate::{config, git, lock, meta, style, util};
 
 /// Maximum number of output lines to display
 const MAX_OUTPUT_LINES: usize = 5;
 
-/// Box drawing characters
-const BOX_VERTICAL: &str = "│";
-const BOX_CORNER_BOTTOM: &str = "╰";
-const BOX_HORIZONTAL: &str = "─";
-
-/// Spinner characters (braille pattern)
-const SPINNER_CHARS: &[char] = &['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
-
 /// Get terminal width, defaulting to 80 if unavailable
 fn get_terminal_width() -> usize {
     terminal_size::terminal_size()
@@ -100,32 +112,84 @@ struct OutputLine {
     is_tool: bool,
 }
 
+/// Headers shown above streamed output while Claude runs and once it's done
+#[derive(Debug, Clone, Copy)]
+pub struct StreamLabels {
+    pub running: &'static str,
+    pub done: &'static str,
+}
+
+impl Default for StreamLabels {
+    fn default() -> Self {
+        Self {
+            running: "Claude is analyzing your project...",
+            done: "Claude analyzed your project",
+        }
+    }
+}
+
 /// State for streaming output display
 struct StreamingDisplay {
+    /// Header shown with the spinner while running
+    header: &'static str,
+    /// Header shown with the checkmark once finished
+    done_header: &'static str,
     lines: Vec<OutputLine>,
     displayed_count: usize,
     spinner_idx: usize,
     header_printed: bool,
     final_result: Option<String>,
     finished: bool,
+    /// Warning shown under the header, e.g. when falling back to raw output
+    notice: Option<String>,
+    /// Whether the notice was drawn in the last redraw
+    notice_printed: bool,
+    /// Append plain lines instead of redrawing in place, when colors are off
+    /// or stdout isn't a terminal
+    plain: bool,
+    /// Whether plain output already ended with the finished header
+    done_printed: bool,
 }
 
 impl StreamingDisplay {
-    fn new() -> Self {
+    fn new(header: &'static str, done_header: &'static str) -> Self {
         Self {
+            header,
+            done_header,
             lines: Vec::new(),
             displayed_count: 0,
             spinner_idx: 0,
             header_printed: false,
             final_result: None,
             finished: false,
+            notice: None,
+            notice_printed: false,
+            plain: !style::color_enabled() || !std::io::stdout().is_terminal(),
+            done_printed: false,
+        }
+    }
+
+    /// Show a warning under the header from now on
+    fn set_notice(&mut self, notice: &str) {
+        self.notice = Some(notice.to_string());
+        self.redraw();
+    }
+
+    /// Print the notice line, if any
+    fn print_notice(&mut self) {
+        if let Some(ref notice) = self.notice {
+            println!(
+                "{}",
+                style::paint(&format!("! {}", notice), Color::DarkYellow)
+            );
+            self.notice_printed = true;
         }
     }
 
     /// Advance spinner and redraw (only if not finished)
     fn tick(&mut self) {
         if !self.finished {
-            self.spinner_idx = (self.spinner_idx + 1) % SPINNER_CHARS.len();
+            self.spinner_idx = (self.spinner_idx + 1) % style::theme().spinner.len();
             self.redraw();
         }
     }
@@ -161,15 +225,48 @@ impl StreamingDisplay {
         }
     }
 
+    /// Print what is new since the last call, for output that can't be
+    /// redrawn in place: the header once, each line as it arrives, and the
+    /// result at the end.
+    fn append_plain(&mut self) {
+        if !self.header_printed {
+            println!("{}", self.header);
+            self.header_printed = true;
+        }
+        if !self.notice_printed {
+            self.print_notice();
+        }
+
+        let box_vertical = style::theme().box_vertical;
+        for line in &self.lines[self.displayed_count..] {
+            println!("{} {}", box_vertical, line.content);
+        }
+        self.displayed_count = self.lines.len();
+
+        if self.finished && !self.done_printed {
+            println!("{} {}", style::theme().ok, self.done_header);
+            if let Some(ref result) = self.final_result {
+                println!("{} {}", box_vertical, result);
+            }
+            self.done_printed = true;
+        }
+        let _ = std::io::stdout().flush();
+    }
+
     /// Redraw the header, visible lines, and closing line
     fn redraw(&mut self) {
+        if self.plain {
+            return self.append_plain();
+        }
+
         let width = get_terminal_width();
         // Reserve space for "│ " prefix (2 chars) + some margin
         let content_width = width.saturating_sub(4);
 
         // Calculate how many lines to move up (header + output lines + closing line)
         let lines_to_clear = if self.header_printed {
-            1 + self.displayed_count + 1 // header + output lines + closing line
+            // header + notice + output lines + closing line
+            1 + self.notice_printed as usize + self.displayed_count + 1
         } else {
             0
         };
@@ -181,16 +278,24 @@ impl StreamingDisplay {
 
         if self.finished {
             // Finished state: checkmark + collapsed view
-            println!("\x1b[32m✓ Claude analyzed your project\x1b[0m");
+            println!(
+                "{}",
+                style::paint(
+                    &format!("{} {}", style::theme().ok, self.done_header),
+                    style::theme().clean
+                )
+            );
             self.header_printed = true;
+            self.print_notice();
 
             // Show final result if available
             if let Some(ref result) = self.final_result {
                 // Truncate result to single line if needed
                 let display_result = Self::truncate_to_width(result, content_width);
                 println!(
-                    "\x1b[90m{}\x1b[0m \x1b[36m{}\x1b[0m",
-                    BOX_VERTICAL, display_result
+                    "{} {}",
+                    style::paint(style::theme().box_vertical, Color::DarkGrey),
+                    style::paint(&display_result, Color::DarkCyan)
                 );
                 self.displayed_count = 1;
             } else {
@@ -198,17 +303,29 @@ impl StreamingDisplay {
             }
 
             // Print closing line
-            let padding: String = BOX_HORIZONTAL.repeat(width.saturating_sub(1));
-            println!("\x1b[90m{}{}\x1b[0m", BOX_CORNER_BOTTOM, padding);
+            let padding: String = style::theme()
+                .box_horizontal
+                .repeat(width.saturating_sub(1));
+            println!(
+                "{}",
+                style::paint(
+                    &format!("{}{}", style::theme().box_corner, padding),
+                    Color::DarkGrey
+                )
+            );
         } else {
             // Active state: spinner + streaming lines
-            let spinner_char = SPINNER_CHARS[self.spinner_idx];
+            let spinner_char = style::theme().spinner[self.spinner_idx];
 
             println!(
-                "\x1b[36m{} Claude is analyzing your project...\x1b[0m",
-                spinner_char
+                "{}",
+                style::paint(
+                    &format!("{} {}", spinner_char, self.header),
+                    Color::DarkCyan
+                )
             );
             self.header_printed = true;
+            self.print_notice();
 
             // Print visible output lines
             let total = self.lines.len();
@@ -227,13 +344,13 @@ impl StreamingDisplay {
                 };
 
                 let (prefix_color, text_color) = match gradient_intensity {
-                    2 => ("\x1b[38;5;238m", "\x1b[38;5;240m"), // Very dark gray
-                    1 => ("\x1b[38;5;243m", "\x1b[38;5;245m"), // Medium gray
+                    2 => (Color::AnsiValue(238), Color::AnsiValue(240)), // Very dark gray
+                    1 => (Color::AnsiValue(243), Color::AnsiValue(245)), // Medium gray
                     _ => {
                         if line.is_tool {
-                            ("\x1b[90m", "\x1b[33m") // Normal: gray pipe, yellow text for tools
+                            (Color::DarkGrey, Color::DarkYellow) // Normal: gray pipe, yellow text for tools
                         } else {
-                            ("\x1b[90m", "\x1b[36m") // Normal: gray pipe, cyan text for messages
+                            (Color::DarkGrey, Color::DarkCyan) // Normal: gray pipe, cyan text for messages
                         }
                     }
                 };
@@ -242,14 +359,23 @@ impl StreamingDisplay {
                 let truncated_content = Self::truncate_to_width(&line.content, content_width);
 
                 println!(
-                    "{}{}\x1b[0m {}{}\x1b[0m",
-                    prefix_color, BOX_VERTICAL, text_color, truncated_content
+                    "{} {}",
+                    style::paint(style::theme().box_vertical, prefix_color),
+                    style::paint(&truncated_content, text_color)
                 );
             }
 
             // Print closing line
-            let padding: String = BOX_HORIZONTAL.repeat(width.saturating_sub(1));
-            println!("\x1b[90m{}{}\x1b[0m", BOX_CORNER_BOTTOM, padding);
+            let padding: String = style::theme()
+                .box_horizontal
+                .repeat(width.saturating_sub(1));
+            println!(
+                "{}",
+                style::paint(
+                    &format!("{}{}", style::theme().box_corner, padding),
+                    Color::DarkGrey
+                )
+            );
 
             self.displayed_count = visible_lines.len();
         }
@@ -262,18 +388,111 @@ impl StreamingDisplay {
 /// Print the closing box line padded to terminal width
 fn print_closing_line() {
     let width = get_terminal_width();
-    // BOX_CORNER_BOTTOM is 3 bytes but 1 char, BOX_HORIZONTAL is 3 bytes but 1 char
     let padding_count = width.saturating_sub(1); // -1 for the corner
-    let padding: String = BOX_HORIZONTAL.repeat(padding_count);
-    println!("\x1b[90m{}{}\x1b[0m", BOX_CORNER_BOTTOM, padding);
+    let padding: String = style::theme().box_horizontal.repeat(padding_count);
+    println!(
+        "{}",
+        style::paint(
+            &format!("{}{}", style::theme().box_corner, padding),
+            Color::DarkGrey
+        )
+    );
 }
 
 /// Reset terminal colors (used for cleanup on Ctrl+C)
 fn reset_terminal() {
-    print!("\x1b[0m");
+    if style::color_enabled() {
+        print!("\x1b[0m");
+    }
     let _ = std::io::stdout().flush();
 }
 
+/// Pid of a build step Ctrl+C waits for instead of exiting, 0 if none
+static BUILD_PID: AtomicU32 = AtomicU32::new(0);
+
+/// Set once Ctrl+C interrupted a build step
+static BUILD_INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Whether a streaming display needs to be closed on Ctrl+C
+static STREAMING: AtomicBool = AtomicBool::new(false);
+
+/// Install the process-wide Ctrl+C handler, once.
+///
+/// During a build step, Ctrl+C reaches the runtime too (it's in the same
+/// process group), so vibe waits for it to stop cleanly; a second Ctrl+C
+/// kills it. Otherwise vibe closes any streaming display and exits.
+fn install_interrupt_handler() {
+    static INSTALLED: OnceLock<()> = OnceLock::new();
+    INSTALLED.get_or_init(|| {
+        let _ = ctrlc::set_handler(|| {
+            let pid = BUILD_PID.load(Ordering::SeqCst);
+            if pid != 0 {
+                if BUILD_INTERRUPTED.swap(true, Ordering::SeqCst) {
+                    let _ = signal::kill(Pid::from_raw(pid as i32), Signal::SIGKILL);
+                }
+                return;
+            }
+            if STREAMING.load(Ordering::SeqCst) {
+                reset_terminal();
+                println!(); // New line after any partial output
+                print_closing_line();
+            }
+            std::process::exit(130); // Standard exit code for Ctrl+C
+        });
+    });
+}
+
+/// Run a build step (a pull or `docker build`) so that Ctrl+C stops it
+/// without vibe exiting halfway.
+///
+/// Fails if the step was interrupted; layers it completed stay in the
+/// runtime's cache, so re-running resumes from them.
+fn run_build_step(command: &mut Command) -> Result<ExitStatus> {
+    install_interrupt_handler();
+    BUILD_INTERRUPTED.store(false, Ordering::SeqCst);
+
+    let mut child = command
+        .spawn()
+        .with_context(|| format!("Failed to run {}", container_runtime()))?;
+    BUILD_PID.store(child.id(), Ordering::SeqCst);
+    let status = child.wait();
+    BUILD_PID.store(0, Ordering::SeqCst);
+
+    let status = status.with_context(|| format!("Failed to wait for {}", container_runtime()))?;
+    if BUILD_INTERRUPTED.load(Ordering::SeqCst) {
+        bail!(
+            "Build interrupted\n\
+             Run the command again to continue from the layers that were already built"
+        );
+    }
+    Ok(status)
+}
+
+/// Minimum number of stdout lines seen before suspecting a changed event format
+const DRIFT_MIN_LINES: usize = 10;
+
+/// Counts stdout lines that could not be understood as Claude events
+#[derive(Default)]
+struct ParseStats {
+    total: usize,
+    unrecognized: usize,
+}
+
+impl ParseStats {
+    fn record(&mut self, recognized: bool) {
+        self.total += 1;
+        if !recognized {
+            self.unrecognized += 1;
+        }
+    }
+
+    /// Whether most lines are unrecognized, suggesting Claude changed its
+    /// stream-json schema
+    fn drifted(&self) -> bool {
+        self.total >= DRIFT_MIN_LINES && self.unrecognized * 2 > self.total
+    }
+}
+
 /// Process a Claude event: collect lines, handle result, return cost if present
 fn process_event(event: &ClaudeEvent, display: &Mutex<StreamingDisplay>) -> Option<f64> {
     match event {
@@ -360,6 +579,8 @@ pub enum ImageSource {
         dockerfile: PathBuf,
         context: PathBuf,
     },
+    /// Use the `base_image` of the workspace's `.vibes.toml`
+    UseRepoImage { image: String, config: PathBuf },
     /// Use the default pre-built image
     UseDefault,
 }
@@ -369,30 +590,144 @@ pub enum ImageSource {
 /// Search order:
 /// 1. Dockerfile.vibes in the worktree path
 /// 2. Dockerfile.vibes in the bare repo workspace root
-/// 3. Fall back to default sirsedev/claude-vibe image
+/// 3. `base_image` in the workspace root's `.vibes.toml`
+/// 4. Fall back to the default image (see the `default_image` setting)
 pub fn find_image_source(worktree_path: &Path) -> Result<ImageSource> {
-    // First: check worktree path
-    let worktree_dockerfile = worktree_path.join("Dockerfile.vibes");
-    if worktree_dockerfile.exists() {
-        return Ok(ImageSource::BuildFrom {
-            dockerfile: worktree_dockerfile,
-            context: worktree_path.to_path_buf(),
+    for dockerfile in dockerfile_candidates(worktree_path)? {
+        if dockerfile.exists() {
+            let context = dockerfile.parent().unwrap_or(worktree_path).to_path_buf();
+            return Ok(ImageSource::BuildFrom {
+                dockerfile,
+                context,
+            });
+        }
+    }
+
+    if let Some(repo_info) = git::get_bare_repo_info()?
+        && let Some(image) = config::repo_base_image(&repo_info.workspace_root)?
+    {
+        return Ok(ImageSource::UseRepoImage {
+            image,
+            config: repo_info.workspace_root.join(config::REPO_CONFIG_FILE),
         });
     }
 
-    // Second: check bare repo workspace root
+    // Nothing found: use the default image
+    Ok(ImageSource::UseDefault)
+}
+
+/// Dockerfile.vibes locations checked for a worktree, in order: the worktree
+/// itself, then the bare repo workspace root.
+pub fn dockerfile_candidates(worktree_path: &Path) -> Result<Vec<PathBuf>> {
+    let mut candidates = vec![worktree_path.join("Dockerfile.vibes")];
+
     if let Some(repo_info) = git::get_bare_repo_info()? {
         let workspace_dockerfile = repo_info.workspace_root.join("Dockerfile.vibes");
-        if workspace_dockerfile.exists() {
-            return Ok(ImageSource::BuildFrom {
-                dockerfile: workspace_dockerfile,
-                context: repo_info.workspace_root,
-            });
+        if !candidates.contains(&workspace_dockerfile) {
+            candidates.push(workspace_dockerfile);
         }
     }
 
-    // Third: use default image
-    Ok(ImageSource::UseDefault)
+    Ok(candidates)
+}
+
+/// Prefix of the images vibe builds from Dockerfile.vibes
+pub const IMAGE_PREFIX: &str = "claude-vibe-";
+
+/// Image used by `vibe setup`, not tied to any session
+pub const SETUP_IMAGE: &str = "claude-vibe-setup";
+
+/// Image name for the session on `branch`.
+pub fn session_image(branch: &str) -> String {
+    let name = branch
+        .strip_prefix(config::worktree_prefix())
+        .unwrap_or(branch);
+    format!("{}{}", IMAGE_PREFIX, name)
+}
+
+/// A locally stored image built by vibe
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalImage {
+    /// Repository name, without the tag
+    pub name: String,
+    /// Size in bytes, including layers shared with other images
+    pub size: u64,
+}
+
+/// List the images vibe has built for the workspace at `workspace_root`.
+///
+/// Only images labelled with the workspace at build time are listed, so
+/// images of other workspaces (and ones built before images were labelled)
+/// are never included.
+pub fn list_vibe_images(workspace_root: &Path) -> Result<Vec<LocalImage>> {
+    let output = Command::new(container_runtime())
+        .args([
+            "images",
+            "--filter",
+            &format!("label={}={}", WORKSPACE_LABEL, workspace_root.display()),
+            "--format",
+            "{{.Repository}}",
+        ])
+        .output()
+        .context("Failed to run docker images")?;
+    if !output.status.success() {
+        bail!(
+            "docker images failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    // `docker images` only prints rounded, human-readable sizes
+    let mut images = Vec::new();
+    for name in vibe_image_names(&String::from_utf8_lossy(&output.stdout)) {
+        let output = Command::new(container_runtime())
+            .args(["image", "inspect", "--format", "{{.Size}}", &name])
+            .output()
+            .context("Failed to run docker image inspect")?;
+        let size = String::from_utf8_lossy(&output.stdout)
+            .trim()
+            .parse()
+            .unwrap_or(0);
+        images.push(LocalImage { name, size });
+    }
+    Ok(images)
+}
+
+/// Unique names of vibe's images in `docker images` output, one per line.
+///
+/// Podman lists local images as `localhost/<name>`; the prefix is dropped
+/// so names compare equal to `session_image`.
+fn vibe_image_names(output: &str) -> BTreeSet<String> {
+    output
+        .lines()
+        .map(|line| line.trim())
+        .map(|name| name.strip_prefix("localhost/").unwrap_or(name))
+        .filter(|name| name.starts_with(IMAGE_PREFIX))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Check whether an image is stored locally.
+pub fn image_exists(name: &str) -> Result<bool> {
+    let status = Command::new(container_runtime())
+        .args(["image", "inspect", name])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .with_context(|| format!("Failed to run {} (is it installed?)", container_runtime()))?;
+    Ok(status.success())
+}
+
+/// Remove a local image by name.
+pub fn remove_image(name: &str) -> Result<()> {
+    let output = Command::new(container_runtime())
+        .args(["rmi", name])
+        .output()
+        .context("Failed to run docker rmi")?;
+    if !output.status.success() {
+        bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(())
 }
 
 /// Build a Docker image if needed, or return the default image name.
@@ -404,253 +739,1312 @@ pub fn prepare_image(worktree_path: &Path, image_name: &str) -> Result<String> {
             dockerfile,
             context,
         } => {
+            // Builds of the same tag take turns; later ones mostly hit the cache
+            let _lock = lock::acquire_build(image_name)?;
             println!("Building from {}...", dockerfile.display());
             build_image_from(&dockerfile, &context, image_name)?;
             Ok(image_name.to_string())
         }
+        ImageSource::UseRepoImage { image, config } => {
+            println!(
+                "Using image: {} (no Dockerfile.vibes, base_image set in {})",
+                image,
+                config.display()
+            );
+            Ok(image)
+        }
         ImageSource::UseDefault => {
-            println!("Using default image: {}", git::DEFAULT_IMAGE);
-            Ok(git::DEFAULT_IMAGE.to_string())
+            println!(
+                "Using default image: {} (no Dockerfile.vibes found)",
+                config::default_image()
+            );
+            Ok(config::default_image().to_string())
         }
     }
 }
 
-/// Build a Docker image from a specific Dockerfile.
-fn build_image_from(dockerfile: &Path, context: &Path, image_name: &str) -> Result<()> {
-    let (uid, gid) = get_host_uid_gid();
-    let status = Command::new("docker")
-        .args([
-            "build",
-            "-t",
-            image_name,
-            "--build-arg",
-            &format!("USER_ID={}", uid),
-            "--build-arg",
-            &format!("GROUP_ID={}", gid),
-            "-f",
-            dockerfile.to_str().unwrap(),
-            context.to_str().unwrap(),
-        ])
-        .status()
-        .context("Failed to run docker build")?;
+/// How the container runtime maps container UIDs to host UIDs.
+///
+/// Images are built with the host UID/GID for the `claude` user, which only
+/// means the same user on the host if container IDs are host IDs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum UserNamespace {
+    /// Container IDs are host IDs (rootful Docker or Podman)
+    Host,
+    /// Docker daemon with `userns-remap`; containers can opt out with `--userns=host`
+    Remapped,
+    /// Rootless Docker: only container root maps to the host user
+    RootlessDocker,
+    /// Rootless Podman; `--userns=keep-id` maps the host user to the same ID
+    RootlessPodman,
+}
 
-    if !status.success() {
-        bail!("Docker build failed");
+impl UserNamespace {
+    /// Extra `docker run` arguments that keep `/workspace` files owned by the host user
+    fn run_args(self) -> &'static [&'static str] {
+        match self {
+            UserNamespace::Host | UserNamespace::RootlessDocker => &[],
+            UserNamespace::Remapped => &["--userns=host"],
+            UserNamespace::RootlessPodman => &["--userns=keep-id"],
+        }
     }
-
-    Ok(())
 }
 
-/// Run a Docker container with Claude Code.
+/// Container runtimes tried in order when none is configured
+const RUNTIMES: &[&str] = &["docker", "podman"];
+
+/// The command used for all container operations, e.g. `docker`.
 ///
-/// Mounts the worktree, copies Claude config, and launches an interactive session.
-pub fn run_container(worktree_path: &Path, image_name: &str, prompt: Option<&str>) -> Result<()> {
-    let home = std::env::var("HOME").context("HOME not set")?;
-    let api_key = std::env::var("ANTHROPIC_API_KEY").unwrap_or_default();
+/// Taken from `VIBE_RUNTIME`, then the `runtime` setting, and detected from
+/// `PATH` otherwise. Resolved once per process.
+pub fn container_runtime() -> &'static str {
+    static RUNTIME: OnceLock<String> = OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        let env = std::env::var("VIBE_RUNTIME").ok();
+        resolve_runtime(env.as_deref(), config::get().runtime.as_deref(), on_path)
+    })
+}
 
-    let mut args = vec![
-        "run".to_string(),
-        "--rm".to_string(),
-        "-it".to_string(),
-        "-v".to_string(),
-        format!("{}:/workspace", worktree_path.display()),
-        "-w".to_string(),
-        "/workspace".to_string(),
-        "-e".to_string(),
-        format!("ANTHROPIC_API_KEY={}", api_key),
-    ];
+/// Pick the runtime from the environment, the config or what `installed`
+/// finds, in that order.
+///
+/// Falls back to `docker` if nothing is installed, so errors name it.
+fn resolve_runtime(
+    env: Option<&str>,
+    configured: Option<&str>,
+    installed: impl Fn(&str) -> bool,
+) -> String {
+    if let Some(runtime) = env.or(configured).filter(|r| !r.trim().is_empty()) {
+        return runtime.trim().to_string();
+    }
+    RUNTIMES
+        .iter()
+        .find(|runtime| installed(runtime))
+        .unwrap_or(&RUNTIMES[0])
+        .to_string()
+}
 
-    // Build init script for container startup
-    let mut init_script = String::from("set -e; ");
+/// Check whether an executable of this name is on `PATH`.
+pub fn on_path(name: &str) -> bool {
+    std::env::var_os("PATH")
+        .is_some_and(|path| std::env::split_paths(&path).any(|dir| dir.join(name).is_file()))
+}
 
-    // Mount and copy Claude config directory if it exists
-    let claude_dir = PathBuf::from(&home).join(".claude");
-    if claude_dir.exists() {
-        args.extend([
-            "-v".to_string(),
-            format!("{}:/tmp/.claude-host:ro", claude_dir.display()),
-        ]);
-        init_script.push_str(
-            "sudo rm -rf ~/.claude && sudo cp -a /tmp/.claude-host ~/.claude; \
-             sudo chown -R claude:claude ~/.claude; \
-             sed -i 's/\"installMethod\":[^,}]*/\"installMethod\":\"native\"/g' ~/.claude/*.json 2>/dev/null || true; ",
-        );
-    }
+/// Detect the user namespace setup of the container runtime, once per process.
+///
+/// `docker --version` tells Podman (often installed as `docker`) apart from
+/// Docker. Podman reports rootless mode in `.Host.Security.Rootless`, Docker
+/// lists `name=rootless` or `name=userns` in `.SecurityOptions`. If detection
+/// fails, container IDs are assumed to be host IDs.
+fn user_namespace() -> UserNamespace {
+    static DETECTED: OnceLock<UserNamespace> = OnceLock::new();
+
+    *DETECTED.get_or_init(|| {
+        let docker_output = |args: &[&str]| {
+            Command::new(container_runtime())
+                .args(args)
+                .stderr(Stdio::null())
+                .output()
+                .ok()
+                .filter(|o| o.status.success())
+                .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        };
 
-    // Mount and copy Claude config file if it exists
-    let claude_json = PathBuf::from(&home).join(".claude.json");
-    if claude_json.exists() {
-        args.extend([
-            "-v".to_string(),
-            format!("{}:/tmp/.claude-host.json:ro", claude_json.display()),
-        ]);
-        init_script.push_str(
-            "sudo cp /tmp/.claude-host.json ~/.claude.json; \
-             sudo chown claude:claude ~/.claude.json; \
-             sed -i 's/\"installMethod\":[^,}]*/\"installMethod\":\"native\"/g' ~/.claude.json 2>/dev/null || true; ",
-        );
-    }
+        let is_podman =
+            docker_output(&["--version"]).is_some_and(|v| v.to_lowercase().contains("podman"));
 
-    // Setup Claude settings with pre-trusted /workspace directory
-    init_script.push_str(
-        r#"mkdir -p ~/.claude; cat > ~/.claude/settings.json << 'SETTINGS'
-{
-  "permissions": {
-    "additionalDirectories": ["/workspace"],
-    "allow": [
-      "Bash",
-      "Read",
-      "Write",
-      "Edit",
-      "Glob",
-      "Grep",
-      "WebFetch(domain:*)",
-      "WebSearch",
-      "Task",
-      "TodoWrite",
-      "mcp__*"
-    ],
-    "deny": []
-  }
-}
-SETTINGS
-"#,
-    );
+        if is_podman {
+            match docker_output(&["info", "--format", "{{.Host.Security.Rootless}}"]).as_deref() {
+                Some("true") => UserNamespace::RootlessPodman,
+                _ => UserNamespace::Host,
+            }
+        } else {
+            docker_output(&["info", "--format", "{{json .SecurityOptions}}"])
+                .map(|options| parse_security_options(&options))
+                .unwrap_or(UserNamespace::Host)
+        }
+    })
+}
 
-    // Add prompt via environment variable if provided
-    if let Some(p) = prompt {
-        args.extend(["-e".to_string(), format!("CLAUDE_PROMPT={}", p)]);
-        init_script
-            .push_str(r#"exec claude --permission-mode acceptEdits -p "$CLAUDE_PROMPT""#);
+/// Read the user namespace setup from Docker's `SecurityOptions` JSON,
+/// e.g. `["name=seccomp,profile=builtin","name=rootless"]`.
+fn parse_security_options(json: &str) -> UserNamespace {
+    let options: Vec<String> = serde_json::from_str(json).unwrap_or_default();
+    let has = |name: &str| {
+        options.iter().any(|option| {
+            option
+                .split(',')
+                .any(|field| field == format!("name={}", name))
+        })
+    };
+
+    if has("rootless") {
+        UserNamespace::RootlessDocker
+    } else if has("userns") {
+        UserNamespace::Remapped
     } else {
-        init_script.push_str("exec claude --permission-mode acceptEdits");
+        UserNamespace::Host
     }
+}
 
-    args.extend([
-        image_name.to_string(),
-        "bash".to_string(),
-        "-c".to_string(),
-        init_script,
-    ]);
-
-    let status = Command::new("docker")
-        .args(&args)
-        .status()
-        .context("Failed to run docker container")?;
+/// Build contexts larger than this get a warning before building
+const LARGE_CONTEXT_BYTES: u64 = 500 * 1024 * 1024;
 
-    if !status.success() {
-        bail!("Docker container exited with error");
-    }
+/// Whether a large build context is an error, set from the command line
+static STRICT_CONTEXT: OnceLock<bool> = OnceLock::new();
 
-    Ok(())
+/// Fail builds with a large context instead of warning. Call once at startup.
+pub fn set_strict_context(strict: bool) {
+    let _ = STRICT_CONTEXT.set(strict);
 }
 
-/// Run a Docker container with Claude Code and stream output with progress indicator.
+/// Patterns of a `.dockerignore` file, in order.
 ///
-/// Similar to `run_container` but captures and displays Claude's output in real-time
-/// with a spinner to indicate activity. Used for non-interactive prompts.
-pub fn run_container_with_output(
-    worktree_path: &Path,
-    image_name: &str,
-    prompt: &str,
-) -> Result<()> {
-    let home = std::env::var("HOME").context("HOME not set")?;
-    let api_key = std::env::var("ANTHROPIC_API_KEY").unwrap_or_default();
+/// Matching is approximate: `*` also matches across `/`, which only ever
+/// makes the ignored set larger than docker's.
+struct DockerIgnore {
+    /// Each pattern along with whether it's a `!` exception
+    rules: Vec<(GlobMatcher, bool)>,
+}
 
-    let mut args = vec![
-        "run".to_string(),
-        "--rm".to_string(),
-        "-v".to_string(),
-        format!("{}:/workspace", worktree_path.display()),
-        "-w".to_string(),
-        "/workspace".to_string(),
-        "-e".to_string(),
-        format!("ANTHROPIC_API_KEY={}", api_key),
-        "-e".to_string(),
-        format!("CLAUDE_PROMPT={}", prompt),
-    ];
+impl DockerIgnore {
+    fn parse(text: &str) -> Result<Self> {
+        let mut rules = Vec::new();
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (pattern, exception) = match line.strip_prefix('!') {
+                Some(pattern) => (pattern, true),
+                None => (line, false),
+            };
+            let pattern = pattern.trim_start_matches('/').trim_end_matches('/');
+            rules.push((Glob::new(pattern)?.compile_matcher(), exception));
+        }
+        Ok(DockerIgnore { rules })
+    }
 
-    // Build init script for container startup
-    let mut init_script = String::from("set -e; ");
+    /// Whether `path` (relative, `/`-separated) is left out of the context.
+    ///
+    /// A pattern matching a directory also covers everything inside it, and
+    /// the last matching pattern wins.
+    fn is_ignored(&self, path: &str) -> bool {
+        let mut ignored = false;
+        for (glob, exception) in &self.rules {
+            let matches = glob.is_match(path)
+                || path
+                    .match_indices('/')
+                    .any(|(i, _)| glob.is_match(&path[..i]));
+            if matches {
+                ignored = !exception;
+            }
+        }
+        ignored
+    }
 
-    // Mount and copy Claude config directory if it exists
-    let claude_dir = PathBuf::from(&home).join(".claude");
-    if claude_dir.exists() {
-        args.extend([
-            "-v".to_string(),
-            format!("{}:/tmp/.claude-host:ro", claude_dir.display()),
-        ]);
-        init_script.push_str(
-            "sudo rm -rf ~/.claude && sudo cp -a /tmp/.claude-host ~/.claude; \
-             sudo chown -R claude:claude ~/.claude; \
-             sed -i 's/\"installMethod\":[^,}]*/\"installMethod\":\"native\"/g' ~/.claude/*.json 2>/dev/null || true; ",
-        );
+    fn has_exceptions(&self) -> bool {
+        self.rules.iter().any(|(_, exception)| *exception)
     }
+}
 
-    // Mount and copy Claude config file if it exists
-    let claude_json = PathBuf::from(&home).join(".claude.json");
-    if claude_json.exists() {
-        args.extend([
-            "-v".to_string(),
-            format!("{}:/tmp/.claude-host.json:ro", claude_json.display()),
-        ]);
-        init_script.push_str(
-            "sudo cp /tmp/.claude-host.json ~/.claude.json; \
-             sudo chown claude:claude ~/.claude.json; \
-             sed -i 's/\"installMethod\":[^,}]*/\"installMethod\":\"native\"/g' ~/.claude.json 2>/dev/null || true; ",
-        );
+/// Size of the files docker would send as the build context, counting
+/// only until `limit` is exceeded.
+fn context_size(dir: &Path, relative: &str, ignore: &DockerIgnore, limit: u64) -> u64 {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return 0;
+    };
+
+    let mut total = 0;
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let path = match relative {
+            "" => name.to_string_lossy().into_owned(),
+            _ => format!("{}/{}", relative, name.to_string_lossy()),
+        };
+        let ignored = ignore.is_ignored(&path);
+
+        // Symlinks are sent as links, not followed
+        total += match entry.file_type() {
+            Ok(t) if t.is_dir() => {
+                // Exceptions can bring back files inside ignored directories
+                if ignored && !ignore.has_exceptions() {
+                    0
+                } else {
+                    context_size(&entry.path(), &path, ignore, limit - total)
+                }
+            }
+            Ok(t) if t.is_file() && !ignored => entry.metadata().map(|m| m.len()).unwrap_or(0),
+            _ => 0,
+        };
+        if total > limit {
+            break;
+        }
     }
+    total
+}
 
-    // Setup Claude settings with pre-trusted /workspace directory
-    init_script.push_str(
-        r#"mkdir -p ~/.claude; cat > ~/.claude/settings.json << 'SETTINGS'
-{
-  "permissions": {
-    "additionalDirectories": ["/workspace"],
-    "allow": [
-      "Bash",
-      "Read",
-      "Write",
-      "Edit",
-      "Glob",
-      "Grep",
-      "WebFetch(domain:*)",
-      "WebSearch",
-      "Task",
-      "TodoWrite",
-      "mcp__*"
-    ],
-    "deny": []
-  }
-}
-SETTINGS
-"#,
-    );
+/// Warn before building from a context that would take long to upload,
+/// or fail under `--strict`.
+fn check_context_size(context: &Path) -> Result<()> {
+    let ignore_file = context.join(".dockerignore");
+    let ignore = match fs::read_to_string(&ignore_file) {
+        Ok(text) => DockerIgnore::parse(&text)?,
+        Err(_) => DockerIgnore { rules: Vec::new() },
+    };
+
+    let size = context_size(context, "", &ignore, LARGE_CONTEXT_BYTES);
+    if size <= LARGE_CONTEXT_BYTES {
+        return Ok(());
+    }
 
-    // Run Claude with print mode, verbose, and stream-json output for progress display
-    init_script.push_str(
-        r#"exec claude --permission-mode acceptEdits --verbose --output-format stream-json -p "$CLAUDE_PROMPT""#,
+    let advice = if ignore_file.exists() {
+        format!("exclude more files in {}", ignore_file.display())
+    } else {
+        format!("add a .dockerignore to {}", context.display())
+    };
+    let message = format!(
+        "the build context {} is over {}, so the build may be slow to start; {}",
+        context.display(),
+        util::format_bytes(LARGE_CONTEXT_BYTES),
+        advice
     );
 
-    args.extend([
-        image_name.to_string(),
-        "bash".to_string(),
-        "-c".to_string(),
-        init_script,
-    ]);
+    if STRICT_CONTEXT.get().copied().unwrap_or(false) {
+        bail!("Refusing to build: {}", message);
+    }
+    style::print_colored("Warning:", style::theme().uncommitted);
+    println!(" {}", message);
+    Ok(())
+}
 
-    // Set up Ctrl+C handler to clean up terminal
-    let _ = ctrlc::set_handler(move || {
-        reset_terminal();
-        println!(); // New line after any partial output
-        print_closing_line();
-        std::process::exit(130); // Standard exit code for Ctrl+C
-    });
+/// Whether to pass the host time zone and locale, set from the command line
+static HOST_LOCALE: OnceLock<bool> = OnceLock::new();
 
-    // Streaming display state and cost
-    let display = Arc::new(Mutex::new(StreamingDisplay::new()));
-    let cost_usd = Arc::new(Mutex::new(None::<f64>));
-    let spinner_running = Arc::new(AtomicBool::new(true));
+/// Pass the host time zone and locale into containers. Call once at startup.
+pub fn set_host_locale(enabled: bool) {
+    let _ = HOST_LOCALE.set(enabled);
+}
+
+/// Time zone name from the target of the `/etc/localtime` symlink, e.g.
+/// `/usr/share/zoneinfo/Europe/Vilnius` gives `Europe/Vilnius`.
+fn tz_from_localtime(target: &Path) -> Option<String> {
+    let target = target.to_str()?;
+    let (_, name) = target.split_once("zoneinfo/")?;
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// Host time zone from `TZ`, falling back to `/etc/localtime`.
+fn host_time_zone() -> Option<String> {
+    std::env::var("TZ")
+        .ok()
+        .filter(|tz| !tz.is_empty())
+        .or_else(|| tz_from_localtime(&std::fs::read_link("/etc/localtime").ok()?))
+}
+
+/// Add the host's `TZ`, `LANG`, `LANGUAGE` and `LC_*` variables.
+fn add_locale_env(args: &mut Vec<String>) {
+    if !HOST_LOCALE.get().copied().unwrap_or(true) {
+        return;
+    }
+
+    let mut vars: Vec<String> = host_time_zone()
+        .map(|tz| format!("TZ={}", tz))
+        .into_iter()
+        .collect();
+    vars.extend(
+        std::env::vars()
+            .filter(|(name, value)| {
+                !value.is_empty()
+                    && (name == "LANG" || name == "LANGUAGE" || name.starts_with("LC_"))
+            })
+            .map(|(name, value)| format!("{}={}", name, value)),
+    );
+
+    for var in vars {
+        args.extend(["-e".to_string(), var]);
+    }
+}
+
+/// Pass the host's `ANTHROPIC_API_KEY`, if it is set.
+///
+/// Without one, Claude authenticates with the copied host config instead,
+/// e.g. a subscription login, which an empty key would override.
+fn add_api_key(args: &mut Vec<String>) {
+    if host_var_set("ANTHROPIC_API_KEY") {
+        args.extend(["-e".to_string(), "ANTHROPIC_API_KEY".to_string()]);
+    }
+}
+
+/// Add the variables of the `forward_env` setting that are set on the host.
+///
+/// They are passed by name only, so docker takes the values from its own
+/// environment and they never show up in the command line.
+fn add_forwarded_env(args: &mut Vec<String>) {
+    for name in &config::get().forward_env {
+        if host_var_set(name) {
+            args.extend(["-e".to_string(), name.clone()]);
+        }
+    }
+}
+
+/// Whether a host variable is set to a non-empty value.
+fn host_var_set(name: &str) -> bool {
+    std::env::var_os(name).is_some_and(|value| !value.is_empty())
+}
+
+/// Add the `VIBE_*` variables describing the branch checked out at the mount.
+fn add_branch_env(args: &mut Vec<String>, worktree_path: &Path) {
+    let Ok(branch) = git::get_worktree_branch(worktree_path) else {
+        return;
+    };
+    if branch.is_empty() || branch == "HEAD" {
+        return;
+    }
+
+    let session = branch
+        .strip_prefix(config::worktree_prefix())
+        .unwrap_or(&branch);
+    let base = if branch == git::main_branch() {
+        None
+    } else {
+        meta::read_meta(worktree_path)
+            .and_then(|meta| meta.base_branch)
+            .or_else(|| Some(git::main_branch().to_string()))
+    };
+
+    let mut vars = vec![
+        format!("VIBE_SESSION={}", branch),
+        format!("VIBE_SESSION_NAME={}", session),
+        format!("VIBE_BRANCH={}", branch),
+    ];
+    vars.extend(base.map(|base| format!("VIBE_BASE_BRANCH={}", base)));
+
+    for var in vars {
+        args.extend(["-e".to_string(), var]);
+    }
+}
+
+/// Label with the workspace root of a session container or image; every
+/// container vibe starts and every image it builds has it
+const WORKSPACE_LABEL: &str = "vibe.workspace";
+
+/// Label with the checked out branch of a session container
+const BRANCH_LABEL: &str = "vibe.branch";
+
+/// Workspace root of the current repository, or `fallback` outside of one.
+fn session_workspace(fallback: &Path) -> PathBuf {
+    git::get_bare_repo_info()
+        .ok()
+        .flatten()
+        .map(|info| info.workspace_root)
+        .unwrap_or_else(|| fallback.to_path_buf())
+}
+
+/// Label containers so `vibe top` can tell which session they belong to.
+fn add_session_labels(args: &mut Vec<String>, worktree_path: &Path) {
+    let workspace = session_workspace(worktree_path);
+    args.extend([
+        "--label".to_string(),
+        format!("{}={}", WORKSPACE_LABEL, workspace.display()),
+    ]);
+
+    if let Ok(branch) = git::get_worktree_branch(worktree_path)
+        && !branch.is_empty()
+        && branch != "HEAD"
+    {
+        args.extend([
+            "--label".to_string(),
+            format!("{}={}", BRANCH_LABEL, branch),
+        ]);
+    }
+}
+
+/// Whether a session container for `branch` of the workspace is running.
+///
+/// False if the container runtime can't be queried, e.g. when it isn't
+/// installed.
+pub fn session_running(workspace_root: &Path, branch: &str) -> bool {
+    Command::new(container_runtime())
+        .args([
+            "ps",
+            "--quiet",
+            "--filter",
+            &format!("label={}={}", WORKSPACE_LABEL, workspace_root.display()),
+            "--filter",
+            &format!("label={}={}", BRANCH_LABEL, branch),
+        ])
+        .stderr(Stdio::null())
+        .output()
+        .is_ok_and(|o| o.status.success() && !o.stdout.trim_ascii().is_empty())
+}
+
+/// Resource usage of a running session container, as formatted by docker
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContainerStats {
+    /// Branch of the session, empty if unknown (e.g. for `vibe setup`)
+    pub branch: String,
+    /// Workspace root the session belongs to
+    pub workspace: String,
+    pub cpu: String,
+    /// Memory in use and the limit, e.g. `1.2GiB / 15.5GiB`
+    pub memory: String,
+    pub memory_percent: String,
+}
+
+/// Resource usage of all running vibe containers, from any repository.
+pub fn session_stats() -> Result<Vec<ContainerStats>> {
+    let output = Command::new(container_runtime())
+        .args([
+            "ps",
+            "--filter",
+            &format!("label={}", WORKSPACE_LABEL),
+            "--format",
+            &format!(
+                "{{{{.ID}}}}\t{{{{.Label \"{}\"}}}}\t{{{{.Label \"{}\"}}}}",
+                WORKSPACE_LABEL, BRANCH_LABEL
+            ),
+        ])
+        .output()
+        .with_context(|| format!("Failed to run {} (is it installed?)", container_runtime()))?;
+    if !output.status.success() {
+        bail!(
+            "docker ps failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let containers = parse_tab_lines(&String::from_utf8_lossy(&output.stdout), 3);
+    if containers.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut args = vec![
+        "stats".to_string(),
+        "--no-stream".to_string(),
+        "--format".to_string(),
+        "{{.ID}}\t{{.CPUPerc}}\t{{.MemUsage}}\t{{.MemPerc}}".to_string(),
+    ];
+    args.extend(containers.iter().map(|c| c[0].clone()));
+    let output = Command::new(container_runtime())
+        .args(&args)
+        .output()
+        .context("Failed to run docker stats")?;
+
+    // Containers that exited in between are simply left out
+    let stats = parse_tab_lines(&String::from_utf8_lossy(&output.stdout), 4);
+    Ok(containers
+        .into_iter()
+        .filter_map(|container| {
+            let stat = stats.iter().find(|s| container[0].starts_with(&s[0]))?;
+            Some(ContainerStats {
+                workspace: container[1].clone(),
+                branch: container[2].clone(),
+                cpu: stat[1].clone(),
+                memory: stat[2].clone(),
+                memory_percent: stat[3].clone(),
+            })
+        })
+        .collect())
+}
+
+/// Split tab-separated lines into their fields, skipping lines that don't
+/// have exactly `fields` of them.
+fn parse_tab_lines(text: &str, fields: usize) -> Vec<Vec<String>> {
+    text.lines()
+        .map(|line| line.split('\t').map(str::to_string).collect::<Vec<_>>())
+        .filter(|parts| parts.len() == fields)
+        .collect()
+}
+
+/// Parse a `KEY=VALUE` environment variable for a session, or a bare `KEY`
+/// to pass the host's value.
+pub fn parse_env_var(text: &str) -> Result<String> {
+    let name = text.split('=').next().unwrap_or(text);
+    let valid = !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        bail!("Invalid environment variable name '{}'", name);
+    }
+    Ok(text.to_string())
+}
+
+/// Per-session additions to the `docker run` command line
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    /// `KEY=VALUE` variables, or a bare `KEY` to pass the host's value
+    pub env: Vec<String>,
+    /// Files mounted read-only into the container
+    pub secrets: Vec<SecretMount>,
+    /// Extra arguments passed to `docker run` as given
+    pub docker_args: Vec<String>,
+}
+
+impl RunOptions {
+    /// Add the session's variables and read-only mounts.
+    ///
+    /// Bare names are skipped if the host doesn't have them set, so they
+    /// never reach the container as empty variables.
+    fn add_args(&self, args: &mut Vec<String>) {
+        for var in &self.env {
+            if var.contains('=') || host_var_set(var) {
+                args.extend(["-e".to_string(), var.clone()]);
+            }
+        }
+        for secret in &self.secrets {
+            args.extend([
+                "-v".to_string(),
+                format!("{}:{}:ro", secret.host.display(), secret.container),
+            ]);
+        }
+    }
+
+    /// Finish the command line with the extra docker arguments, followed by
+    /// the image and the command to run in it.
+    fn finish_args(
+        &self,
+        args: &mut Vec<String>,
+        image_name: &str,
+        command: impl IntoIterator<Item = String>,
+    ) {
+        args.extend(self.docker_args.iter().cloned());
+        args.push(image_name.to_string());
+        args.extend(command);
+    }
+}
+
+/// A host file mounted read-only into the container for a single session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SecretMount {
+    pub host: PathBuf,
+    pub container: String,
+}
+
+/// Parse a `<host-path>:<container-path>` secret mount.
+///
+/// The host file must exist, and the container path must be outside
+/// `/workspace` so the secret can't end up committed in the worktree.
+pub fn parse_secret_mount(text: &str) -> Result<SecretMount> {
+    let (host, container) = text
+        .rsplit_once(':')
+        .with_context(|| format!("Expected <host-path>:<container-path>, got '{}'", text))?;
+
+    let host = Path::new(host);
+    if !host.is_file() {
+        bail!("Secret file {} does not exist", host.display());
+    }
+    check_secret_target(container)?;
+
+    Ok(SecretMount {
+        host: host
+            .canonicalize()
+            .context("Failed to resolve secret path")?,
+        container: container.to_string(),
+    })
+}
+
+/// Check that a secret's container path is absolute and outside `/workspace`.
+fn check_secret_target(container: &str) -> Result<()> {
+    let path = Path::new(container);
+    if !path.is_absolute() {
+        bail!(
+            "Secret path '{}' in the container must be absolute",
+            container
+        );
+    }
+    if path
+        .components()
+        .any(|c| c == std::path::Component::ParentDir)
+    {
+        bail!("Secret path '{}' must not contain '..'", container);
+    }
+    if path.starts_with("/workspace") {
+        bail!(
+            "Secret path '{}' is inside /workspace, where it could be committed",
+            container
+        );
+    }
+    Ok(())
+}
+
+/// Images that passed `probe_image` in this run
+static PROBED_IMAGES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Prefix of Anthropic API keys
+const API_KEY_PREFIX: &str = "sk-ant-";
+
+/// Whether the credentials were already checked in this run
+static CREDENTIALS_CHECKED: AtomicBool = AtomicBool::new(false);
+
+/// Check that Claude will be able to authenticate in containers, see
+/// `check_credentials`.
+///
+/// Commands call this before any slow setup so a missing key fails early;
+/// the check only runs once per run.
+pub fn require_credentials() -> Result<()> {
+    if CREDENTIALS_CHECKED.load(Ordering::SeqCst) {
+        return Ok(());
+    }
+    let home = std::env::var("HOME").context("HOME not set")?;
+    let api_key = std::env::var("ANTHROPIC_API_KEY").unwrap_or_default();
+    check_credentials(&api_key, Path::new(&home))?;
+    CREDENTIALS_CHECKED.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+/// Check that Claude will be able to authenticate with `api_key`, or with
+/// the host config in `home`.
+///
+/// Fails without an API key and without a host Claude config to copy in,
+/// since Claude would only fail inside the container. A key that doesn't
+/// look like an Anthropic one is warned about but used anyway.
+fn check_credentials(api_key: &str, home: &Path) -> Result<()> {
+    if api_key.is_empty() {
+        if !home.join(".claude.json").exists() && !home.join(".claude").exists() {
+            bail!(
+                "No Claude credentials found\n\
+                 Set ANTHROPIC_API_KEY, or log in by running 'claude' on this machine once"
+            );
+        }
+    } else if !api_key.starts_with(API_KEY_PREFIX) {
+        style::print_colored("Warning:", style::theme().uncommitted);
+        println!(
+            " ANTHROPIC_API_KEY doesn't look like an API key (expected it to start with {})",
+            API_KEY_PREFIX
+        );
+    }
+    Ok(())
+}
+
+/// Check that claude starts in an image, if enabled with `probe_image`.
+///
+/// Catches broken or wrong-architecture images before a session is set up
+/// in them. Each image is checked at most once per run.
+fn probe_image(image_name: &str) -> Result<()> {
+    if !config::get().probe_image {
+        return Ok(());
+    }
+    let mut probed = PROBED_IMAGES.lock().unwrap();
+    if probed.iter().any(|image| image == image_name) {
+        return Ok(());
+    }
+
+    let output = Command::new(container_runtime())
+        .args(["run", "--rm", image_name, "claude", "--version"])
+        .stdin(Stdio::null())
+        .output()
+        .context("Failed to run docker")?;
+    if !output.status.success() {
+        bail!(probe_error(
+            image_name,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    probed.push(image_name.to_string());
+    Ok(())
+}
+
+/// Explain why `claude --version` failed in an image.
+fn probe_error(image_name: &str, stderr: &str) -> String {
+    let hint = if stderr.contains("exec format error") {
+        "the image was built for a different CPU architecture"
+    } else if stderr.contains("executable file not found") || stderr.contains("not found") {
+        "claude is not installed in the image, or not on its PATH"
+    } else {
+        "check that the image starts and has a working claude"
+    };
+
+    let mut message = format!("claude does not run in image {} ({})", image_name, hint);
+    if !stderr.is_empty() {
+        message.push('\n');
+        message.push_str(stderr);
+    }
+    message
+}
+
+/// Add the user namespace arguments to a `docker run` command line, warning
+/// when files in `/workspace` can't be kept owned by the host user.
+fn add_userns_args(args: &mut Vec<String>) {
+    let userns = user_namespace();
+    if userns == UserNamespace::RootlessDocker {
+        style::print_colored("Warning:", style::theme().uncommitted);
+        println!(
+            " rootless Docker maps the container user to a subordinate ID, \
+             files Claude creates will not be owned by you"
+        );
+    }
+    args.extend(userns.run_args().iter().map(|arg| arg.to_string()));
+}
+
+/// Build a Docker image from a specific Dockerfile.
+///
+/// The `claude` user gets the host UID/GID; see `UserNamespace` for how
+/// containers are run so that this stays the host user.
+fn build_image_from(dockerfile: &Path, context: &Path, image_name: &str) -> Result<()> {
+    check_context_size(context)?;
+    pull_base_images(dockerfile)?;
+
+    let (uid, gid) = get_host_uid_gid();
+    let status = run_build_step(Command::new(container_runtime()).args([
+        "build",
+        "-t",
+        image_name,
+        "--label",
+        &format!(
+            "{}={}",
+            WORKSPACE_LABEL,
+            session_workspace(context).display()
+        ),
+        "--build-arg",
+        &format!("USER_ID={}", uid),
+        "--build-arg",
+        &format!("GROUP_ID={}", gid),
+        "-f",
+        dockerfile.to_str().unwrap(),
+        context.to_str().unwrap(),
+    ]))?;
+
+    if !status.success() {
+        bail!("Docker build failed");
+    }
+
+    Ok(())
+}
+
+/// Pull the Dockerfile's base images that aren't stored locally yet.
+///
+/// Pulls are retried `pull_retries` times, waiting a little longer each
+/// time, so a network blip doesn't fail the whole build. Images already
+/// present are left to the build, like those built by earlier stages.
+fn pull_base_images(dockerfile: &Path) -> Result<()> {
+    let text = fs::read_to_string(dockerfile)
+        .with_context(|| format!("Failed to read {}", dockerfile.display()))?;
+    let retries = config::get().pull_retries;
+
+    for image in base_images(&text) {
+        if image_exists(&image)? {
+            continue;
+        }
+        let mut attempt = 0;
+        loop {
+            let status = run_build_step(Command::new(container_runtime()).args(["pull", &image]))?;
+            if status.success() {
+                break;
+            }
+            if attempt == retries {
+                bail!("Failed to pull base image {}", image);
+            }
+            attempt += 1;
+            let delay = Duration::from_secs(2 * u64::from(attempt));
+            style::print_colored("Warning:", style::theme().uncommitted);
+            println!(
+                " pulling {} failed, retrying in {}s ({} of {})",
+                image,
+                delay.as_secs(),
+                attempt,
+                retries
+            );
+            std::thread::sleep(delay);
+        }
+    }
+    Ok(())
+}
+
+/// Images named in a Dockerfile's `FROM` lines.
+///
+/// Skips `scratch`, earlier build stages and images given by build
+/// arguments, which can't be pulled ahead of the build.
+fn base_images(dockerfile: &str) -> Vec<String> {
+    let mut stages: Vec<String> = Vec::new();
+    let mut images = Vec::new();
+
+    for line in dockerfile.lines() {
+        let mut words = line.split_whitespace();
+        if !words.next().is_some_and(|w| w.eq_ignore_ascii_case("FROM")) {
+            continue;
+        }
+        let mut words = words.skip_while(|w| w.starts_with("--"));
+        let Some(image) = words.next() else {
+            continue;
+        };
+        let skip = image.eq_ignore_ascii_case("scratch")
+            || image.contains('$')
+            || stages.contains(&image.to_lowercase())
+            || images.iter().any(|i| i == image);
+        if !skip {
+            images.push(image.to_string());
+        }
+
+        if words.next().is_some_and(|w| w.eq_ignore_ascii_case("AS"))
+            && let Some(stage) = words.next()
+        {
+            stages.push(stage.to_lowercase());
+        }
+    }
+    images
+}
+
+/// Exit code the init script uses when a setup step fails before `exec claude`
+const INIT_FAILED_EXIT_CODE: i32 = 86;
+
+/// Start of the line the init script prints on stderr when a setup step fails
+const INIT_FAILED_MESSAGE: &str = "vibe: container setup failed at:";
+
+/// Number of non-Claude output lines kept for reporting a failed container
+const MAX_CAPTURED_LINES: usize = 20;
+
+/// Build the container init script along with the docker arguments (mounts
+/// and variables) it relies on.
+///
+/// The script copies the host Claude config into the container, writes the
+/// settings file with the `allow` list and then execs `command`. Without an
+/// allow list Claude isn't started, and no settings file is written. If a
+/// setup step fails, it names the failing command on stderr and exits with
+/// `INIT_FAILED_EXIT_CODE` so that setup failures can be told apart from
+/// errors of the command.
+///
+/// The script text itself is fixed: configured values like the settings are
+/// passed in variables, just like the prompt in `CLAUDE_PROMPT`, so nothing
+/// user-provided is ever parsed by the shell.
+///
+/// The config is copied with `sudo -n`, which fails instead of waiting for
+/// a password that can never be typed in. On images where sudo needs one
+/// (or isn't installed), it is copied as the container user instead.
+fn build_init_script(home: &str, command: &str, allow: Option<&[String]>) -> (Vec<String>, String) {
+    let mut docker_args = Vec::new();
+    let mut init_script = format!(
+        "set -e; trap 'echo \"{} $BASH_COMMAND\" >&2; exit {}' ERR; \
+         if sudo -n true 2>/dev/null; then as_root() {{ sudo -n \"$@\"; }}; else as_root() {{ \"$@\"; }}; fi; ",
+        INIT_FAILED_MESSAGE, INIT_FAILED_EXIT_CODE
+    );
+
+    // Mount and copy Claude config directory if it exists
+    let claude_dir = PathBuf::from(home).join(".claude");
+    if claude_dir.exists() {
+        docker_args.extend([
+            "-v".to_string(),
+            format!("{}:/tmp/.claude-host:ro", claude_dir.display()),
+        ]);
+        init_script.push_str(
+            "as_root rm -rf ~/.claude && as_root cp -a /tmp/.claude-host ~/.claude; \
+             as_root chown -R claude:claude ~/.claude; \
+             sed -i 's/\"installMethod\":[^,}]*/\"installMethod\":\"native\"/g' ~/.claude/*.json 2>/dev/null || true; ",
+        );
+    }
+
+    // Mount and copy Claude config file if it exists
+    let claude_json = PathBuf::from(home).join(".claude.json");
+    if claude_json.exists() {
+        docker_args.extend([
+            "-v".to_string(),
+            format!("{}:/tmp/.claude-host.json:ro", claude_json.display()),
+        ]);
+        init_script.push_str(
+            "as_root cp /tmp/.claude-host.json ~/.claude.json; \
+             as_root chown claude:claude ~/.claude.json; \
+             sed -i 's/\"installMethod\":[^,}]*/\"installMethod\":\"native\"/g' ~/.claude.json 2>/dev/null || true; ",
+        );
+    }
+
+    // Setup Claude settings with pre-trusted /workspace directory
+    if let Some(allow) = allow {
+        docker_args.extend([
+            "-e".to_string(),
+            format!("VIBE_CLAUDE_SETTINGS={}", settings_json(allow)),
+        ]);
+        init_script.push_str(
+            "mkdir -p ~/.claude; printf '%s\\n' \"$VIBE_CLAUDE_SETTINGS\" > ~/.claude/settings.json; ",
+        );
+    }
+
+    // Setup is done; from here on errors belong to the command itself
+    init_script.push_str("trap - ERR; exec ");
+    init_script.push_str(command);
+
+    (docker_args, init_script)
+}
+
+/// Tools Claude may use in containers unless configured otherwise
+const DEFAULT_ALLOW: &[&str] = &[
+    "Bash",
+    "Read",
+    "Write",
+    "Edit",
+    "Glob",
+    "Grep",
+    "WebFetch(domain:*)",
+    "WebSearch",
+    "Task",
+    "TodoWrite",
+    "mcp__*",
+];
+
+/// Build Claude's permission allow-list from the `claude.allow` and
+/// `claude.bash_allow` settings.
+///
+/// `bash_allow` replaces a plain `Bash` entry (which allows any command)
+/// with one `Bash(<pattern>)` entry per pattern.
+fn permission_allow_list(
+    allow: Option<Vec<String>>,
+    bash_allow: Option<Vec<String>>,
+) -> Vec<String> {
+    let allow = allow.unwrap_or_else(|| DEFAULT_ALLOW.iter().map(|s| s.to_string()).collect());
+    let Some(bash_allow) = bash_allow else {
+        return allow;
+    };
+
+    let mut list = Vec::new();
+    for tool in allow {
+        if tool == "Bash" {
+            list.extend(
+                bash_allow
+                    .iter()
+                    .map(|pattern| format!("Bash({})", pattern)),
+            );
+        } else {
+            list.push(tool);
+        }
+    }
+    list
+}
+
+/// Permission allow-list from the user config.
+fn configured_allow_list() -> Vec<String> {
+    let config = config::get();
+    permission_allow_list(
+        config.claude_allow.clone(),
+        config.claude_bash_allow.clone(),
+    )
+}
+
+/// Claude's `settings.json` for containers, trusting `/workspace`.
+fn settings_json(allow: &[String]) -> String {
+    let settings = serde_json::json!({
+        "permissions": {
+            "additionalDirectories": ["/workspace"],
+            "allow": allow,
+            "deny": [],
+        }
+    });
+    serde_json::to_string_pretty(&settings).expect("settings are valid JSON")
+}
+
+/// Claude command line for an interactive session, optionally with a prompt.
+///
+/// The prompt is sent as the first message of the live session rather than
+/// with `-p`, which would print a single answer and exit. It is read from
+/// `CLAUDE_PROMPT` so that it never needs shell quoting.
+fn claude_command(prompt: Option<&str>) -> String {
+    if prompt.is_some() {
+        r#"claude --permission-mode acceptEdits -- "$CLAUDE_PROMPT""#.to_string()
+    } else {
+        "claude --permission-mode acceptEdits".to_string()
+    }
+}
+
+/// How headless Claude output is shown
+#[derive(Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputFormat {
+    /// Parse Claude's event stream into a live progress box
+    #[default]
+    StreamJson,
+    /// Print Claude's plain text output as-is
+    Text,
+}
+
+impl OutputFormat {
+    /// Claude command line for a headless run in this format.
+    fn claude_command(self) -> &'static str {
+        match self {
+            OutputFormat::StreamJson => {
+                r#"claude --permission-mode acceptEdits --verbose --output-format stream-json -p "$CLAUDE_PROMPT""#
+            }
+            OutputFormat::Text => {
+                r#"claude --permission-mode acceptEdits --output-format text -p "$CLAUDE_PROMPT""#
+            }
+        }
+    }
+}
+
+/// Describe a failed container exit.
+///
+/// Distinguishes a container that never got to start Claude from Claude
+/// itself exiting with an error.
+fn exit_error(status: ExitStatus) -> anyhow::Error {
+    match status.code() {
+        Some(INIT_FAILED_EXIT_CODE) => anyhow!("Container setup failed before Claude started"),
+        Some(125) => anyhow!("Docker failed to start the container"),
+        Some(126) | Some(127) => anyhow!("Claude could not be started in the container"),
+        Some(code) => anyhow!("Claude exited with error (exit code {})", code),
+        None => anyhow!("Docker container was terminated by a signal"),
+    }
+}
+
+/// Check that a path is safe to mount at `/workspace`.
+///
+/// The path must be an existing directory and, inside a bare repo, either the
+/// workspace root or one of its Claude worktrees. This keeps a stale path
+/// from an orphaned worktree from mounting something unexpected.
+fn validate_mount_path(worktree_path: &Path) -> Result<PathBuf> {
+    if !worktree_path.is_dir() {
+        bail!(
+            "Worktree directory {} does not exist (orphaned worktree?)\n\
+             Use 'vibe cleanup' to remove it",
+            worktree_path.display()
+        );
+    }
+
+    let path = worktree_path
+        .canonicalize()
+        .context("Failed to resolve worktree path")?;
+
+    let Some(repo_info) = git::get_bare_repo_info()? else {
+        return Ok(path);
+    };
+
+    let is_known = |candidate: &Path| candidate.canonicalize().is_ok_and(|c| c == path);
+    if is_known(&repo_info.workspace_root)
+        || git::list_claude_worktrees()?
+            .iter()
+            .any(|wt| is_known(&wt.path))
+    {
+        return Ok(path);
+    }
+
+    bail!(
+        "{} is not a worktree of {}",
+        path.display(),
+        repo_info.workspace_root.display()
+    );
+}
+
+/// Run a command in a session's container instead of Claude.
+///
+/// The container is set up as for a session, minus Claude's settings. Its
+/// output goes straight to the terminal, and it only gets a TTY when vibe
+/// runs in one. Returns the command's exit code.
+///
+/// The command may exit with `INIT_FAILED_EXIT_CODE` itself, so that code
+/// only counts as a setup failure when the init script reported one on
+/// stderr. With a TTY, stderr can't be told apart from stdout and the code
+/// is returned as-is, after the init script's report was shown. Exit code
+/// 125 is always taken to mean docker failed, even though a command exiting
+/// with 125 looks just the same.
+pub fn run_command(
+    worktree_path: &Path,
+    image_name: &str,
+    command: &[String],
+    options: &RunOptions,
+) -> Result<i32> {
+    let worktree_path = validate_mount_path(worktree_path)?;
+    let home = std::env::var("HOME").context("HOME not set")?;
+
+    let mut args = vec![
+        "run".to_string(),
+        "--rm".to_string(),
+        "-i".to_string(),
+        "-v".to_string(),
+        format!("{}:/workspace", worktree_path.display()),
+        "-w".to_string(),
+        "/workspace".to_string(),
+    ];
+    let tty = std::io::stdin().is_terminal() && std::io::stdout().is_terminal();
+    if tty {
+        args.push("-t".to_string());
+    }
+
+    add_api_key(&mut args);
+    add_userns_args(&mut args);
+    add_session_labels(&mut args, &worktree_path);
+    add_branch_env(&mut args, &worktree_path);
+    add_locale_env(&mut args);
+    add_forwarded_env(&mut args);
+    options.add_args(&mut args);
+
+    // The command is passed as arguments of the script, never parsed by it
+    let (init_args, init_script) = build_init_script(&home, "\"$@\"", None);
+    args.extend(init_args);
+    let script = [
+        "bash".to_string(),
+        "-c".to_string(),
+        init_script,
+        "vibe".to_string(),
+    ];
+    options.finish_args(
+        &mut args,
+        image_name,
+        script.into_iter().chain(command.iter().cloned()),
+    );
+
+    let mut child = Command::new(container_runtime())
+        .args(&args)
+        .stderr(if tty {
+            Stdio::inherit()
+        } else {
+            Stdio::piped()
+        })
+        .spawn()
+        .context("Failed to run docker container")?;
+
+    // Pass stderr through, watching for the init script's report
+    let mut setup_failed = false;
+    if let Some(stderr) = child.stderr.take() {
+        let mut reader = BufReader::new(stderr);
+        let mut line = Vec::new();
+        while reader.read_until(b'\n', &mut line).is_ok_and(|n| n > 0) {
+            setup_failed |= line.starts_with(INIT_FAILED_MESSAGE.as_bytes());
+            let _ = std::io::stderr().write_all(&line);
+            line.clear();
+        }
+    }
+    let status = child
+        .wait()
+        .context("Failed to wait for docker container")?;
+
+    match status.code() {
+        Some(INIT_FAILED_EXIT_CODE) if setup_failed => {
+            bail!("Container setup failed before the command started")
+        }
+        Some(125) => bail!("Docker failed to start the container"),
+        Some(code) => Ok(code),
+        None => bail!("Docker container was terminated by a signal"),
+    }
+}
+
+/// Run a Docker container with Claude Code.
+///
+/// Mounts the worktree, copies Claude config, and launches an interactive session.
+pub fn run_container(
+    worktree_path: &Path,
+    image_name: &str,
+    prompt: Option<&str>,
+    options: &RunOptions,
+) -> Result<()> {
+    let worktree_path = validate_mount_path(worktree_path)?;
+    require_credentials()?;
+    probe_image(image_name)?;
+    let home = std::env::var("HOME").context("HOME not set")?;
+
+    let mut args = vec![
+        "run".to_string(),
+        "--rm".to_string(),
+        "-it".to_string(),
+        "-v".to_string(),
+        format!("{}:/workspace", worktree_path.display()),
+        "-w".to_string(),
+        "/workspace".to_string(),
+    ];
+
+    add_api_key(&mut args);
+    add_userns_args(&mut args);
+    add_session_labels(&mut args, &worktree_path);
+    add_branch_env(&mut args, &worktree_path);
+    add_locale_env(&mut args);
+    add_forwarded_env(&mut args);
+    options.add_args(&mut args);
+
+    let allow = configured_allow_list();
+    let (init_args, init_script) = build_init_script(&home, &claude_command(prompt), Some(&allow));
+    args.extend(init_args);
+
+    // Passed as its own argument, so docker takes quotes and newlines verbatim
+    if let Some(p) = prompt {
+        args.extend(["-e".to_string(), format!("CLAUDE_PROMPT={}", p)]);
+    }
+
+    options.finish_args(
+        &mut args,
+        image_name,
+        ["bash".to_string(), "-c".to_string(), init_script],
+    );
+
+    let status = Command::new(container_runtime())
+        .args(&args)
+        .status()
+        .context("Failed to run docker container")?;
+
+    if !status.success() {
+        return Err(exit_error(status));
+    }
+
+    Ok(())
+}
+
+/// Run a Docker container with Claude Code and stream output with progress indicator.
+///
+/// Similar to `run_container` but captures and displays Claude's output in real-time
+/// with a spinner to indicate activity. Used for non-interactive prompts.
+///
+/// The progress display is headed by `labels`. With `OutputFormat::Text`,
+/// Claude's plain output is passed through instead.
+pub fn run_container_with_output(
+    worktree_path: &Path,
+    image_name: &str,
+    prompt: &str,
+    format: OutputFormat,
+    labels: StreamLabels,
+    options: &RunOptions,
+) -> Result<()> {
+    let worktree_path = validate_mount_path(worktree_path)?;
+    require_credentials()?;
+    probe_image(image_name)?;
+    let home = std::env::var("HOME").context("HOME not set")?;
+
+    let mut args = vec![
+        "run".to_string(),
+        "--rm".to_string(),
+        "-v".to_string(),
+        format!("{}:/workspace", worktree_path.display()),
+        "-w".to_string(),
+        "/workspace".to_string(),
+        "-e".to_string(),
+        format!("CLAUDE_PROMPT={}", prompt),
+    ];
+
+    add_api_key(&mut args);
+    add_userns_args(&mut args);
+    add_session_labels(&mut args, &worktree_path);
+    add_branch_env(&mut args, &worktree_path);
+    add_locale_env(&mut args);
+    add_forwarded_env(&mut args);
+    options.add_args(&mut args);
+
+    let allow = configured_allow_list();
+    // Run Claude in print mode; stream-json output drives the progress display
+    let (init_args, init_script) = build_init_script(&home, format.claude_command(), Some(&allow));
+    args.extend(init_args);
+
+    options.finish_args(
+        &mut args,
+        image_name,
+        ["bash".to_string(), "-c".to_string(), init_script],
+    );
+
+    if format == OutputFormat::Text {
+        // Nothing to parse: let the output go straight to the terminal
+        let status = Command::new(container_runtime())
+            .args(&args)
+            .status()
+            .context("Failed to run docker container")?;
+        if !status.success() {
+            return Err(exit_error(status));
+        }
+        return Ok(());
+    }
+
+    // Clean up the terminal on Ctrl+C
+    STREAMING.store(true, Ordering::SeqCst);
+    install_interrupt_handler();
+
+    // Streaming display state and cost
+    let display = Arc::new(Mutex::new(StreamingDisplay::new(
+        labels.running,
+        labels.done,
+    )));
+    let cost_usd = Arc::new(Mutex::new(None::<f64>));
+    let spinner_running = Arc::new(AtomicBool::new(true));
 
     // Start spinner thread
     let display_spinner = Arc::clone(&display);
@@ -660,7 +2054,7 @@ SETTINGS
         display_spinner.lock().unwrap().redraw();
 
         while spinner_flag.load(Ordering::SeqCst) {
-            std::thread::sleep(Duration::from_millis(80));
+            std::thread::sleep(style::theme().spinner_interval);
             if spinner_flag.load(Ordering::SeqCst) {
                 display_spinner.lock().unwrap().tick();
             }
@@ -668,7 +2062,7 @@ SETTINGS
     });
 
     // Spawn docker process and capture output
-    let mut child = Command::new("docker")
+    let mut child = Command::new(container_runtime())
         .args(&args)
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
@@ -681,29 +2075,62 @@ SETTINGS
 
     let display_clone = Arc::clone(&display);
     let cost_clone = Arc::clone(&cost_usd);
+    let captured = Arc::new(Mutex::new(Vec::<String>::new()));
+    let captured_clone = Arc::clone(&captured);
     let stdout_thread = std::thread::spawn(move || {
         let reader = BufReader::new(stdout);
-        for line in reader.lines() {
-            if let Ok(line) = line {
-                // Try to parse as Claude stream-json event
-                if let Ok(event) = serde_json::from_str::<ClaudeEvent>(&line) {
+        let mut stats = ParseStats::default();
+        let mut raw = false;
+        for line in reader.lines().map_while(Result::ok) {
+            // Try to parse as Claude stream-json event
+            let event = serde_json::from_str::<ClaudeEvent>(&line)
+                .ok()
+                .filter(|event| !matches!(event, ClaudeEvent::Unknown));
+            stats.record(event.is_some());
+
+            match event {
+                Some(event) => {
                     if let Some(cost) = process_event(&event, &display_clone) {
                         *cost_clone.lock().unwrap() = Some(cost);
                     }
                 }
-                // Silently ignore unparseable JSON lines (internal Claude messages)
+                None => {
+                    if raw {
+                        display_clone.lock().unwrap().add_line(OutputLine {
+                            content: line.clone(),
+                            is_tool: false,
+                        });
+                    }
+                    // Kept in case the container fails
+                    let mut captured = captured_clone.lock().unwrap();
+                    if captured.len() == MAX_CAPTURED_LINES {
+                        captured.remove(0);
+                    }
+                    captured.push(line);
+                }
+            }
+
+            // Rather than appear frozen, show what Claude prints as-is
+            if !raw && stats.drifted() {
+                raw = true;
+                let mut display = display_clone.lock().unwrap();
+                display.set_notice("Claude's output format was not recognized, showing raw output");
+                for line in captured_clone.lock().unwrap().iter() {
+                    display.add_line(OutputLine {
+                        content: line.clone(),
+                        is_tool: false,
+                    });
+                }
             }
         }
     });
 
     let stderr_thread = std::thread::spawn(move || {
         let reader = BufReader::new(stderr);
-        for line in reader.lines() {
-            if let Ok(line) = line {
-                // stderr is usually error messages or status, display as-is
-                if !line.trim().is_empty() {
-                    eprintln!("\x1b[31m{}\x1b[0m", line);
-                }
+        for line in reader.lines().map_while(Result::ok) {
+            // stderr is usually error messages or status, display as-is
+            if !line.trim().is_empty() {
+                eprintln!("{}", style::paint(&line, Color::DarkRed));
             }
         }
     });
@@ -726,13 +2153,358 @@ SETTINGS
 
     // Display cost if available
     if let Some(cost) = *cost_usd.lock().unwrap() {
-        println!("\x1b[90m  Cost: ${:.4}\x1b[0m", cost);
+        println!(
+            "{}",
+            style::paint(&format!("  Cost: ${:.4}", cost), Color::DarkGrey)
+        );
     }
 
     if !status.success() {
-        bail!("Docker container exited with error");
+        let captured = captured.lock().unwrap();
+        if status.code() == Some(INIT_FAILED_EXIT_CODE) && !captured.is_empty() {
+            println!("Container output:");
+            for line in captured.iter() {
+                println!("  {}", line);
+            }
+        }
+        return Err(exit_error(status));
     }
 
-    println!("\x1b[32mSetup complete!\x1b[0m");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_security_options() {
+        assert_eq!(
+            parse_security_options(r#"["name=apparmor","name=seccomp,profile=builtin"]"#),
+            UserNamespace::Host
+        );
+        assert_eq!(
+            parse_security_options(
+                r#"["name=seccomp,profile=builtin","name=rootless","name=cgroupns"]"#
+            ),
+            UserNamespace::RootlessDocker
+        );
+        assert_eq!(
+            parse_security_options(r#"["name=seccomp,profile=builtin","name=userns"]"#),
+            UserNamespace::Remapped
+        );
+        assert_eq!(parse_security_options("null"), UserNamespace::Host);
+    }
+
+    #[test]
+    fn test_tz_from_localtime() {
+        let tz = |target: &str| tz_from_localtime(Path::new(target));
+
+        assert_eq!(
+            tz("/usr/share/zoneinfo/Europe/Vilnius").as_deref(),
+            Some("Europe/Vilnius")
+        );
+        assert_eq!(
+            tz("../usr/share/zoneinfo/Etc/UTC").as_deref(),
+            Some("Etc/UTC")
+        );
+        assert_eq!(
+            tz("/var/db/timezone/zoneinfo/Asia/Tokyo").as_deref(),
+            Some("Asia/Tokyo")
+        );
+        assert_eq!(tz("/usr/share/zoneinfo/"), None);
+        assert_eq!(tz("/etc/custom-time"), None);
+    }
+
+    #[test]
+    fn test_parse_env_var() {
+        assert_eq!(parse_env_var("RUST_LOG=debug").unwrap(), "RUST_LOG=debug");
+        assert_eq!(parse_env_var("EMPTY=").unwrap(), "EMPTY=");
+        assert_eq!(parse_env_var("FROM_HOST").unwrap(), "FROM_HOST");
+        assert!(parse_env_var("=x").is_err());
+        assert!(parse_env_var("1X=y").is_err());
+        assert!(parse_env_var("A-B=y").is_err());
+    }
+
+    fn strings(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_dockerignore() {
+        let ignore =
+            DockerIgnore::parse("# comment\n/target\nnode_modules/\n*.log\n!keep.log\n").unwrap();
+
+        assert!(ignore.is_ignored("target"));
+        assert!(ignore.is_ignored("target/debug/vibe"));
+        assert!(!ignore.is_ignored("web/node_modules"));
+        assert!(ignore.is_ignored("node_modules/left-pad/index.js"));
+        assert!(ignore.is_ignored("build.log"));
+        assert!(!ignore.is_ignored("keep.log"));
+        assert!(!ignore.is_ignored("src/main.rs"));
+        assert!(ignore.has_exceptions());
+    }
+
+    #[test]
+    fn test_context_size() {
+        let dir = std::env::temp_dir().join(format!("vibe-context-test-{}", std::process::id()));
+        fs::create_dir_all(dir.join("target")).unwrap();
+        fs::write(dir.join("Dockerfile.vibes"), [0; 10]).unwrap();
+        fs::write(dir.join("target/big"), [0; 1000]).unwrap();
+
+        let none = DockerIgnore::parse("").unwrap();
+        let target = DockerIgnore::parse("target").unwrap();
+        assert_eq!(context_size(&dir, "", &none, u64::MAX), 1010);
+        assert_eq!(context_size(&dir, "", &target, u64::MAX), 10);
+        assert!(context_size(&dir, "", &none, 100) > 100);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_resolve_runtime() {
+        let only_docker = |name: &str| name == "docker";
+        let only_podman = |name: &str| name == "podman";
+        let nothing = |_: &str| false;
+
+        assert_eq!(resolve_runtime(None, None, only_docker), "docker");
+        assert_eq!(resolve_runtime(None, None, only_podman), "podman");
+        assert_eq!(resolve_runtime(None, None, nothing), "docker");
+        // Configured runtimes win over whatever is installed
+        assert_eq!(resolve_runtime(None, Some("podman"), only_docker), "podman");
+        assert_eq!(
+            resolve_runtime(Some("nerdctl"), Some("podman"), only_docker),
+            "nerdctl"
+        );
+        assert_eq!(resolve_runtime(Some(""), None, only_podman), "podman");
+    }
+
+    #[test]
+    fn test_probe_error() {
+        let message = probe_error("img", "exec /usr/bin/claude: exec format error");
+        assert!(message.contains("different CPU architecture"));
+        assert!(message.ends_with("exec format error"));
+
+        let message = probe_error(
+            "img",
+            "exec: \"claude\": executable file not found in $PATH",
+        );
+        assert!(message.contains("not installed"));
+    }
+
+    #[test]
+    fn test_vibe_image_names() {
+        let output = "claude-vibe-b\nlocalhost/claude-vibe-a\nclaude-vibe-b\n<none>\nlocalhost/claude-vibe-b\n";
+        let names: Vec<_> = vibe_image_names(output).into_iter().collect();
+        assert_eq!(names, ["claude-vibe-a", "claude-vibe-b"]);
+    }
+
+    #[test]
+    fn test_base_images() {
+        let dockerfile = "\
+ARG BASE=debian
+FROM --platform=linux/amd64 rust:1.85 AS builder
+FROM builder AS test
+from node:22
+FROM ${BASE}
+FROM scratch
+FROM rust:1.85
+";
+        assert_eq!(base_images(dockerfile), vec!["rust:1.85", "node:22"]);
+    }
+
+    #[test]
+    fn test_parse_tab_lines() {
+        let stats = "abc123\t1.50%\t100MiB / 2GiB\t4.88%\n\nbroken line\n";
+        assert_eq!(
+            parse_tab_lines(stats, 4),
+            vec![vec!["abc123", "1.50%", "100MiB / 2GiB", "4.88%"]]
+        );
+        // Unlabeled fields are empty, not missing
+        assert_eq!(
+            parse_tab_lines("abc\t/ws\t\n", 3),
+            vec![vec!["abc", "/ws", ""]]
+        );
+    }
+
+    #[test]
+    fn test_claude_command_prompt() {
+        assert_eq!(claude_command(None), "claude --permission-mode acceptEdits");
+
+        let command = claude_command(Some("it's \"done\""));
+        assert!(!command.contains(" -p "));
+        assert!(command.ends_with(r#""$CLAUDE_PROMPT""#));
+    }
+
+    #[test]
+    fn test_init_script_has_no_configured_values() {
+        let allow = strings(&["Bash(echo \"; rm -rf /; echo \")"]);
+        let (args, script) =
+            build_init_script("/nonexistent", &claude_command(Some("x")), Some(&allow));
+
+        assert!(!script.contains("rm -rf"));
+        assert!(
+            args.iter()
+                .any(|arg| arg.starts_with("VIBE_CLAUDE_SETTINGS=") && arg.contains("rm -rf"))
+        );
+
+        // Commands other than Claude don't get a settings file
+        let (args, script) = build_init_script("/nonexistent", "\"$@\"", None);
+        assert!(!script.contains("settings.json"));
+        assert!(
+            !args
+                .iter()
+                .any(|arg| arg.starts_with("VIBE_CLAUDE_SETTINGS="))
+        );
+        assert!(script.ends_with("exec \"$@\""));
+    }
+
+    #[test]
+    fn test_init_script_never_waits_for_sudo() {
+        let home = std::env::temp_dir().join(format!("vibe-sudo-test-{}", std::process::id()));
+        fs::create_dir_all(home.join(".claude")).unwrap();
+        fs::write(home.join(".claude.json"), "{}").unwrap();
+
+        let (_, script) = build_init_script(home.to_str().unwrap(), "true", None);
+        assert!(script.contains("as_root cp /tmp/.claude-host.json"));
+        // Any sudo that could prompt for a password would hang the container
+        assert_eq!(
+            script.matches("sudo").count(),
+            script.matches("sudo -n").count()
+        );
+
+        fs::remove_dir_all(&home).unwrap();
+    }
+
+    #[test]
+    fn test_check_credentials() {
+        let home =
+            std::env::temp_dir().join(format!("vibe-credentials-test-{}", std::process::id()));
+        fs::create_dir_all(&home).unwrap();
+
+        assert!(check_credentials("", &home).is_err());
+        assert!(check_credentials("sk-ant-api03-x", &home).is_ok());
+        // Malformed keys are only warned about
+        assert!(check_credentials("not-a-key", &home).is_ok());
+
+        // A logged in host config is enough
+        fs::write(home.join(".claude.json"), "{}").unwrap();
+        assert!(check_credentials("", &home).is_ok());
+
+        fs::remove_dir_all(&home).unwrap();
+    }
+
+    #[test]
+    fn test_prompt_is_delivered_verbatim() {
+        let dir = std::env::temp_dir().join(format!("vibe-prompt-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let marker = dir.join("executed");
+        // Stand-in for claude that prints its last argument
+        let fake_claude = dir.join("claude");
+        fs::write(
+            &fake_claude,
+            "#!/bin/sh\nfor last; do :; done\nprintf '%s' \"$last\"\n",
+        )
+        .unwrap();
+        fs::set_permissions(
+            &fake_claude,
+            std::os::unix::fs::PermissionsExt::from_mode(0o755),
+        )
+        .unwrap();
+
+        let prompt = format!(
+            "\"; touch {}; echo \"\nsecond line $HOME `id`",
+            marker.display()
+        );
+        let path = format!(
+            "{}:{}",
+            dir.display(),
+            std::env::var("PATH").unwrap_or_default()
+        );
+        for command in [
+            claude_command(Some(&prompt)),
+            OutputFormat::StreamJson.claude_command().to_string(),
+            OutputFormat::Text.claude_command().to_string(),
+        ] {
+            let output = Command::new("bash")
+                .args(["-c", &format!("exec {}", command)])
+                .env("PATH", &path)
+                .env("CLAUDE_PROMPT", &prompt)
+                .output()
+                .unwrap();
+            assert_eq!(String::from_utf8_lossy(&output.stdout), prompt);
+        }
+        assert!(!marker.exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_permission_allow_list() {
+        assert_eq!(permission_allow_list(None, None), strings(DEFAULT_ALLOW));
+
+        let bash = permission_allow_list(None, Some(strings(&["cargo *", "git *"])));
+        assert_eq!(
+            &bash[..3],
+            strings(&["Bash(cargo *)", "Bash(git *)", "Read"])
+        );
+        assert!(!bash.contains(&"Bash".to_string()));
+
+        let custom =
+            permission_allow_list(Some(strings(&["Read", "Bash"])), Some(strings(&["make"])));
+        assert_eq!(custom, strings(&["Read", "Bash(make)"]));
+
+        // Without Bash in the allow-list there's nothing to narrow down
+        let no_bash = permission_allow_list(Some(strings(&["Read"])), Some(strings(&["make"])));
+        assert_eq!(no_bash, strings(&["Read"]));
+    }
+
+    #[test]
+    fn test_settings_json() {
+        let json: serde_json::Value =
+            serde_json::from_str(&settings_json(&strings(&["Read", "Bash(cargo *)"]))).unwrap();
+        assert_eq!(
+            json["permissions"]["allow"],
+            serde_json::json!(["Read", "Bash(cargo *)"])
+        );
+        assert_eq!(
+            json["permissions"]["additionalDirectories"],
+            serde_json::json!(["/workspace"])
+        );
+    }
+
+    #[test]
+    fn test_docker_args_before_image() {
+        let options = RunOptions {
+            docker_args: strings(&["--network", "host", "--gpus=all"]),
+            ..Default::default()
+        };
+        let mut args = strings(&["run", "-e", "FOO=1"]);
+        options.finish_args(&mut args, "claude-vibe-x", strings(&["bash", "-c", "true"]));
+        assert_eq!(
+            args,
+            strings(&[
+                "run",
+                "-e",
+                "FOO=1",
+                "--network",
+                "host",
+                "--gpus=all",
+                "claude-vibe-x",
+                "bash",
+                "-c",
+                "true",
+            ])
+        );
+    }
+
+    #[test]
+    fn test_check_secret_target() {
+        assert!(check_secret_target("/home/claude/.netrc").is_ok());
+        assert!(check_secret_target("/workspaces/creds").is_ok());
+        assert!(check_secret_target("/workspace").is_err());
+        assert!(check_secret_target("/workspace/.env").is_err());
+        assert!(check_secret_target("/tmp/../workspace/.env").is_err());
+        assert!(check_secret_target("creds.json").is_err());
+    }
+}
@@ -0,0 +1,104 @@
+//! Disk usage of directory trees, with limits for huge ones.
+
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+/// Directories never counted: git's object stores aren't part of a worktree
+const SKIPPED_DIRS: &[&str] = &[".git", ".bare"];
+
+/// Limits on a disk usage walk
+#[derive(Clone, Copy, Default)]
+pub struct Limits {
+    /// Don't descend more than this many directories below the root
+    pub max_depth: Option<usize>,
+    /// Stop counting at this time
+    pub deadline: Option<Instant>,
+}
+
+/// Size of a directory tree
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Usage {
+    pub bytes: u64,
+    /// Whether a limit was hit, making `bytes` a lower bound
+    pub capped: bool,
+}
+
+/// Total size of the files under `path`, skipping anything unreadable.
+///
+/// Symlinks are not followed and `.git`/`.bare` directories are skipped.
+pub fn usage(path: &Path, limits: Limits) -> Usage {
+    let mut usage = Usage::default();
+    walk(path, 0, &limits, &mut usage);
+    usage
+}
+
+fn walk(dir: &Path, depth: usize, limits: &Limits, usage: &mut Usage) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        if limits.deadline.is_some_and(|d| Instant::now() >= d) {
+            usage.capped = true;
+            return;
+        }
+
+        match entry.file_type() {
+            Ok(t) if t.is_dir() => {
+                if SKIPPED_DIRS.iter().any(|name| entry.file_name() == *name) {
+                    continue;
+                }
+                if limits.max_depth.is_some_and(|max| depth >= max) {
+                    usage.capped = true;
+                    continue;
+                }
+                walk(&entry.path(), depth + 1, limits, usage);
+            }
+            Ok(t) if t.is_file() => {
+                usage.bytes += entry.metadata().map(|m| m.len()).unwrap_or(0);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_usage_limits() {
+        let dir = std::env::temp_dir().join(format!("vibe-du-test-{}", std::process::id()));
+        fs::create_dir_all(dir.join("a/b")).unwrap();
+        fs::create_dir_all(dir.join(".git")).unwrap();
+        fs::write(dir.join("top"), [0; 10]).unwrap();
+        fs::write(dir.join("a/mid"), [0; 100]).unwrap();
+        fs::write(dir.join("a/b/deep"), [0; 1000]).unwrap();
+        fs::write(dir.join(".git/index"), [0; 5]).unwrap();
+
+        let full = Usage {
+            bytes: 1110,
+            capped: false,
+        };
+        assert_eq!(usage(&dir, Limits::default()), full);
+
+        let shallow = Limits {
+            max_depth: Some(1),
+            ..Default::default()
+        };
+        let capped = Usage {
+            bytes: 110,
+            capped: true,
+        };
+        assert_eq!(usage(&dir, shallow), capped);
+
+        let expired = Limits {
+            deadline: Some(Instant::now()),
+            ..Default::default()
+        };
+        assert!(usage(&dir, expired).capped);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,11 +1,15 @@
 //! Git utility functions for worktree and repository management.
 
 use anyhow::{Context, Result, bail};
+use globset::Glob;
 use std::fs;
-use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use crate::WORKTREE_PREFIX;
+use crate::{config, meta, style};
 
 /// Default Docker image when no Dockerfile.vibes is found.
 pub const DEFAULT_IMAGE: &str = "sirsedev/claude-vibe";
@@ -20,7 +24,6 @@ pub struct Worktree {
 /// Information about a bare repository setup with worktree support.
 pub struct BareRepoInfo {
     /// Path to the .bare directory (the actual git repository)
-    #[allow(dead_code)]
     pub bare_path: PathBuf,
     /// Path to the workspace root (directory containing .git file)
     pub workspace_root: PathBuf,
@@ -34,13 +37,16 @@ pub struct BareRepoInfo {
 ///
 /// Returns None if not in such a setup (e.g., regular git repo).
 pub fn get_bare_repo_info() -> Result<Option<BareRepoInfo>> {
-    // Get the git directory path
+    // Get the shared git directory, which is .bare from the root and from any worktree
     let output = Command::new("git")
-        .args(["rev-parse", "--git-dir"])
+        .args(["rev-parse", "--git-common-dir"])
         .output()
         .context("Failed to get git directory")?;
 
     if !output.status.success() {
+        if let Some(hint) = broken_link_hint() {
+            bail!(hint);
+        }
         return Ok(None);
     }
 
@@ -67,6 +73,13 @@ pub fn get_bare_repo_info() -> Result<Option<BareRepoInfo>> {
         return Ok(None);
     }
 
+    if !stale_worktree_links(&git_dir_abs, &workspace_root).is_empty() {
+        bail!(
+            "Worktrees still point to where this workspace used to be (was it moved?)\n\
+             Run 'vibe repair' to fix them"
+        );
+    }
+
     Ok(Some(BareRepoInfo {
         bare_path: git_dir_abs,
         workspace_root,
@@ -78,6 +91,9 @@ pub fn get_bare_repo_info() -> Result<Option<BareRepoInfo>> {
 /// Returns BareRepoInfo if valid, or an error with helpful message if not.
 pub fn require_bare_repo() -> Result<BareRepoInfo> {
     if !is_git_repo() {
+        if let Some(hint) = broken_link_hint() {
+            bail!(hint);
+        }
         bail!("Not in a git repository");
     }
 
@@ -90,8 +106,99 @@ pub fn require_bare_repo() -> Result<BareRepoInfo> {
     })
 }
 
+/// Hint for when git finds no repository but a vibe workspace is around,
+/// i.e. this worktree's link to the repository is broken.
+fn broken_link_hint() -> Option<String> {
+    let cwd = std::env::current_dir().ok()?;
+    find_workspace_root(&cwd)?;
+    Some(
+        "This worktree's link to the repository is broken (was the workspace moved?)\n\
+         Run 'vibe repair' to fix it"
+            .to_string(),
+    )
+}
+
+/// Workspace root at or above `dir`: the closest directory with a `.bare`
+/// repository.
+///
+/// Found from the file system alone, so it works when git can't find the
+/// repository.
+pub fn find_workspace_root(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .find(|d| d.join(".bare").is_dir())
+        .map(Path::to_path_buf)
+}
+
+/// Linked worktrees as recorded in the bare repository: the entry's
+/// directory under `.bare/worktrees` and the worktree `.git` file it links to.
+pub fn recorded_worktree_links(bare_path: &Path) -> Vec<(PathBuf, PathBuf)> {
+    let Ok(entries) = fs::read_dir(bare_path.join("worktrees")) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let link = fs::read_to_string(entry.path().join("gitdir")).ok()?;
+            Some((entry.path(), PathBuf::from(link.trim())))
+        })
+        .collect()
+}
+
+/// Recorded worktree links that are gone and lie outside `workspace_root`,
+/// which is what's left when the whole workspace was moved.
+fn stale_worktree_links(bare_path: &Path, workspace_root: &Path) -> Vec<PathBuf> {
+    recorded_worktree_links(bare_path)
+        .into_iter()
+        .map(|(_, link)| link)
+        .filter(|link| !link.exists() && !link.starts_with(workspace_root))
+        .collect()
+}
+
+/// Where a worktree `.git` file recorded before the workspace moved is now.
+///
+/// Tries ever shorter tails of the old path below `workspace_root`, so
+/// `/old/ws/claude/abc/.git` is found as `<workspace_root>/claude/abc/.git`.
+/// Tails keep at least the worktree directory, never matching the
+/// workspace's own `.git` file.
+pub fn relocated_link(
+    workspace_root: &Path,
+    old: &Path,
+    exists: impl Fn(&Path) -> bool,
+) -> Option<PathBuf> {
+    let parts: Vec<_> = old
+        .components()
+        .filter(|c| matches!(c, Component::Normal(_)))
+        .collect();
+    (0..parts.len().saturating_sub(1))
+        .map(|start| workspace_root.join(parts[start..].iter().collect::<PathBuf>()))
+        .find(|candidate| exists(candidate))
+}
+
+/// Whether to avoid network access, set from the command line
+static OFFLINE: OnceLock<bool> = OnceLock::new();
+
+/// Skip fetches and remote queries, relying on local refs. Call once at startup.
+pub fn set_offline(offline: bool) {
+    let _ = OFFLINE.set(offline);
+}
+
+/// Check whether offline mode is on.
+///
+/// In offline mode remote state comes from the locally cached `origin/*`
+/// refs, which may be stale.
+pub fn is_offline() -> bool {
+    OFFLINE.get().copied().unwrap_or(false)
+}
+
 /// Get the main branch name from remote.
+///
+/// In offline mode this uses the cached `origin/HEAD`, or the bare
+/// repository's HEAD (set when it was cloned).
 pub fn get_main_branch() -> Result<String> {
+    if is_offline() {
+        return Ok(local_main_branch().unwrap_or_else(|| "main".to_string()));
+    }
+
     let output = Command::new("git")
         .args(["remote", "show", "origin"])
         .output()
@@ -109,30 +216,302 @@ pub fn get_main_branch() -> Result<String> {
     Ok("main".to_string())
 }
 
+/// Main branch name from local refs only.
+fn local_main_branch() -> Option<String> {
+    let symbolic_ref = |args: &[&str]| {
+        let output = Command::new("git").args(args).output().ok()?;
+        let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        (output.status.success() && !name.is_empty()).then_some(name)
+    };
+
+    if let Some(name) = symbolic_ref(&[
+        "symbolic-ref",
+        "--quiet",
+        "--short",
+        "refs/remotes/origin/HEAD",
+    ]) {
+        return Some(name.strip_prefix("origin/").unwrap_or(&name).to_string());
+    }
+
+    let repo_info = get_bare_repo_info().ok()??;
+    symbolic_ref(&[
+        "--git-dir",
+        repo_info.bare_path.to_str()?,
+        "symbolic-ref",
+        "--short",
+        "HEAD",
+    ])
+}
+
+/// Check whether `branch` exists on origin.
+///
+/// Asks the remote, or in offline mode looks for the cached `origin/<branch>` ref.
+fn remote_branch_exists(worktree_path: &Path, branch: &str) -> Result<bool> {
+    let remote_ref = format!("refs/remotes/origin/{}", branch);
+
+    let mut command = Command::new("git");
+    command.current_dir(worktree_path);
+    if is_offline() {
+        command.args(["rev-parse", "--verify", "--quiet", &remote_ref]);
+    } else {
+        command.args(["ls-remote", "--exit-code", "--heads", "origin", branch]);
+    }
+
+    Ok(command.output()?.status.success())
+}
+
+/// Main branch name, looked up once per process.
+pub fn main_branch() -> &'static str {
+    static MAIN_BRANCH: OnceLock<String> = OnceLock::new();
+    MAIN_BRANCH.get_or_init(|| get_main_branch().unwrap_or_else(|_| "main".to_string()))
+}
+
+/// Check whether a worktree has the main branch checked out.
+///
+/// Such a worktree is the main checkout and must never be offered for
+/// cleanup or removed.
+pub fn is_main_worktree(wt: &Worktree) -> bool {
+    wt.branch == main_branch()
+}
+
+/// How `create_worktree` should set up the worktree's branch
+#[derive(Debug, PartialEq)]
+enum BranchPlan {
+    /// Create a fresh branch from HEAD (`-b`)
+    Create,
+    /// Check out the existing branch as-is
+    Reuse,
+    /// Reset the existing branch to HEAD (`-B`)
+    Recreate,
+}
+
+impl BranchPlan {
+    /// Whether the branch only exists because of this plan, and so should
+    /// be deleted again if creating the worktree fails.
+    fn owns_branch(&self) -> bool {
+        *self == BranchPlan::Create
+    }
+}
+
+/// Decide how to create a worktree for `branch` given the repository state.
+///
+/// A leftover branch (e.g. from a failed earlier run) is reused unless
+/// `force` asks to recreate it. A branch that is still checked out in
+/// another worktree can't be used either way.
+fn plan_branch(branch: &str, exists: bool, checked_out: bool, force: bool) -> Result<BranchPlan> {
+    if !exists {
+        return Ok(BranchPlan::Create);
+    }
+
+    if checked_out {
+        bail!(
+            "Branch '{}' is already checked out in another worktree\n\
+             Use 'vibe continue' to resume it, or 'vibe cleanup' to remove it",
+            branch
+        );
+    }
+
+    Ok(if force {
+        BranchPlan::Recreate
+    } else {
+        BranchPlan::Reuse
+    })
+}
+
+/// Check whether `rev` resolves to a commit.
+pub fn commit_exists(rev: &str) -> bool {
+    Command::new("git")
+        .args([
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("{}^{{commit}}", rev),
+        ])
+        .stdout(Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
 /// Create a new git worktree with the given name.
-pub fn create_worktree(repo_root: &Path, worktree_name: &str) -> Result<PathBuf> {
+///
+/// The branch starts from `base`, or the current HEAD without one. If a
+/// branch with that name already exists it is reused, or reset to the start
+/// point when `force` is set.
+pub fn create_worktree(
+    repo_root: &Path,
+    worktree_name: &str,
+    force: bool,
+    base: Option<&str>,
+) -> Result<PathBuf> {
+    if let Some(base) = base
+        && !commit_exists(base)
+    {
+        bail!("Base '{}' does not resolve to a commit", base);
+    }
+
     let worktree_path = repo_root.join(worktree_name);
 
+    if worktree_path.exists() {
+        bail!(
+            "Directory '{}' already exists\n\
+             Remove it, or run 'git worktree prune' if it is left over from a removed worktree",
+            worktree_path.display()
+        );
+    }
+
+    let checked_out = list_worktrees()?
+        .iter()
+        .any(|wt| wt.branch == worktree_name);
+    let plan = plan_branch(
+        worktree_name,
+        branch_exists(worktree_name),
+        checked_out,
+        force,
+    )?;
+
+    if plan == BranchPlan::Reuse {
+        println!("Reusing existing branch {}", worktree_name);
+        if base.is_some() {
+            println!("Ignoring the base, use --force to reset the branch to it");
+        }
+    }
+    add_worktree(repo_root, &worktree_path, worktree_name, &plan, base)?;
+
+    std::fs::canonicalize(&worktree_path).context("Failed to resolve worktree path")
+}
+
+/// Run `git worktree add` in `repo` as planned, rolling back on failure.
+fn add_worktree(
+    repo: &Path,
+    worktree_path: &Path,
+    branch: &str,
+    plan: &BranchPlan,
+    base: Option<&str>,
+) -> Result<()> {
+    let mut args = vec!["worktree", "add", worktree_path.to_str().unwrap()];
+    match plan {
+        BranchPlan::Create => args.extend(["-b", branch]),
+        BranchPlan::Recreate => args.extend(["-B", branch]),
+        BranchPlan::Reuse => args.push(branch),
+    }
+    if *plan != BranchPlan::Reuse {
+        args.extend(base);
+    }
+
     let status = Command::new("git")
-        .args([
-            "worktree",
-            "add",
-            worktree_path.to_str().unwrap(),
-            "-b",
-            worktree_name,
-        ])
+        .current_dir(repo)
+        .args(&args)
         .status()
         .context("Failed to create worktree")?;
 
     if !status.success() {
+        rollback_worktree(repo, worktree_path, branch, plan);
         bail!("Failed to create worktree");
     }
+    Ok(())
+}
+
+/// Undo a partially created worktree so that retrying starts clean.
+///
+/// `git worktree add` can fail after creating the branch or the directory,
+/// e.g. when the checkout itself fails. Branches that existed before are
+/// left alone.
+fn rollback_worktree(repo: &Path, worktree_path: &Path, branch: &str, plan: &BranchPlan) {
+    if worktree_path.exists() {
+        let _ = std::fs::remove_dir_all(worktree_path);
+    }
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .current_dir(repo)
+            .args(args)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|s| s.success())
+    };
+    git(&["worktree", "prune"]);
+
+    let branch_ref = format!("refs/heads/{}", branch);
+    if plan.owns_branch() && git(&["rev-parse", "--verify", "--quiet", &branch_ref]) {
+        git(&["branch", "-D", branch]);
+    }
+}
+
+/// Remove a worktree along with its branch, when setting it up failed after
+/// `checkout_worktree` recreated both.
+pub fn discard_worktree(repo: &Path, worktree_path: &Path, branch: &str) {
+    rollback_worktree(repo, worktree_path, branch, &BranchPlan::Create);
+}
+
+/// Create a worktree for an existing branch (without creating a new branch).
+pub fn checkout_worktree(repo_root: &Path, branch: &str) -> Result<PathBuf> {
+    let worktree_path = repo_root.join(branch);
+
+    let status = Command::new("git")
+        .current_dir(repo_root)
+        .args(["worktree", "add", worktree_path.to_str().unwrap(), branch])
+        .status()
+        .context("Failed to create worktree")?;
+
+    if !status.success() {
+        bail!("Failed to create worktree for branch '{}'", branch);
+    }
 
     std::fs::canonicalize(&worktree_path).context("Failed to resolve worktree path")
 }
 
+/// Check if a local branch exists.
+pub fn branch_exists(branch: &str) -> bool {
+    Command::new("git")
+        .args([
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("refs/heads/{}", branch),
+        ])
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
 /// List all Claude worktrees (those starting with the worktree prefix).
 pub fn list_claude_worktrees() -> Result<Vec<Worktree>> {
+    let mut worktrees = list_worktrees()?;
+    worktrees.retain(|wt| wt.branch.starts_with(config::worktree_prefix()));
+    Ok(worktrees)
+}
+
+/// List the Claude worktrees that are sessions, leaving out the main
+/// checkout.
+///
+/// These are the rows `vibe status` numbers, in the same order.
+pub fn list_session_worktrees() -> Result<Vec<Worktree>> {
+    let mut worktrees = list_claude_worktrees()?;
+    worktrees.retain(|wt| !is_main_worktree(wt));
+    Ok(worktrees)
+}
+
+/// Keep the worktrees whose branch matches the glob `pattern`, as given
+/// to `--match`.
+pub fn filter_by_glob(worktrees: &[Worktree], pattern: &str) -> Result<Vec<Worktree>> {
+    let glob = Glob::new(pattern)
+        .with_context(|| format!("Invalid --match pattern '{}'", pattern))?
+        .compile_matcher();
+    Ok(worktrees
+        .iter()
+        .filter(|wt| glob.is_match(&wt.branch))
+        .cloned()
+        .collect())
+}
+
+/// Branch shown for worktrees with a detached HEAD
+const DETACHED: &str = "(detached)";
+
+/// List all worktrees, not just Claude ones.
+///
+/// Worktrees with a detached HEAD get `DETACHED` as their branch; the bare
+/// repository itself is not included.
+pub fn list_worktrees() -> Result<Vec<Worktree>> {
     let output = Command::new("git")
         .args(["worktree", "list", "--porcelain"])
         .output()
@@ -148,10 +527,10 @@ pub fn list_claude_worktrees() -> Result<Vec<Worktree>> {
             current_path = Some(PathBuf::from(path));
         } else if let Some(branch) = line.strip_prefix("branch refs/heads/") {
             current_branch = Some(branch.to_string());
+        } else if line == "detached" {
+            current_branch = Some(DETACHED.to_string());
         } else if line.is_empty() {
-            if let (Some(path), Some(branch)) = (current_path.take(), current_branch.take())
-                && branch.starts_with(WORKTREE_PREFIX)
-            {
+            if let (Some(path), Some(branch)) = (current_path.take(), current_branch.take()) {
                 worktrees.push(Worktree { path, branch });
             }
             current_path = None;
@@ -160,27 +539,133 @@ pub fn list_claude_worktrees() -> Result<Vec<Worktree>> {
     }
 
     // Handle last entry if no trailing newline
-    if let (Some(path), Some(branch)) = (current_path, current_branch)
-        && branch.starts_with(WORKTREE_PREFIX)
-    {
+    if let (Some(path), Some(branch)) = (current_path, current_branch) {
         worktrees.push(Worktree { path, branch });
     }
 
     Ok(worktrees)
 }
 
+/// Find all worktrees matching a name (partial match supported).
+pub fn find_worktrees(name: &str) -> Result<Vec<Worktree>> {
+    Ok(match_worktrees(
+        &list_claude_worktrees()?,
+        name,
+        MatchRank::Substring,
+    ))
+}
+
 /// Find a worktree by name (partial match supported).
+///
+/// Fails listing the candidates if the name matches more than one worktree.
 pub fn find_worktree(name: &str) -> Result<Option<Worktree>> {
+    single_match(name, find_worktrees(name)?)
+}
+
+/// Find a session worktree whose branch or short name is `name` or starts
+/// with it, never matching paths or the middle of a name.
+pub fn find_worktree_by_name(name: &str) -> Result<Option<Worktree>> {
+    let matches = match_worktrees(&list_session_worktrees()?, name, MatchRank::Prefix);
+    single_match(name, matches)
+}
+
+/// Find a worktree by its exact name or a unique prefix of it, for commands
+/// that remove or rename it.
+///
+/// Unlike `find_worktree`, the name never matches the middle of a branch or
+/// path, so a slip can't pick a worktree the user didn't mean.
+pub fn find_worktree_strict(name: &str) -> Result<Option<Worktree>> {
+    if name.is_empty() {
+        bail!("Worktree name must not be empty");
+    }
     let worktrees = list_claude_worktrees()?;
+    let matches = match_worktrees(&worktrees, name, MatchRank::Prefix);
+    if matches.is_empty()
+        && let [wt] = match_worktrees(&worktrees, name, MatchRank::Substring).as_slice()
+    {
+        bail!(
+            "Worktree '{}' not found (did you mean {}? Use its full name)",
+            name,
+            wt.branch
+        );
+    }
+    single_match(name, matches)
+}
 
-    for wt in worktrees {
-        let path_str = wt.path.to_string_lossy();
-        if path_str.contains(name) || wt.branch.contains(name) {
-            return Ok(Some(wt));
-        }
+/// The only worktree of `matches`, failing with the candidates if there are
+/// several.
+fn single_match(name: &str, mut matches: Vec<Worktree>) -> Result<Option<Worktree>> {
+    if matches.len() > 1 {
+        let candidates: Vec<_> = matches
+            .iter()
+            .map(|wt| format!("  {}", wt.branch))
+            .collect();
+        bail!(
+            "'{}' matches multiple worktrees:\n{}\nUse a more specific name",
+            name,
+            candidates.join("\n")
+        );
+    }
+
+    Ok(matches.pop())
+}
+
+/// How closely a worktree matches a requested name (higher is better)
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum MatchRank {
+    /// Path or branch contains the name
+    Substring,
+    /// Branch without the worktree prefix starts with the name, or the
+    /// branch does and the name goes past the prefix
+    Prefix,
+    /// Branch (with or without the worktree prefix) equals the name
+    Exact,
+}
+
+/// Rank how well a worktree matches `name`, or None if it doesn't match.
+///
+/// An empty name matches nothing, and neither does a part of the worktree
+/// prefix as a prefix match (every session's branch starts with `claude/`).
+fn match_rank(wt: &Worktree, name: &str) -> Option<MatchRank> {
+    if name.is_empty() {
+        return None;
     }
+    let prefix = config::worktree_prefix();
+    let short = wt.branch.strip_prefix(prefix).unwrap_or(&wt.branch);
+    let past_prefix = name.len() > prefix.len() && name.starts_with(prefix);
+
+    if wt.branch == name || short == name {
+        Some(MatchRank::Exact)
+    } else if short.starts_with(name) || (past_prefix && wt.branch.starts_with(name)) {
+        Some(MatchRank::Prefix)
+    } else if wt.path.to_string_lossy().contains(name) || wt.branch.contains(name) {
+        Some(MatchRank::Substring)
+    } else {
+        None
+    }
+}
 
-    Ok(None)
+/// Select the worktrees matching `name` at least as closely as `min`,
+/// keeping only the best-ranked matches.
+///
+/// Exact matches win over prefix matches, which win over substring matches,
+/// so `fix` picks `claude/fix` even when `claude/fix-login` also exists.
+fn match_worktrees(worktrees: &[Worktree], name: &str, min: MatchRank) -> Vec<Worktree> {
+    let ranked: Vec<_> = worktrees
+        .iter()
+        .filter_map(|wt| match_rank(wt, name).map(|rank| (rank, wt)))
+        .filter(|(rank, _)| *rank >= min)
+        .collect();
+
+    let Some(best) = ranked.iter().map(|(rank, _)| *rank).max() else {
+        return Vec::new();
+    };
+
+    ranked
+        .into_iter()
+        .filter(|(rank, _)| *rank == best)
+        .map(|(_, wt)| wt.clone())
+        .collect()
 }
 
 /// Check if worktree is synced with remote (branch exists and commits match).
@@ -188,20 +673,17 @@ pub fn is_worktree_synced(worktree_path: &Path) -> Result<bool> {
     let branch = get_worktree_branch(worktree_path)?;
 
     // Check if branch exists on remote
-    let remote_check = Command::new("git")
-        .current_dir(worktree_path)
-        .args(["ls-remote", "--exit-code", "--heads", "origin", &branch])
-        .output()?;
-
-    if !remote_check.status.success() {
+    if !remote_branch_exists(worktree_path, &branch)? {
         return Ok(false);
     }
 
-    // Fetch latest
-    let _ = Command::new("git")
-        .current_dir(worktree_path)
-        .args(["fetch", "origin", &branch])
-        .output();
+    // Fetch latest (offline mode compares against the cached ref instead)
+    if !is_offline() {
+        let _ = Command::new("git")
+            .current_dir(worktree_path)
+            .args(["fetch", "origin", &branch])
+            .output();
+    }
 
     // Compare local and remote commits
     let local = Command::new("git")
@@ -224,6 +706,71 @@ pub fn is_worktree_synced(worktree_path: &Path) -> Result<bool> {
     Ok(local_commit == remote_commit)
 }
 
+/// Fetch all branches from origin, updating the remote-tracking refs.
+pub fn fetch_origin() -> Result<()> {
+    let output = Command::new("git")
+        .args(["fetch", "--quiet", "origin"])
+        .output()
+        .context("Failed to run git fetch")?;
+    if !output.status.success() {
+        bail!(
+            "git fetch failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+/// Count how far `branch` has diverged from `base`.
+///
+/// Returns `(ahead, behind)`, or None if either ref doesn't resolve.
+pub fn get_ahead_behind(base: &str, branch: &str) -> Option<(usize, usize)> {
+    let output = Command::new("git")
+        .args([
+            "rev-list",
+            "--left-right",
+            "--count",
+            &format!("{}...{}", base, branch),
+        ])
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    // Output is "<only in base>\t<only in branch>"
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut counts = stdout.split_whitespace();
+    let behind = counts.next()?.parse().ok()?;
+    let ahead = counts.next()?.parse().ok()?;
+
+    Some((ahead, behind))
+}
+
+/// Resolve a base branch name to the ref to compare against.
+///
+/// Prefers the remote-tracking ref (`origin/<base>`) when it exists,
+/// falling back to the name as given.
+pub fn resolve_base_ref(base: &str) -> String {
+    let remote_exists = Command::new("git")
+        .args([
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("refs/remotes/origin/{}", base),
+        ])
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false);
+
+    if remote_exists {
+        format!("origin/{}", base)
+    } else {
+        base.to_string()
+    }
+}
+
 /// Check if worktree is unused (no commits beyond base, no changes).
 pub fn is_worktree_unused(worktree_path: &Path) -> Result<bool> {
     // Check for uncommitted changes
@@ -280,6 +827,50 @@ pub fn is_worktree_unused(worktree_path: &Path) -> Result<bool> {
     Ok(count == 0)
 }
 
+/// Time of the most recent activity in a worktree.
+///
+/// This is the later of the last commit time and the modification time of
+/// any changed or untracked file. None if neither can be determined.
+pub fn last_activity(worktree_path: &Path) -> Option<SystemTime> {
+    let commit_time = Command::new("git")
+        .current_dir(worktree_path)
+        .args(["log", "-1", "--format=%ct"])
+        .output()
+        .ok()
+        .and_then(|o| {
+            String::from_utf8_lossy(&o.stdout)
+                .trim()
+                .parse::<u64>()
+                .ok()
+        })
+        .map(|secs| UNIX_EPOCH + Duration::from_secs(secs));
+
+    let file_time = Command::new("git")
+        .current_dir(worktree_path)
+        .args([
+            "ls-files",
+            "--modified",
+            "--others",
+            "--exclude-standard",
+            "-z",
+        ])
+        .output()
+        .ok()
+        .and_then(|o| {
+            String::from_utf8_lossy(&o.stdout)
+                .split('\0')
+                .filter(|f| !f.is_empty())
+                .filter_map(|f| {
+                    fs::metadata(worktree_path.join(f))
+                        .and_then(|m| m.modified())
+                        .ok()
+                })
+                .max()
+        });
+
+    commit_time.max(file_time)
+}
+
 /// Get the current branch name for a worktree.
 pub fn get_worktree_branch(worktree_path: &Path) -> Result<String> {
     let output = Command::new("git")
@@ -291,13 +882,39 @@ pub fn get_worktree_branch(worktree_path: &Path) -> Result<String> {
     Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
 }
 
+/// Check whether the current directory is inside a worktree.
+///
+/// Removing such a worktree would leave the user's shell in a deleted directory.
+pub fn contains_current_dir(worktree_path: &Path) -> bool {
+    let (Ok(cwd), Ok(path)) = (
+        std::env::current_dir().and_then(fs::canonicalize),
+        fs::canonicalize(worktree_path),
+    ) else {
+        return false;
+    };
+    cwd.starts_with(path)
+}
+
 /// Remove a worktree and optionally its branch.
 ///
 /// Handles both existing worktrees and orphaned ones (where directory was deleted).
-pub fn remove_worktree_with_branch(worktree_path: &Path, branch: &str, delete_branch: bool) -> Result<()> {
-    if worktree_path.exists() {
+/// Vibe state left behind is removed as well (see `meta::cleanup_worktree_state`).
+/// Refuses to remove the main branch's worktree.
+pub fn remove_worktree_with_branch(
+    worktree_path: &Path,
+    branch: &str,
+    delete_branch: bool,
+) -> Result<()> {
+    if branch == main_branch() {
+        bail!(
+            "Refusing to remove the worktree of the main branch '{}'",
+            branch
+        );
+    }
+
+    if worktree_path.join(".git").exists() {
         // Normal removal for existing worktree
-        Command::new("git")
+        let status = Command::new("git")
             .args([
                 "worktree",
                 "remove",
@@ -306,16 +923,25 @@ pub fn remove_worktree_with_branch(worktree_path: &Path, branch: &str, delete_br
             ])
             .status()
             .context("Failed to remove worktree")?;
+        if !status.success() {
+            bail!("Failed to remove worktree {}", worktree_path.display());
+        }
     } else {
-        // For orphaned worktrees (directory deleted), use prune
+        // For orphaned worktrees (directory deleted, or only vibe state
+        // left in it), use prune
         Command::new("git")
             .args(["worktree", "prune"])
             .status()
             .context("Failed to prune worktrees")?;
     }
+    meta::cleanup_worktree_state(worktree_path)?;
 
     if delete_branch {
-        let _ = Command::new("git").args(["branch", "-D", branch]).status();
+        // Callers report the removal themselves
+        let _ = Command::new("git")
+            .args(["branch", "-D", branch])
+            .stdout(Stdio::null())
+            .status();
     }
 
     Ok(())
@@ -330,28 +956,109 @@ pub fn is_git_repo() -> bool {
         .unwrap_or(false)
 }
 
-/// Get AI-generated summary of workspace changes using Claude CLI.
-///
-/// Returns None if there are no changes or if Claude CLI fails.
-pub fn get_ai_summary(worktree_path: &Path) -> Option<String> {
-    // Get git diff stats
-    let diff_output = Command::new("git")
+/// Recent commits of a worktree, one `git log --oneline` line each.
+pub fn recent_log(worktree_path: &Path, count: usize) -> Result<String> {
+    let output = Command::new("git")
         .current_dir(worktree_path)
-        .args(["diff", "--stat", "HEAD"])
+        .args(["log", "--oneline", "-n", &count.to_string()])
         .output()
-        .ok()?;
-    let diff_stat = String::from_utf8_lossy(&diff_output.stdout);
+        .context("Failed to run git log")?;
+    if !output.status.success() {
+        bail!("git log failed");
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .trim_end()
+        .to_string())
+}
 
-    // Get status
-    let status_output = Command::new("git")
+/// How long Claude may take to summarize changes
+const SUMMARY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Number of snapshots taken so far, keeping their temporary index files apart
+static SNAPSHOTS: AtomicUsize = AtomicUsize::new(0);
+
+/// Store the working tree of a worktree (untracked files included) as a tree
+/// object without touching its index, returning the tree id.
+///
+/// The temporary index starts as a copy of the worktree's own, so only files
+/// changed since it was last updated are hashed again.
+pub fn snapshot_tree(worktree_path: &Path) -> Result<String> {
+    let index = std::env::temp_dir().join(format!(
+        "vibe-snapshot-{}-{}.index",
+        std::process::id(),
+        SNAPSHOTS.fetch_add(1, Ordering::Relaxed)
+    ));
+    let real_index = Command::new("git")
         .current_dir(worktree_path)
-        .args(["status", "--porcelain"])
+        .args(["rev-parse", "--path-format=absolute", "--git-path", "index"])
         .output()
-        .ok()?;
-    let status = String::from_utf8_lossy(&status_output.stdout);
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| PathBuf::from(String::from_utf8_lossy(&output.stdout).trim()));
+    let seeded = real_index.is_some_and(|real| fs::copy(real, &index).is_ok());
+    let run = |args: &[&str]| -> Result<String> {
+        let output = Command::new("git")
+            .current_dir(worktree_path)
+            .env("GIT_INDEX_FILE", &index)
+            .args(args)
+            .output()
+            .context("Failed to run git")?;
+        if !output.status.success() {
+            bail!(
+                "Failed to snapshot changes: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+
+    let seed = if seeded {
+        Ok(String::new())
+    } else {
+        run(&["read-tree", "HEAD"])
+    };
+    let result = seed
+        .and_then(|_| run(&["add", "--all"]))
+        .and_then(|_| run(&["write-tree"]));
+
+    let _ = fs::remove_file(&index);
+    result
+}
+
+/// Get AI-generated summary of workspace changes using Claude CLI.
+///
+/// With `since`, a tree from `snapshot_tree`, only the changes made after
+/// it was taken are summarized.
+///
+/// Fails if there are no changes, or if Claude CLI fails or takes longer
+/// than `SUMMARY_TIMEOUT`; the error is short enough to show in a list.
+pub fn get_ai_summary(worktree_path: &Path, since: Option<&str>) -> Result<String> {
+    let git_output = |args: &[&str]| -> Result<String> {
+        let output = Command::new("git")
+            .current_dir(worktree_path)
+            .args(args)
+            .output()
+            .with_context(|| format!("git {} failed", args[0]))?;
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    };
+
+    // Get git diff stats and status
+    let (diff_stat, status) = match since {
+        Some(tree) => {
+            let current = snapshot_tree(worktree_path)?;
+            (
+                git_output(&["diff", "--stat", tree, &current])?,
+                git_output(&["diff", "--name-status", tree, &current])?,
+            )
+        }
+        None => (
+            git_output(&["diff", "--stat", "HEAD"])?,
+            git_output(&["status", "--porcelain"])?,
+        ),
+    };
 
     if diff_stat.trim().is_empty() && status.trim().is_empty() {
-        return None;
+        bail!("no changes");
     }
 
     let changes_context = format!("Status:\n{}\n\nDiff stats:\n{}", status, diff_stat);
@@ -363,16 +1070,36 @@ pub fn get_ai_summary(worktree_path: &Path) -> Option<String> {
         changes_context
     );
 
-    let output = Command::new("claude")
+    let mut child = Command::new("claude")
         .args(["--model", "haiku", "-p", &prompt])
-        .output()
-        .ok()?;
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("claude not found")?;
+
+    let deadline = std::time::Instant::now() + SUMMARY_TIMEOUT;
+    while child.try_wait()?.is_none() {
+        if std::time::Instant::now() > deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            bail!("timed out");
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
 
+    let output = child.wait_with_output()?;
     if !output.status.success() {
-        return None;
+        match output.status.code() {
+            Some(code) => bail!("claude exited with code {}", code),
+            None => bail!("claude was killed"),
+        }
     }
 
     let summary = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if summary.is_empty() {
+        bail!("empty answer");
+    }
 
     // Take only the first line if multi-line
     let first_line = summary.lines().next().unwrap_or(&summary).to_string();
@@ -380,9 +1107,9 @@ pub fn get_ai_summary(worktree_path: &Path) -> Option<String> {
     // Truncate to 60 chars if needed
     if first_line.chars().count() > 60 {
         let truncated: String = first_line.chars().take(57).collect();
-        Some(format!("{}...", truncated))
+        Ok(format!("{}...", truncated))
     } else {
-        Some(first_line)
+        Ok(first_line)
     }
 }
 
@@ -403,6 +1130,8 @@ pub struct WorktreeStatus {
     pub lines_deleted: usize,
     /// Number of untracked files
     pub untracked_files: usize,
+    /// Operation that was started but not finished, e.g. `rebase` or `merge`
+    pub in_progress_op: Option<String>,
 }
 
 impl WorktreeStatus {
@@ -412,8 +1141,30 @@ impl WorktreeStatus {
     }
 
     /// Returns true if the worktree is safe to delete (no local changes or orphaned)
+    ///
+    /// A worktree in the middle of a rebase or merge is never safe to delete.
     pub fn is_safe_to_delete(&self) -> bool {
-        self.is_orphaned || !self.has_local_changes()
+        self.is_orphaned || (!self.has_local_changes() && self.in_progress_op.is_none())
+    }
+
+    /// Compact `+N -N ↑N` summary of changes and unpushed commits.
+    ///
+    /// Untracked files count as added lines. Empty when there is nothing to report.
+    pub fn change_summary(&self) -> String {
+        let mut parts = Vec::new();
+
+        let total_added = self.lines_added + self.untracked_files;
+        if total_added > 0 {
+            parts.push(format!("+{}", total_added));
+        }
+        if self.lines_deleted > 0 {
+            parts.push(format!("-{}", self.lines_deleted));
+        }
+        if self.commits_ahead > 0 {
+            parts.push(format!("{}{}", style::theme().ahead, self.commits_ahead));
+        }
+
+        parts.join(" ")
     }
 }
 
@@ -438,12 +1189,50 @@ fn parse_numstat(output: &str) -> (usize, usize) {
     (added, deleted)
 }
 
+/// Find an unfinished rebase, merge, cherry-pick or revert in a git directory.
+fn in_progress_op(git_dir: &Path) -> Option<&'static str> {
+    const MARKERS: [(&str, &str); 5] = [
+        ("rebase-merge", "rebase"),
+        ("rebase-apply", "rebase"),
+        ("MERGE_HEAD", "merge"),
+        ("CHERRY_PICK_HEAD", "cherry-pick"),
+        ("REVERT_HEAD", "revert"),
+    ];
+    MARKERS
+        .iter()
+        .find(|(marker, _)| git_dir.join(marker).exists())
+        .map(|(_, op)| *op)
+}
+
+/// Detect an unfinished operation in a worktree.
+///
+/// Unmerged files without any operation marker (e.g. after a conflicting
+/// `git stash pop`) are reported as a merge.
+fn detect_in_progress_op(worktree_path: &Path) -> Option<String> {
+    let git_dir = Command::new("git")
+        .current_dir(worktree_path)
+        .args(["rev-parse", "--absolute-git-dir"])
+        .output()
+        .ok()?;
+    let git_dir = PathBuf::from(String::from_utf8_lossy(&git_dir.stdout).trim());
+    if let Some(op) = in_progress_op(&git_dir) {
+        return Some(op.to_string());
+    }
+
+    let unmerged = Command::new("git")
+        .current_dir(worktree_path)
+        .args(["diff", "--name-only", "--diff-filter=U"])
+        .output()
+        .ok()?;
+    (!unmerged.stdout.is_empty()).then(|| "merge".to_string())
+}
+
 /// Get the status of a worktree (uncommitted changes, unpushed commits).
 pub fn get_worktree_status(worktree_path: &Path) -> Result<WorktreeStatus> {
     let mut status = WorktreeStatus::default();
 
-    // Check if directory exists - if not, it's an orphaned worktree
-    if !worktree_path.exists() {
+    // Check if the checkout exists - if not, it's an orphaned worktree
+    if !worktree_path.join(".git").exists() {
         status.is_orphaned = true;
         return Ok(status);
     }
@@ -472,25 +1261,16 @@ pub fn get_worktree_status(worktree_path: &Path) -> Result<WorktreeStatus> {
     status.untracked_files = untracked_count;
     status.has_uncommitted =
         status.lines_added > 0 || status.lines_deleted > 0 || status.untracked_files > 0;
+    status.in_progress_op = detect_in_progress_op(worktree_path);
 
     // Check commits ahead of remote
     let branch = get_worktree_branch(worktree_path)?;
 
-    // First check if remote branch exists
-    let remote_check = Command::new("git")
-        .current_dir(worktree_path)
-        .args(["ls-remote", "--exit-code", "--heads", "origin", &branch])
-        .output()?;
-
-    if remote_check.status.success() {
+    if remote_branch_exists(worktree_path, &branch)? {
         // Remote branch exists, check how many commits ahead
         let commits_ahead = Command::new("git")
             .current_dir(worktree_path)
-            .args([
-                "rev-list",
-                "--count",
-                &format!("origin/{}..HEAD", branch),
-            ])
+            .args(["rev-list", "--count", &format!("origin/{}..HEAD", branch)])
             .output()?;
 
         let count: usize = String::from_utf8_lossy(&commits_ahead.stdout)
@@ -523,3 +1303,236 @@ pub fn get_worktree_status(worktree_path: &Path) -> Result<WorktreeStatus> {
 
     Ok(status)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn worktree(branch: &str) -> Worktree {
+        Worktree {
+            path: PathBuf::from("/repo").join(branch),
+            branch: branch.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_match_worktrees_multiple() {
+        let worktrees = vec![
+            worktree("claude/fix-login"),
+            worktree("claude/add-tests"),
+            worktree("claude/fix-logout"),
+        ];
+
+        let matches: Vec<_> = match_worktrees(&worktrees, "fix", MatchRank::Substring)
+            .into_iter()
+            .map(|wt| wt.branch)
+            .collect();
+        assert_eq!(matches, ["claude/fix-login", "claude/fix-logout"]);
+
+        assert_eq!(
+            match_worktrees(&worktrees, "tests", MatchRank::Substring).len(),
+            1
+        );
+        assert!(match_worktrees(&worktrees, "missing", MatchRank::Substring).is_empty());
+    }
+
+    #[test]
+    fn test_change_summary() {
+        let clean = WorktreeStatus::default();
+        assert_eq!(clean.change_summary(), "");
+
+        let only_untracked = WorktreeStatus {
+            untracked_files: 2,
+            ..Default::default()
+        };
+        assert_eq!(only_untracked.change_summary(), "+2");
+
+        let only_ahead = WorktreeStatus {
+            commits_ahead: 3,
+            ..Default::default()
+        };
+        assert_eq!(only_ahead.change_summary(), "↑3");
+
+        let everything = WorktreeStatus {
+            lines_added: 5,
+            untracked_files: 1,
+            lines_deleted: 4,
+            commits_ahead: 2,
+            ..Default::default()
+        };
+        assert_eq!(everything.change_summary(), "+6 -4 ↑2");
+    }
+
+    #[test]
+    fn test_plan_branch() {
+        let plan = |exists, checked_out, force| plan_branch("claude/x", exists, checked_out, force);
+
+        assert_eq!(plan(false, false, false).unwrap(), BranchPlan::Create);
+        assert_eq!(plan(false, false, true).unwrap(), BranchPlan::Create);
+        assert_eq!(plan(true, false, false).unwrap(), BranchPlan::Reuse);
+        assert_eq!(plan(true, false, true).unwrap(), BranchPlan::Recreate);
+        assert!(plan(true, true, false).is_err());
+        assert!(plan(true, true, true).is_err());
+    }
+
+    #[test]
+    fn test_in_progress_op() {
+        let git_dir = std::env::temp_dir().join(format!("vibe-test-op-{}", std::process::id()));
+        fs::create_dir_all(&git_dir).unwrap();
+        assert_eq!(in_progress_op(&git_dir), None);
+
+        fs::write(git_dir.join("MERGE_HEAD"), "").unwrap();
+        assert_eq!(in_progress_op(&git_dir), Some("merge"));
+
+        fs::create_dir(git_dir.join("rebase-merge")).unwrap();
+        assert_eq!(in_progress_op(&git_dir), Some("rebase"));
+
+        fs::remove_dir_all(&git_dir).unwrap();
+    }
+
+    #[test]
+    fn test_in_progress_op_is_not_safe_to_delete() {
+        let status = WorktreeStatus {
+            in_progress_op: Some("rebase".to_string()),
+            ..Default::default()
+        };
+        assert!(!status.is_safe_to_delete());
+        assert!(WorktreeStatus::default().is_safe_to_delete());
+    }
+
+    #[test]
+    fn test_rollback_only_deletes_created_branch() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let repo = std::env::temp_dir().join(format!("vibe-rollback-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&repo);
+        fs::create_dir_all(&repo).unwrap();
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .current_dir(&repo)
+                .args(["-c", "user.name=vibe", "-c", "user.email=vibe@example.com"])
+                .args(args)
+                .output()
+                .unwrap()
+                .status
+                .success()
+        };
+        assert!(git(&["init", "--quiet"]));
+        assert!(git(&["commit", "--quiet", "--allow-empty", "-m", "init"]));
+        assert!(git(&["branch", "claude/old"]));
+
+        // The branch is created and checked out before the hook fails
+        let hook = repo.join(".git/hooks/post-checkout");
+        fs::write(&hook, "#!/bin/sh\nexit 1\n").unwrap();
+        fs::set_permissions(&hook, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let new = repo.join("wt-new");
+        assert!(add_worktree(&repo, &new, "claude/new", &BranchPlan::Create, None).is_err());
+        assert!(!new.exists());
+        assert!(!git(&[
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            "refs/heads/claude/new"
+        ]));
+
+        let old = repo.join("wt-old");
+        assert!(add_worktree(&repo, &old, "claude/old", &BranchPlan::Reuse, None).is_err());
+        assert!(!old.exists());
+        assert!(git(&[
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            "refs/heads/claude/old"
+        ]));
+
+        fs::remove_dir_all(&repo).unwrap();
+    }
+
+    fn matched_branches(worktrees: &[Worktree], name: &str) -> Vec<String> {
+        match_worktrees(worktrees, name, MatchRank::Substring)
+            .into_iter()
+            .map(|wt| wt.branch)
+            .collect()
+    }
+
+    #[test]
+    fn test_filter_by_glob() {
+        let worktrees = vec![
+            worktree("claude/fix-login"),
+            worktree("claude/add-tests"),
+            worktree("claude/fix/logout"),
+            worktree("main"),
+        ];
+        let branches = |pattern| -> Vec<String> {
+            filter_by_glob(&worktrees, pattern)
+                .unwrap()
+                .into_iter()
+                .map(|wt| wt.branch)
+                .collect()
+        };
+
+        assert_eq!(
+            branches("claude/fix*"),
+            ["claude/fix-login", "claude/fix/logout"]
+        );
+        assert_eq!(branches("*tests"), ["claude/add-tests"]);
+        assert_eq!(branches("main"), ["main"]);
+        assert!(branches("fix*").is_empty());
+        assert_eq!(branches("claude/{add,fix}-*").len(), 2);
+
+        let err = filter_by_glob(&worktrees, "claude/[fix").err().unwrap();
+        assert_eq!(err.to_string(), "Invalid --match pattern 'claude/[fix'");
+    }
+
+    #[test]
+    fn test_match_worktrees_prefers_exact() {
+        let worktrees = vec![worktree("claude/fix-login"), worktree("claude/fix")];
+
+        assert_eq!(matched_branches(&worktrees, "fix"), ["claude/fix"]);
+        assert_eq!(matched_branches(&worktrees, "claude/fix"), ["claude/fix"]);
+    }
+
+    #[test]
+    fn test_match_worktrees_prefers_prefix_over_substring() {
+        let worktrees = vec![worktree("claude/hotfix"), worktree("claude/fixup")];
+
+        assert_eq!(matched_branches(&worktrees, "fix"), ["claude/fixup"]);
+        assert_eq!(matched_branches(&worktrees, "otfi"), ["claude/hotfix"]);
+    }
+
+    #[test]
+    fn test_match_worktrees_strict() {
+        let worktrees = vec![worktree("claude/hotfix"), worktree("claude/fixup")];
+        let strict = |name| match_worktrees(&worktrees, name, MatchRank::Prefix);
+
+        assert_eq!(strict("fix").len(), 1);
+        assert_eq!(strict("claude/hot").len(), 1);
+        // Names inside a branch or path only match for read-only commands
+        assert!(strict("otfi").is_empty());
+        assert!(strict("repo").is_empty());
+        // Parts of the worktree prefix are shared by every session
+        for name in ["c", "claude", "claude/", ""] {
+            assert!(strict(name).is_empty(), "'{}' matched", name);
+        }
+    }
+
+    #[test]
+    fn test_relocated_link() {
+        let exists =
+            |p: &Path| p == Path::new("/new/ws/claude/abc/.git") || p == Path::new("/new/ws/.git");
+        assert_eq!(
+            relocated_link(
+                Path::new("/new/ws"),
+                Path::new("/old/ws/claude/abc/.git"),
+                exists
+            ),
+            Some(PathBuf::from("/new/ws/claude/abc/.git"))
+        );
+        // Never the workspace's own .git file
+        assert_eq!(
+            relocated_link(Path::new("/new/ws"), Path::new("/old/ws/gone/.git"), exists),
+            None
+        );
+    }
+}
@@ -0,0 +1,280 @@
+//! Lock that serializes commands which modify worktree state.
+//!
+//! The lock is a `vibe.lock` file in the `.bare` directory, created
+//! exclusively and holding the PID of its owner. A lock left behind by a
+//! process that is no longer running is treated as stale and taken over.
+//!
+//! By default a held lock fails the command immediately; the global `--wait`
+//! flag makes it retry until the lock is free or a timeout expires.
+//!
+//! Image builds use a separate lock per image name under
+//! `~/.local/state/vibe/locks`, so concurrent commands building the same tag
+//! take turns instead of racing `docker build`. Build locks always wait.
+
+use anyhow::{Context, Result, bail};
+use nix::errno::Errno;
+use nix::sys::signal;
+use nix::unistd::Pid;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::paths;
+
+/// Name of the lock file inside the bare repository
+const LOCK_FILE: &str = "vibe.lock";
+
+/// Delay between attempts while waiting for the lock
+const RETRY_INTERVAL_MS: u64 = 200;
+
+/// What to do when the lock is held by another command
+#[derive(Clone, Copy)]
+pub enum Wait {
+    /// Fail immediately
+    FailFast,
+    /// Retry until the lock is free
+    Forever,
+    /// Retry until the lock is free or the timeout expires
+    Timeout(Duration),
+}
+
+/// Wait policy chosen on the command line
+static WAIT: OnceLock<Wait> = OnceLock::new();
+
+/// Set how `acquire` behaves when the lock is held. Call once at startup.
+pub fn set_wait(wait: Wait) {
+    let _ = WAIT.set(wait);
+}
+
+/// A held command lock, released when dropped
+pub struct CommandLock {
+    path: PathBuf,
+}
+
+impl Drop for CommandLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Acquire the command lock for the repository at `bare_path`.
+///
+/// If another live vibe process holds it, this fails or waits according to
+/// the policy set with `set_wait`.
+pub fn acquire(bare_path: &Path) -> Result<CommandLock> {
+    let wait = WAIT.get().copied().unwrap_or(Wait::FailFast);
+    acquire_at(
+        &bare_path.join(LOCK_FILE),
+        wait,
+        "Waiting for another vibe command to finish...",
+    )
+}
+
+/// Acquire the build lock for `image_name`, waiting while another vibe
+/// process is building the same image.
+pub fn acquire_build(image_name: &str) -> Result<CommandLock> {
+    let dir = paths::state_dir()?.join("locks");
+    fs::create_dir_all(&dir).context("Failed to create lock directory")?;
+
+    acquire_at(
+        &dir.join(build_lock_file(image_name)),
+        Wait::Forever,
+        &format!("Waiting for another build of {} to finish...", image_name),
+    )
+}
+
+/// File name of the build lock for an image, e.g. `registry/img:tag` becomes
+/// `build-registry_img_tag.lock`.
+fn build_lock_file(image_name: &str) -> String {
+    let name: String = image_name
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '-' => c,
+            _ => '_',
+        })
+        .collect();
+    format!("build-{}.lock", name)
+}
+
+/// Acquire the lock file at `path`, announcing `waiting` once if it has to wait.
+fn acquire_at(path: &Path, wait: Wait, waiting: &str) -> Result<CommandLock> {
+    let start = Instant::now();
+    let mut announced = false;
+
+    loop {
+        if let Some(lock) = try_acquire(path)? {
+            return Ok(lock);
+        }
+
+        match wait {
+            Wait::FailFast => bail!("{}", held_message(path)),
+            Wait::Timeout(timeout) if start.elapsed() >= timeout => {
+                bail!(
+                    "Timed out after {}s waiting for the lock\n{}",
+                    timeout.as_secs(),
+                    held_message(path)
+                );
+            }
+            _ => {}
+        }
+
+        if !announced {
+            eprintln!("{}", waiting);
+            announced = true;
+        }
+        thread::sleep(Duration::from_millis(RETRY_INTERVAL_MS));
+    }
+}
+
+/// Try to create the lock file, taking over a stale one.
+///
+/// Returns None if the lock is held by a running process.
+fn try_acquire(path: &Path) -> Result<Option<CommandLock>> {
+    loop {
+        match OpenOptions::new().write(true).create_new(true).open(path) {
+            Ok(mut file) => {
+                let lock = CommandLock {
+                    path: path.to_path_buf(),
+                };
+                writeln!(file, "{}", std::process::id()).context("Failed to write lock file")?;
+                return Ok(Some(lock));
+            }
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                if !is_stale(path) {
+                    return Ok(None);
+                }
+                // Whoever removes it, the next create_new decides who gets it
+                remove_stale(path)?;
+            }
+            Err(e) => return Err(e).context("Failed to create lock file"),
+        }
+    }
+}
+
+/// Remove a lock file found to be stale.
+///
+/// Another process may take the lock over between the check and the
+/// removal, so the file is moved aside first and only deleted if it is
+/// still the stale one. Otherwise it is linked back into place.
+fn remove_stale(path: &Path) -> Result<()> {
+    let moved = path.with_extension(format!("stale-{}", std::process::id()));
+    match fs::rename(path, &moved) {
+        Ok(()) => {}
+        // Moved aside by another process
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e).context("Failed to remove stale lock file"),
+    }
+
+    if !is_stale(&moved) {
+        let _ = fs::hard_link(&moved, path);
+    }
+    let _ = fs::remove_file(&moved);
+    Ok(())
+}
+
+/// Check whether the lock file belongs to a process that is no longer running.
+///
+/// A file without a readable PID may still be in the middle of being written,
+/// so it is not considered stale.
+fn is_stale(path: &Path) -> bool {
+    let Some(pid) = read_holder(path) else {
+        return false;
+    };
+
+    matches!(signal::kill(Pid::from_raw(pid), None), Err(Errno::ESRCH))
+}
+
+/// Read the PID stored in a lock file.
+fn read_holder(path: &Path) -> Option<i32> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// Error message for a lock held by another process.
+fn held_message(path: &Path) -> String {
+    let holder = match read_holder(path) {
+        Some(pid) => format!(" (pid {})", pid),
+        None => String::new(),
+    };
+
+    format!(
+        "Another vibe command is running{}\n\
+         If that's not the case, remove {}",
+        holder,
+        path.display()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("vibe-lock-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_lock_is_exclusive_until_dropped() {
+        let dir = temp_dir("exclusive");
+
+        let lock = acquire(&dir).unwrap();
+        assert!(acquire(&dir).is_err());
+
+        drop(lock);
+        assert!(!dir.join(LOCK_FILE).exists());
+        assert!(acquire(&dir).is_ok());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_build_lock_file() {
+        assert_eq!(
+            build_lock_file("claude-vibe-setup"),
+            "build-claude-vibe-setup.lock"
+        );
+        assert_eq!(
+            build_lock_file("registry.io/team/img:1.0"),
+            "build-registry.io_team_img_1.0.lock"
+        );
+    }
+
+    #[test]
+    fn test_stale_lock_is_taken_over() {
+        let dir = temp_dir("stale");
+
+        // PIDs are capped well below i32::MAX, so this process can't exist
+        fs::write(dir.join(LOCK_FILE), format!("{}\n", i32::MAX)).unwrap();
+        let lock = acquire(&dir).unwrap();
+        assert_eq!(
+            read_holder(&dir.join(LOCK_FILE)),
+            Some(std::process::id() as i32)
+        );
+
+        drop(lock);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_remove_stale_keeps_a_taken_over_lock() {
+        let dir = temp_dir("takeover");
+        let path = dir.join(LOCK_FILE);
+
+        // Taken over by this (running) process since it was found stale
+        fs::write(&path, format!("{}\n", std::process::id())).unwrap();
+        remove_stale(&path).unwrap();
+        assert_eq!(read_holder(&path), Some(std::process::id() as i32));
+
+        fs::write(&path, format!("{}\n", i32::MAX)).unwrap();
+        remove_stale(&path).unwrap();
+        assert!(!path.exists());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -4,15 +4,25 @@
 //! enabling parallel Claude Code sessions without branch conflicts.
 
 mod commands;
+mod config;
 mod docker;
+mod du;
 mod git;
+mod lock;
+mod meta;
+mod paths;
 mod style;
+mod summary;
+mod trash;
 mod tui;
+mod util;
 
-use anyhow::Result;
-use clap::{Parser, Subcommand};
+use anyhow::{Context, Result};
+use clap::{ArgGroup, Parser, Subcommand};
+use std::path::PathBuf;
+use std::time::Duration;
 
-/// Worktree prefix for Claude sessions
+/// Worktree prefix for Claude sessions, unless configured otherwise
 pub const WORKTREE_PREFIX: &str = "claude/";
 
 #[derive(Parser)]
@@ -20,6 +30,40 @@ pub const WORKTREE_PREFIX: &str = "claude/";
 #[command(about = "Claude Code session manager with git worktrees")]
 #[command(version)]
 struct Cli {
+    /// Wait for other vibe commands to finish instead of failing,
+    /// optionally for at most this many seconds
+    #[arg(long, global = true, value_name = "SECONDS", num_args = 0..=1, require_equals = true)]
+    wait: Option<Option<u64>>,
+
+    /// Keep colors even when output is not a terminal (also CLICOLOR_FORCE)
+    #[arg(long, global = true)]
+    force_color: bool,
+
+    /// Don't summarize changes with Claude (see the `summaries` setting)
+    #[arg(long, global = true)]
+    no_summaries: bool,
+
+    /// Never use colors (also NO_COLOR)
+    #[arg(long, global = true, conflicts_with = "force_color")]
+    no_color: bool,
+
+    /// Don't access the network; compare against locally cached remote refs
+    #[arg(long, global = true)]
+    offline: bool,
+
+    /// Use ASCII instead of Unicode glyphs for status icons (the default when
+    /// the locale isn't UTF-8)
+    #[arg(long, global = true)]
+    ascii: bool,
+
+    /// Don't pass the host time zone and locale into containers
+    #[arg(long, global = true)]
+    no_host_locale: bool,
+
+    /// Fail instead of warning when a Docker build context is over 500 MiB
+    #[arg(long, global = true)]
+    strict: bool,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -32,45 +76,525 @@ enum Commands {
         url: String,
         /// Directory name (defaults to repository name)
         directory: Option<String>,
+        /// How to show Claude's output during setup
+        #[arg(long, value_enum, default_value_t)]
+        output_format: docker::OutputFormat,
+        /// Don't show clone progress
+        #[arg(short, long)]
+        quiet: bool,
+        /// Start a first session in a new worktree once setup is done
+        #[arg(long)]
+        start: bool,
+        /// Prompt to run setup with instead of the configured one
+        #[arg(long)]
+        setup_prompt: Option<String>,
+        /// Read the setup prompt from a file
+        #[arg(long, value_name = "FILE", conflicts_with = "setup_prompt")]
+        setup_prompt_file: Option<PathBuf>,
     },
 
     /// Create a new session with a fresh git worktree
-    New,
+    New {
+        /// Name for the session, random if omitted
+        name: Option<String>,
+        /// Recreate the branch if it already exists instead of reusing it
+        #[arg(short, long)]
+        force: bool,
+        /// Run the prompt non-interactively, streaming output, then exit
+        #[arg(long, requires = "prompt")]
+        headless: bool,
+        /// Prompt to start the session with
+        #[arg(short, long)]
+        prompt: Option<String>,
+        /// How to show Claude's output in headless mode
+        #[arg(long, value_enum, default_value_t, requires = "headless")]
+        output_format: docker::OutputFormat,
+        /// Mount a file read-only outside /workspace for this session only
+        #[arg(long, value_name = "HOST:CONTAINER", value_parser = docker::parse_secret_mount)]
+        mount_secret: Vec<docker::SecretMount>,
+        /// Set an environment variable for this session, kept for `continue`
+        /// (a bare KEY passes the host's value, if set)
+        #[arg(short, long, value_name = "KEY[=VALUE]", value_parser = docker::parse_env_var)]
+        env: Vec<String>,
+        /// Reuse the environment recorded for another session
+        #[arg(long, value_name = "WORKTREE")]
+        env_from: Option<String>,
+        /// Branch or ref to start from instead of the current HEAD
+        #[arg(long, value_name = "REF")]
+        base: Option<String>,
+        /// Start with the uncommitted changes of another worktree
+        #[arg(long, value_name = "WORKTREE")]
+        copy_from: Option<String>,
+        /// Use the image already built for another session instead of building one
+        #[arg(long, value_name = "WORKTREE")]
+        image_from: Option<String>,
+        /// Pass an extra argument to `docker run` (repeatable, e.g. --docker-arg=--gpus=all)
+        #[arg(long = "docker-arg", value_name = "ARG", allow_hyphen_values = true)]
+        docker_arg: Vec<String>,
+        /// When the session exits, offer to commit and push unsaved work
+        #[arg(long, conflicts_with = "headless")]
+        keep_on_exit: bool,
+    },
 
     /// Attach to an existing session
     Continue {
-        /// Name of the worktree to continue
+        /// Name of the worktree to continue, or its position in `vibe status`
         worktree_name: Option<String>,
+        /// Continue the worktree containing the current directory
+        /// (falls back to the picker if there is none)
+        #[arg(long, conflicts_with = "worktree_name")]
+        cwd: bool,
+        /// Prompt to resume the session with
+        #[arg(short, long)]
+        prompt: Option<String>,
+        /// Add the environment recorded for another session to this one
+        #[arg(long, value_name = "WORKTREE")]
+        env_from: Option<String>,
+        /// Use the session's image as-is, without rebuilding it
+        #[arg(long)]
+        no_build: bool,
+        /// Use the image already built for another session instead of this one's
+        #[arg(long, value_name = "WORKTREE", conflicts_with = "no_build")]
+        image_from: Option<String>,
+        /// Pass an extra argument to `docker run` (repeatable, e.g. --docker-arg=--gpus=all)
+        #[arg(long = "docker-arg", value_name = "ARG", allow_hyphen_values = true)]
+        docker_arg: Vec<String>,
     },
 
     /// Remove worktrees that are synced with remote or unused
+    #[command(group(ArgGroup::new("forceable").args(["older_than", "interactive"]).multiple(true)))]
     Cleanup {
         /// Interactive mode: select worktrees to delete with TUI
         #[arg(short, long)]
         interactive: bool,
+        /// Only consider worktrees whose branch matches this glob
+        #[arg(long = "match", value_name = "PATTERN")]
+        pattern: Option<String>,
+        /// Also remove worktrees with no activity for this long (e.g. 7d, 12h)
+        #[arg(long, value_name = "DURATION", value_parser = util::parse_duration)]
+        older_than: Option<Duration>,
+        /// With --older-than, also remove inactive worktrees that have local changes;
+        /// with -i, allow deleting worktrees in the middle of a rebase or merge
+        #[arg(short, long, requires = "forceable")]
+        force: bool,
+        /// Remove without asking for confirmation
+        #[arg(short, long, conflicts_with = "interactive")]
+        yes: bool,
+        /// Only remove claude-vibe-* images whose worktree no longer exists
+        #[arg(long, conflicts_with_all = ["interactive", "pattern", "older_than"])]
+        images_only: bool,
+        /// List what would be removed without removing anything
+        #[arg(short = 'n', long, conflicts_with_all = ["interactive", "yes"])]
+        dry_run: bool,
+        /// Ask before removing each worktree instead of once for all (--yes skips)
+        #[arg(long, conflicts_with_all = ["interactive", "dry_run", "images_only"])]
+        confirm_each: bool,
+        /// Only remove worktrees whose directory is missing (no network checks)
+        #[arg(long, conflicts_with_all = ["interactive", "older_than", "images_only", "confirm_each"])]
+        orphans_only: bool,
     },
 
     /// Initialize Dockerfile.vibes for a project
-    Setup,
+    Setup {
+        /// How to show Claude's output
+        #[arg(long, value_enum, default_value_t)]
+        output_format: docker::OutputFormat,
+        /// Write a default Dockerfile.vibes instead of asking Claude (no Docker needed)
+        #[arg(long)]
+        template: bool,
+        /// With --template, overwrite an existing Dockerfile.vibes
+        #[arg(short, long, requires = "template")]
+        force: bool,
+        /// Prompt to run setup with instead of the configured one
+        #[arg(long, conflicts_with = "template")]
+        prompt: Option<String>,
+        /// Read the setup prompt from a file
+        #[arg(long, value_name = "FILE", conflicts_with_all = ["prompt", "template"])]
+        prompt_file: Option<PathBuf>,
+    },
 
     /// Show status of all worktrees
     #[command(visible_aliases = ["stat", "ls"])]
-    Status,
+    Status {
+        /// Only show worktrees whose branch matches this glob
+        #[arg(long = "match", value_name = "PATTERN")]
+        pattern: Option<String>,
+        /// Include worktrees not managed by vibe
+        #[arg(short, long)]
+        all: bool,
+        /// Print stable tab-separated lines for scripts:
+        /// branch, state, added, deleted, ahead, behind
+        #[arg(long, conflicts_with = "json")]
+        porcelain: bool,
+        /// Print a JSON array of worktree statuses for scripts
+        #[arg(long)]
+        json: bool,
+        /// Order worktrees by name, activity or changed lines
+        #[arg(long, value_enum)]
+        sort: Option<commands::status::SortKey>,
+        /// Reverse the order of the listing
+        #[arg(short, long)]
+        reverse: bool,
+        /// Show how much disk space each worktree uses
+        #[arg(long, conflicts_with = "porcelain")]
+        size: bool,
+        /// With --size, only count files this many directories deep
+        #[arg(long, value_name = "N", requires = "size")]
+        depth: Option<usize>,
+        /// Summarize changes again instead of using cached summaries
+        #[arg(long, conflicts_with = "porcelain")]
+        refresh: bool,
+        /// Only summarize what changed since the last incremental status or session
+        #[arg(long, visible_alias = "since-last", conflicts_with_all = ["porcelain", "refresh"])]
+        incremental: bool,
+        /// Fetch, then only list worktrees behind their base branch, furthest first
+        #[arg(
+            long,
+            visible_alias = "needs-rebase",
+            conflicts_with_all = ["porcelain", "json", "sort", "size", "refresh", "incremental"]
+        )]
+        behind_only: bool,
+    },
+
+    /// Show how worktrees branch off one another
+    Stack,
+
+    /// Show a session's uncommitted changes
+    Diff {
+        /// Name of the worktree (partial names work)
+        worktree_name: String,
+        /// Only show which files changed, with counts of changed lines
+        #[arg(long)]
+        stat: bool,
+        /// Show everything changed since the base branch, committed or not
+        #[arg(long)]
+        base: bool,
+    },
+
+    /// Check for common setup problems
+    Doctor {
+        /// Repair the problems that can be fixed automatically
+        #[arg(long)]
+        fix: bool,
+        /// Apply fixes without asking for confirmation
+        #[arg(short, long, requires = "fix")]
+        yes: bool,
+    },
+
+    /// Save a worktree as a git bundle (plus uncommitted changes) and remove it
+    Archive {
+        /// Name of the worktree to archive, or a unique prefix of it
+        worktree_name: String,
+    },
+
+    /// Recreate a worktree from an archive (lists archives without a name)
+    Unarchive {
+        /// Name of the archived worktree to restore
+        name: Option<String>,
+    },
+
+    /// Restore the worktrees removed by the last cleanup
+    Undo,
+
+    /// Show the effective settings, or read and change them in the config file
+    Config {
+        #[command(subcommand)]
+        action: Option<ConfigAction>,
+    },
+
+    /// Show which image a session would use, without building anything
+    Image {
+        /// Worktree to resolve for (defaults to the current one)
+        worktree_name: Option<String>,
+    },
+
+    /// Start a session again with the options it was last launched with
+    Relaunch {
+        /// Name of the worktree to relaunch
+        worktree_name: String,
+    },
+
+    /// Rename a worktree and its branch
+    Rename {
+        /// Name of the worktree to rename, or a unique prefix of it
+        old: String,
+        /// New name, with or without the claude/ prefix
+        new: String,
+    },
+
+    /// Fix the links between the repository and its worktrees after moving the workspace
+    Repair,
+
+    /// Remove a worktree and its branch (restorable with undo)
+    Rm {
+        /// Name of the worktree to remove, or a unique prefix of it
+        worktree_name: String,
+        /// Don't ask before removing a worktree with local changes
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Show CPU and memory usage of running sessions
+    Top,
+
+    /// Open a worktree in your editor ($EDITOR, $VISUAL or VS Code)
+    Open {
+        /// Name of the worktree to open (shows the picker if omitted)
+        worktree_name: Option<String>,
+    },
+
+    /// Push a worktree's branch to origin, setting its upstream if needed
+    Push {
+        /// Name of the worktree to push (shows the picker if omitted)
+        worktree_name: Option<String>,
+    },
+
+    /// Run a command in a worktree's container, without Claude
+    Exec {
+        /// Name of the worktree to run in (partial names work)
+        worktree_name: String,
+        /// Command to run, after `--`
+        #[arg(last = true, required = true, value_name = "COMMAND")]
+        command: Vec<String>,
+    },
+}
+
+/// Actions of the `config` command
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Print the path of the config file
+    Path,
+
+    /// Print the value of a setting
+    Get {
+        /// Setting name, e.g. default_image
+        key: String,
+    },
+
+    /// Change a setting, validating its value
+    Set {
+        /// Setting name, e.g. default_image
+        key: String,
+        /// New value (lists are comma-separated)
+        value: String,
+    },
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
 
+    style::set_force_color(cli.force_color);
+    style::set_no_color(cli.no_color);
+    summary::set_disabled(cli.no_summaries);
+    git::set_offline(cli.offline);
+    docker::set_host_locale(!cli.no_host_locale);
+    docker::set_strict_context(cli.strict);
+
+    // The config commands load the file themselves, so a broken one can be fixed
+    if !matches!(cli.command, Some(Commands::Config { .. })) {
+        config::load()?;
+    }
+
+    let mut theme = if cli.ascii || !style::unicode_supported() {
+        style::Theme::ascii()
+    } else {
+        style::Theme::unicode()
+    };
+    let config = config::get();
+    if let Some(spinner) = config.spinner {
+        theme.spinner = spinner.frames();
+    }
+    theme.spinner_interval = Duration::from_millis(config.spinner_interval_ms);
+    // Both were checked when the config was loaded
+    if let Some(icons) = &config.theme_icons {
+        theme = theme.with_icons(icons)?;
+    }
+    if let Some(colors) = &config.theme_colors {
+        theme = theme.with_colors(colors)?;
+    }
+    style::set_theme(match std::env::var("VIBE_COLORS") {
+        Ok(spec) => theme.with_colors(&spec).context("Invalid VIBE_COLORS")?,
+        Err(_) => theme,
+    });
+
+    lock::set_wait(match cli.wait {
+        None => lock::Wait::FailFast,
+        Some(None) => lock::Wait::Forever,
+        Some(Some(secs)) => lock::Wait::Timeout(Duration::from_secs(secs)),
+    });
+
     match cli.command {
-        Some(Commands::Clone { url, directory }) => commands::clone::run(&url, directory),
-        Some(Commands::New) => commands::new::run(),
-        Some(Commands::Continue { worktree_name }) => {
-            commands::continue_session::run(worktree_name).await
+        Some(Commands::Clone {
+            url,
+            directory,
+            output_format,
+            quiet,
+            start,
+            setup_prompt,
+            setup_prompt_file,
+        }) => commands::clone::run(
+            &url,
+            directory,
+            output_format,
+            quiet,
+            start,
+            commands::setup::PromptSource {
+                prompt: setup_prompt,
+                file: setup_prompt_file,
+            },
+        ),
+        Some(Commands::New {
+            name,
+            force,
+            headless,
+            prompt,
+            output_format,
+            mount_secret,
+            env,
+            env_from,
+            base,
+            copy_from,
+            image_from,
+            docker_arg,
+            keep_on_exit,
+        }) => commands::new::run(commands::new::NewOptions {
+            name,
+            force,
+            headless,
+            prompt,
+            format: output_format,
+            secrets: mount_secret,
+            env,
+            env_from,
+            base,
+            copy_from,
+            image_from,
+            docker_args: docker_arg,
+            keep_on_exit,
+        }),
+        Some(Commands::Continue {
+            worktree_name,
+            cwd,
+            prompt,
+            env_from,
+            no_build,
+            image_from,
+            docker_arg,
+        }) => {
+            commands::continue_session::run(commands::continue_session::ContinueOptions {
+                worktree_name,
+                cwd,
+                prompt,
+                env_from,
+                no_build,
+                image_from,
+                docker_args: docker_arg,
+            })
+            .await
+        }
+        Some(Commands::Cleanup {
+            interactive,
+            pattern,
+            older_than,
+            force,
+            yes,
+            images_only,
+            dry_run,
+            confirm_each,
+            orphans_only,
+        }) => {
+            commands::cleanup::run(commands::cleanup::CleanupOptions {
+                interactive,
+                pattern,
+                older_than,
+                force,
+                yes,
+                images_only,
+                dry_run,
+                confirm_each,
+                orphans_only,
+            })
+            .await
+        }
+        Some(Commands::Setup {
+            output_format,
+            template,
+            force,
+            prompt,
+            prompt_file,
+        }) => {
+            if template {
+                commands::setup::run_template(force)
+            } else {
+                commands::setup::run(
+                    output_format,
+                    commands::setup::PromptSource {
+                        prompt,
+                        file: prompt_file,
+                    },
+                )
+            }
+        }
+        Some(Commands::Status {
+            pattern,
+            all,
+            porcelain,
+            json,
+            sort,
+            reverse,
+            size,
+            depth,
+            refresh,
+            incremental,
+            behind_only,
+        }) => {
+            commands::status::run(commands::status::StatusOptions {
+                pattern,
+                all,
+                porcelain,
+                json,
+                sort,
+                reverse,
+                size,
+                depth,
+                refresh,
+                behind_only,
+                incremental,
+            })
+            .await
         }
-        Some(Commands::Cleanup { interactive }) => commands::cleanup::run(interactive).await,
-        Some(Commands::Setup) => commands::setup::run(),
-        Some(Commands::Status) => commands::status::run().await,
+        Some(Commands::Stack) => commands::stack::run(),
+        Some(Commands::Diff {
+            worktree_name,
+            stat,
+            base,
+        }) => commands::diff::run(&worktree_name, stat, base),
+        Some(Commands::Doctor { fix, yes }) => commands::doctor::run(fix, yes),
+        Some(Commands::Archive { worktree_name }) => commands::archive::run(&worktree_name),
+        Some(Commands::Unarchive { name }) => commands::archive::run_unarchive(name.as_deref()),
+        Some(Commands::Undo) => commands::undo::run(),
+        Some(Commands::Config { action }) => match action {
+            None => commands::config::run_show(),
+            Some(ConfigAction::Path) => commands::config::run_path(),
+            Some(ConfigAction::Get { key }) => commands::config::run_get(&key),
+            Some(ConfigAction::Set { key, value }) => commands::config::run_set(&key, &value),
+        },
+        Some(Commands::Image { worktree_name }) => commands::image::run(worktree_name.as_deref()),
+        Some(Commands::Relaunch { worktree_name }) => commands::relaunch::run(&worktree_name),
+        Some(Commands::Rename { old, new }) => commands::rename::run(&old, &new),
+        Some(Commands::Repair) => commands::repair::run(),
+        Some(Commands::Rm {
+            worktree_name,
+            force,
+        }) => commands::rm::run(&worktree_name, force),
+        Some(Commands::Top) => commands::top::run().await,
+        Some(Commands::Open { worktree_name }) => commands::open::run(worktree_name).await,
+        Some(Commands::Push { worktree_name }) => commands::push::run(worktree_name).await,
+        Some(Commands::Exec {
+            worktree_name,
+            command,
+        }) => commands::exec::run(&worktree_name, &command),
         None => {
             // Default to help
             use clap::CommandFactory;
@@ -0,0 +1,324 @@
+//! Per-worktree metadata stored in a `.vibe/meta.json` sidecar file.
+//!
+//! The `.vibe` directory carries its own `.gitignore` so vibe state never
+//! shows up as untracked changes in the worktree.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::docker;
+
+/// Directory inside each worktree holding vibe state
+pub const META_DIR: &str = ".vibe";
+
+/// Metadata file name inside the `.vibe` directory
+const META_FILE: &str = "meta.json";
+
+/// Metadata recorded for a worktree created by vibe
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Meta {
+    /// When the worktree was created, in seconds since the Unix epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<u64>,
+    /// Prompt the session was first started with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    /// Branch the worktree was created from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_branch: Option<String>,
+    /// Extra environment variables for the session's containers, see
+    /// `redact_env` for how they are stored
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub env: Vec<String>,
+    /// How the session's container was last started
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub launch: Option<Launch>,
+    /// Snapshot of the worktree when it was last looked at, see `summary::mark`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary_marker: Option<String>,
+}
+
+impl Meta {
+    /// Metadata for a worktree created now.
+    pub fn new() -> Meta {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        Meta {
+            created_at: Some(now.as_secs()),
+            ..Default::default()
+        }
+    }
+
+    /// When the worktree was created, if known.
+    pub fn created(&self) -> Option<SystemTime> {
+        self.created_at
+            .map(|secs| UNIX_EPOCH + Duration::from_secs(secs))
+    }
+}
+
+/// When the worktree at `worktree_path` was created, if recorded.
+pub fn created(worktree_path: &Path) -> Option<SystemTime> {
+    read_meta(worktree_path)?.created()
+}
+
+/// Options of a container launch, recorded so `vibe relaunch` can repeat it
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Launch {
+    /// Image the container ran
+    pub image: String,
+    /// Prompt Claude was started with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    /// Whether the prompt ran non-interactively
+    #[serde(default)]
+    pub headless: bool,
+    /// How headless output was shown
+    #[serde(default)]
+    pub format: docker::OutputFormat,
+    /// Files mounted read-only into the container
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub secrets: Vec<docker::SecretMount>,
+    /// Extra arguments passed to `docker run`, see `redact_docker_args`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub docker_args: Vec<String>,
+    /// Offer to commit and push unsaved work once the session exits
+    #[serde(default)]
+    pub keep_on_exit: bool,
+}
+
+/// Parts of variable names that suggest a secret value
+const SENSITIVE_NAMES: [&str; 7] = [
+    "TOKEN",
+    "SECRET",
+    "PASSWORD",
+    "PASSWD",
+    "KEY",
+    "CREDENTIAL",
+    "AUTH",
+];
+
+/// Prepare `KEY=VALUE` variables for storing in metadata.
+///
+/// Variables whose names look sensitive (e.g. `GITHUB_TOKEN`) are stored as
+/// a bare name, so later launches take the value from the host environment
+/// instead of from a plain-text file.
+pub fn redact_env(env: &[String]) -> Vec<String> {
+    env.iter().map(|var| redact_var(var)).collect()
+}
+
+/// A `KEY=VALUE` variable as stored by `redact_env`.
+fn redact_var(var: &str) -> String {
+    let name = var.split('=').next().unwrap_or(var);
+    let upper = name.to_ascii_uppercase();
+    if SENSITIVE_NAMES.iter().any(|s| upper.contains(s)) {
+        name.to_string()
+    } else {
+        var.to_string()
+    }
+}
+
+/// Prepare extra `docker run` arguments for storing in metadata.
+///
+/// Variables set with `-e` or `--env` are stored like `redact_env` does.
+pub fn redact_docker_args(args: &[String]) -> Vec<String> {
+    let mut redacted = Vec::with_capacity(args.len());
+    let mut env_value_next = false;
+    for arg in args {
+        if env_value_next {
+            redacted.push(redact_var(arg));
+            env_value_next = false;
+        } else if arg == "-e" || arg == "--env" {
+            redacted.push(arg.clone());
+            env_value_next = true;
+        } else if let Some(var) = arg.strip_prefix("--env=") {
+            redacted.push(format!("--env={}", redact_var(var)));
+        } else if let Some(var) = arg.strip_prefix("-e").filter(|_| !arg.starts_with("--")) {
+            // `-eKEY=VALUE` or `-e=KEY=VALUE`
+            let (sep, var) = match var.strip_prefix('=') {
+                Some(var) => ("=", var),
+                None => ("", var),
+            };
+            redacted.push(format!("-e{}{}", sep, redact_var(var)));
+        } else {
+            redacted.push(arg.clone());
+        }
+    }
+    redacted
+}
+
+/// Combine `KEY=VALUE` lists, with `overrides` replacing variables of the
+/// same name in `base`.
+pub fn merge_env(base: &[String], overrides: &[String]) -> Vec<String> {
+    let name = |var: &str| var.split('=').next().unwrap_or(var).to_string();
+    let mut env: Vec<String> = base
+        .iter()
+        .filter(|var| !overrides.iter().any(|o| name(o) == name(var)))
+        .cloned()
+        .collect();
+    env.extend(overrides.iter().cloned());
+    env
+}
+
+/// Get the `.vibe` directory for a worktree.
+pub fn meta_dir(worktree_path: &Path) -> PathBuf {
+    worktree_path.join(META_DIR)
+}
+
+/// Read metadata for a worktree.
+///
+/// Returns None for worktrees without a (valid) meta file, e.g. ones created
+/// before metadata was recorded.
+pub fn read_meta(worktree_path: &Path) -> Option<Meta> {
+    let content = fs::read_to_string(meta_dir(worktree_path).join(META_FILE)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Record how a worktree's container was started, keeping other metadata.
+///
+/// Secrets in its docker arguments are redacted (see `redact_docker_args`).
+pub fn record_launch(worktree_path: &Path, launch: Launch) -> Result<()> {
+    let mut meta = read_meta(worktree_path).unwrap_or_default();
+    meta.launch = Some(Launch {
+        docker_args: redact_docker_args(&launch.docker_args),
+        ..launch
+    });
+    write_meta(worktree_path, &meta)
+}
+
+/// Create the `.vibe` directory of a worktree if needed, and return it.
+pub fn ensure_meta_dir(worktree_path: &Path) -> Result<PathBuf> {
+    let dir = meta_dir(worktree_path);
+    fs::create_dir_all(&dir).context("Failed to create .vibe directory")?;
+
+    // Keep the whole directory out of git status
+    let gitignore = dir.join(".gitignore");
+    if !gitignore.exists() {
+        fs::write(&gitignore, "*\n").context("Failed to write .vibe/.gitignore")?;
+    }
+    Ok(dir)
+}
+
+/// Write metadata for a worktree, creating the `.vibe` directory if needed.
+pub fn write_meta(worktree_path: &Path, meta: &Meta) -> Result<()> {
+    let dir = ensure_meta_dir(worktree_path)?;
+
+    let content = serde_json::to_string_pretty(meta)?;
+    fs::write(dir.join(META_FILE), content).context("Failed to write worktree metadata")
+}
+
+/// Remove the vibe state left behind by a removed worktree.
+///
+/// Metadata and cached summaries live in the worktree's `.vibe` directory,
+/// which usually goes away with the worktree. It outlives orphaned
+/// worktrees whose checkout was deleted except for it, or whose
+/// directory was recreated by a tool writing into it. The directory itself is
+/// removed too if nothing else is left in it.
+pub fn cleanup_worktree_state(worktree_path: &Path) -> Result<()> {
+    let dir = meta_dir(worktree_path);
+    if dir.exists() {
+        fs::remove_dir_all(&dir).context("Failed to remove .vibe directory")?;
+    }
+    // Fails if anything else is left, which is kept
+    let _ = fs::remove_dir(worktree_path);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_meta_without_new_fields() {
+        // Written before creation times were recorded
+        let meta: Meta = serde_json::from_str(r#"{"base_branch": "main"}"#).unwrap();
+        assert_eq!(meta.created(), None);
+        assert_eq!(meta.prompt, None);
+
+        let meta = Meta::new();
+        let age = SystemTime::now()
+            .duration_since(meta.created().unwrap())
+            .unwrap();
+        assert!(age < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_cleanup_worktree_state() {
+        let dir = std::env::temp_dir().join(format!("vibe-state-{}", std::process::id()));
+        write_meta(&dir, &Meta::new()).unwrap();
+        cleanup_worktree_state(&dir).unwrap();
+        assert!(!dir.exists());
+
+        // Files that aren't vibe's are left alone
+        write_meta(&dir, &Meta::new()).unwrap();
+        fs::write(dir.join("notes.txt"), "keep").unwrap();
+        cleanup_worktree_state(&dir).unwrap();
+        assert!(!meta_dir(&dir).exists());
+        assert!(dir.join("notes.txt").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn vars(list: &[&str]) -> Vec<String> {
+        list.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_merge_env() {
+        let base = vars(&["A=1", "B=2"]);
+        assert_eq!(merge_env(&base, &[]), base);
+        assert_eq!(
+            merge_env(&base, &vars(&["B=3", "C=4"])),
+            vars(&["A=1", "B=3", "C=4"])
+        );
+        assert_eq!(merge_env(&[], &vars(&["A=x=y"])), vars(&["A=x=y"]));
+        assert_eq!(
+            merge_env(&vars(&["TOKEN"]), &vars(&["TOKEN=x"])),
+            vars(&["TOKEN=x"])
+        );
+    }
+
+    #[test]
+    fn test_redact_env() {
+        assert_eq!(
+            redact_env(&vars(&[
+                "RUST_LOG=debug",
+                "GITHUB_TOKEN=ghp_x",
+                "api_key=1",
+                "NPM_AUTH"
+            ])),
+            vars(&["RUST_LOG=debug", "GITHUB_TOKEN", "api_key", "NPM_AUTH"])
+        );
+    }
+
+    #[test]
+    fn test_redact_docker_args() {
+        assert_eq!(
+            redact_docker_args(&vars(&[
+                "-e",
+                "GITHUB_TOKEN=ghp_x",
+                "--env",
+                "RUST_LOG=debug",
+                "--env=NPM_AUTH=y",
+                "-eAWS_SECRET=z",
+                "-e=API_KEY=k",
+                "--network",
+                "TOKEN=host",
+            ])),
+            vars(&[
+                "-e",
+                "GITHUB_TOKEN",
+                "--env",
+                "RUST_LOG=debug",
+                "--env=NPM_AUTH",
+                "-eAWS_SECRET",
+                "-e=API_KEY",
+                "--network",
+                "TOKEN=host",
+            ])
+        );
+    }
+}
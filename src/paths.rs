@@ -0,0 +1,38 @@
+//! Per-user directories for vibe data, following the XDG base directory spec.
+
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+
+/// Directory for persistent vibe data (`$XDG_DATA_HOME/vibe`).
+///
+/// Defaults to `~/.local/share/vibe`.
+pub fn data_dir() -> Result<PathBuf> {
+    xdg_dir("XDG_DATA_HOME", ".local/share")
+}
+
+/// Directory for vibe configuration (`$XDG_CONFIG_HOME/vibe`).
+///
+/// Defaults to `~/.config/vibe`.
+pub fn config_dir() -> Result<PathBuf> {
+    xdg_dir("XDG_CONFIG_HOME", ".config")
+}
+
+/// Directory for vibe state that may be lost without harm
+/// (`$XDG_STATE_HOME/vibe`).
+///
+/// Defaults to `~/.local/state/vibe`.
+pub fn state_dir() -> Result<PathBuf> {
+    xdg_dir("XDG_STATE_HOME", ".local/state")
+}
+
+/// Resolve `$<var>/vibe`, falling back to `~/<fallback>/vibe`.
+fn xdg_dir(var: &str, fallback: &str) -> Result<PathBuf> {
+    if let Ok(dir) = std::env::var(var)
+        && !dir.is_empty()
+    {
+        return Ok(PathBuf::from(dir).join("vibe"));
+    }
+
+    let home = std::env::var("HOME").context("HOME not set")?;
+    Ok(PathBuf::from(home).join(fallback).join("vibe"))
+}
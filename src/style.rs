@@ -2,11 +2,69 @@
 //!
 //! Provides styled text output without raw ANSI escape codes.
 
-use crossterm::style::{Color, ResetColor, SetForegroundColor};
-use std::io;
+use anyhow::{Result, anyhow, bail};
+use crossterm::style::{Color, ResetColor, SetForegroundColor, Stylize};
+use std::io::{self, IsTerminal};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::git::WorktreeStatus;
+
+/// Whether colors were forced on from the command line
+static FORCE_COLOR: OnceLock<bool> = OnceLock::new();
+
+/// Whether colors were turned off from the command line
+static NO_COLOR: OnceLock<bool> = OnceLock::new();
+
+/// Force colors on even when stdout isn't a terminal. Call once at startup.
+pub fn set_force_color(force: bool) {
+    let _ = FORCE_COLOR.set(force);
+}
+
+/// Turn colors off even when stdout is a terminal. Call once at startup.
+pub fn set_no_color(disable: bool) {
+    let _ = NO_COLOR.set(disable);
+}
+
+/// Decide whether output should be colored.
+///
+/// `--no-color` always disables colors and `--force-color` always enables
+/// them. Without either flag, a non-empty `NO_COLOR` disables colors and a
+/// non-zero `CLICOLOR_FORCE` enables them (for piping into pagers like
+/// `less -R`); otherwise colors are used only when stdout is a terminal.
+pub fn color_enabled() -> bool {
+    static ENABLED: OnceLock<bool> = OnceLock::new();
+    *ENABLED.get_or_init(|| {
+        if NO_COLOR.get().copied().unwrap_or(false) {
+            return false;
+        }
+        if FORCE_COLOR.get().copied().unwrap_or(false) {
+            return true;
+        }
+        if std::env::var("NO_COLOR").is_ok_and(|v| !v.is_empty()) {
+            return false;
+        }
+        std::env::var("CLICOLOR_FORCE").is_ok_and(|v| !v.is_empty() && v != "0")
+            || io::stdout().is_terminal()
+    })
+}
+
+/// Text in the given color, or as-is when colors are disabled.
+pub fn paint(text: &str, color: Color) -> String {
+    if color_enabled() {
+        text.with(color).to_string()
+    } else {
+        text.to_string()
+    }
+}
 
 /// Print colored text to stdout.
 pub fn print_colored(text: &str, color: Color) {
+    if !color_enabled() {
+        print!("{}", text);
+        return;
+    }
+
     let mut stdout = io::stdout();
     let _ = crossterm::execute!(stdout, SetForegroundColor(color));
     print!("{}", text);
@@ -19,15 +77,295 @@ pub fn println_colored(text: &str, color: Color) {
     println!();
 }
 
-/// Status indicator colors
-pub mod indicators {
-    use crossterm::style::Color;
+/// Glyphs and colors used to render worktree status.
+///
+/// The active theme is chosen once at startup with `set_theme`. Glyphs and
+/// colors can be overridden from the `theme` table of the config file, and
+/// colors also through `VIBE_COLORS` (see `Theme::with_icons` and
+/// `Theme::with_colors`).
+pub struct Theme {
+    /// Worktree status dot
+    pub status: &'static str,
+    /// Orphaned worktree marker
+    pub orphaned: &'static str,
+    /// Rebase or merge in progress marker
+    pub conflicted: &'static str,
+    /// Status not loaded yet
+    pub pending: &'static str,
+    /// Checked box in multi-select lists
+    pub checked: &'static str,
+    /// Commits ahead of upstream/base
+    pub ahead: &'static str,
+    /// Commits behind base
+    pub behind: &'static str,
+    /// Prefix of sizes that are only a lower bound
+    pub at_least: &'static str,
+    /// Check or step that succeeded
+    pub ok: &'static str,
+    /// Check or step that failed
+    pub fail: &'static str,
+    /// Tree connector of an entry with more siblings below it
+    pub tree_branch: &'static str,
+    /// Tree connector of the last entry
+    pub tree_last: &'static str,
+    /// Tree indent below an entry with more siblings
+    pub tree_pipe: &'static str,
+    /// Left edge of streamed output boxes
+    pub box_vertical: &'static str,
+    /// Bottom-left corner of streamed output boxes
+    pub box_corner: &'static str,
+    /// Bottom edge of streamed output boxes
+    pub box_horizontal: &'static str,
+    /// Spinner animation frames
+    pub spinner: &'static [char],
+    /// Time each spinner frame is shown
+    pub spinner_interval: Duration,
+    pub clean: Color,
+    pub uncommitted: Color,
+    pub unpushed: Color,
+    pub danger: Color,
+    pub dim: Color,
+}
+
+impl Theme {
+    /// Default theme with Unicode glyphs.
+    pub fn unicode() -> Self {
+        Self {
+            status: "●",
+            orphaned: "✗",
+            conflicted: "⚠",
+            pending: "◌",
+            checked: "✓",
+            ahead: "↑",
+            behind: "↓",
+            at_least: "≥ ",
+            ok: "✓",
+            fail: "✗",
+            tree_branch: "├── ",
+            tree_last: "└── ",
+            tree_pipe: "│   ",
+            box_vertical: "│",
+            box_corner: "╰",
+            box_horizontal: "─",
+            spinner: Spinner::Braille.frames(),
+            spinner_interval: Duration::from_millis(DEFAULT_SPINNER_INTERVAL_MS),
+            clean: Color::Green,
+            uncommitted: Color::Yellow,
+            unpushed: Color::Blue,
+            danger: Color::Red,
+            dim: Color::DarkGrey,
+        }
+    }
+
+    /// Theme for terminals without Unicode support.
+    pub fn ascii() -> Self {
+        Self {
+            status: "*",
+            orphaned: "x",
+            conflicted: "!",
+            pending: ".",
+            checked: "x",
+            ahead: "^",
+            behind: "v",
+            at_least: ">= ",
+            ok: "+",
+            fail: "x",
+            tree_branch: "|-- ",
+            tree_last: "`-- ",
+            tree_pipe: "|   ",
+            box_vertical: "|",
+            box_corner: "`",
+            box_horizontal: "-",
+            spinner: Spinner::Ascii.frames(),
+            ..Self::unicode()
+        }
+    }
+
+    /// Override colors from a spec like `clean=green:danger=dark_red`.
+    ///
+    /// Keys are `clean`, `uncommitted`, `unpushed`, `danger` and `dim`; values
+    /// are crossterm color names (`red`, `dark_grey`, ...).
+    pub fn with_colors(mut self, spec: &str) -> Result<Self> {
+        for (key, value) in spec_entries(spec, "color")? {
+            let color = Color::try_from(value).map_err(|_| anyhow!("Unknown color '{}'", value))?;
+            let slot = match key {
+                "clean" => &mut self.clean,
+                "uncommitted" => &mut self.uncommitted,
+                "unpushed" => &mut self.unpushed,
+                "danger" => &mut self.danger,
+                "dim" => &mut self.dim,
+                _ => bail!("Unknown color key '{}'", key),
+            };
+            *slot = color;
+        }
+        Ok(self)
+    }
+
+    /// Override glyphs from a spec like `ok=v:fail=x:status=o`.
+    ///
+    /// Keys are the glyph fields (`status`, `orphaned`, `conflicted`,
+    /// `pending`, `checked`, `ahead`, `behind`, `at_least`, `ok`, `fail`,
+    /// `tree_branch`, `tree_last`, `tree_pipe`, `box_vertical`, `box_corner`
+    /// and `box_horizontal`); values are used as-is.
+    pub fn with_icons(mut self, spec: &'static str) -> Result<Self> {
+        for (key, value) in spec_entries(spec, "icon")? {
+            *self.icon_mut(key)? = value;
+        }
+        Ok(self)
+    }
+
+    /// Check a spec for `with_icons` without applying it.
+    pub fn check_icons(spec: &str) -> Result<()> {
+        let mut theme = Theme::unicode();
+        for (key, _) in spec_entries(spec, "icon")? {
+            theme.icon_mut(key)?;
+        }
+        Ok(())
+    }
+
+    /// The glyph field called `key`.
+    fn icon_mut(&mut self, key: &str) -> Result<&mut &'static str> {
+        Ok(match key {
+            "status" => &mut self.status,
+            "orphaned" => &mut self.orphaned,
+            "conflicted" => &mut self.conflicted,
+            "pending" => &mut self.pending,
+            "checked" => &mut self.checked,
+            "ahead" => &mut self.ahead,
+            "behind" => &mut self.behind,
+            "at_least" => &mut self.at_least,
+            "ok" => &mut self.ok,
+            "fail" => &mut self.fail,
+            "tree_branch" => &mut self.tree_branch,
+            "tree_last" => &mut self.tree_last,
+            "tree_pipe" => &mut self.tree_pipe,
+            "box_vertical" => &mut self.box_vertical,
+            "box_corner" => &mut self.box_corner,
+            "box_horizontal" => &mut self.box_horizontal,
+            _ => bail!("Unknown icon key '{}'", key),
+        })
+    }
+}
+
+/// Split a `key=value:key=value` spec into its entries.
+fn spec_entries<'a>(spec: &'a str, what: &str) -> Result<Vec<(&'a str, &'a str)>> {
+    spec.split(':')
+        .filter(|e| !e.is_empty())
+        .map(|entry| {
+            entry
+                .split_once('=')
+                .ok_or_else(|| anyhow!("Invalid {} entry '{}': expected key={}", what, entry, what))
+        })
+        .collect()
+}
+
+/// Default time each spinner frame is shown, in milliseconds
+pub const DEFAULT_SPINNER_INTERVAL_MS: u64 = 80;
+
+/// Spinner animation styles, see the `spinner.style` setting
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Spinner {
+    Braille,
+    Ascii,
+    Dots,
+}
+
+impl Spinner {
+    /// All styles, in the order they are listed in errors
+    pub const ALL: [Spinner; 3] = [Spinner::Braille, Spinner::Ascii, Spinner::Dots];
+
+    /// Name of the style in the config.
+    pub fn name(self) -> &'static str {
+        match self {
+            Spinner::Braille => "braille",
+            Spinner::Ascii => "ascii",
+            Spinner::Dots => "dots",
+        }
+    }
+
+    /// Look a style up by its config name.
+    pub fn from_name(name: &str) -> Option<Spinner> {
+        Spinner::ALL.into_iter().find(|s| s.name() == name)
+    }
+
+    /// Animation frames of the style; only braille needs Unicode.
+    pub fn frames(self) -> &'static [char] {
+        match self {
+            Spinner::Braille => &['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'],
+            Spinner::Ascii => &['|', '/', '-', '\\'],
+            Spinner::Dots => &['.', 'o', 'O', 'o'],
+        }
+    }
+}
+
+/// Whether the locale lets the terminal show Unicode glyphs.
+///
+/// Only a locale that is set to something other than UTF-8 (e.g. `LANG=C`)
+/// counts against it, since terminals without any locale set usually
+/// handle UTF-8 fine.
+pub fn unicode_supported() -> bool {
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .into_iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty());
+    locale.as_deref().is_none_or(is_utf8_locale)
+}
+
+/// Whether a locale name like `en_US.UTF-8` uses UTF-8.
+fn is_utf8_locale(locale: &str) -> bool {
+    let locale = locale.to_ascii_lowercase();
+    locale.contains("utf-8") || locale.contains("utf8")
+}
+
+/// Spinner frame to show `elapsed` into the animation.
+pub fn spinner_frame(elapsed: Duration) -> char {
+    let theme = theme();
+    let step = elapsed.as_millis() / theme.spinner_interval.as_millis().max(1);
+    theme.spinner[(step % theme.spinner.len() as u128) as usize]
+}
+
+/// Theme chosen at startup
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+/// Set the theme used for rendering. Call once at startup.
+pub fn set_theme(theme: Theme) {
+    let _ = THEME.set(theme);
+}
+
+/// Get the active theme (the Unicode theme if none was set).
+pub fn theme() -> &'static Theme {
+    THEME.get_or_init(Theme::unicode)
+}
 
-    pub const CLEAN: Color = Color::Green;
-    pub const UNCOMMITTED: Color = Color::Yellow;
-    pub const UNPUSHED: Color = Color::Blue;
-    pub const DANGER: Color = Color::Red;
-    pub const DIM: Color = Color::DarkGrey;
+/// Icon and color summarizing a worktree's status.
+///
+/// Shared by `vibe status` and the selection TUI so both render a worktree
+/// the same way.
+pub fn status_indicator(status: &WorktreeStatus) -> (&'static str, Color) {
+    let theme = theme();
+    if status.is_orphaned {
+        (theme.orphaned, theme.danger)
+    } else if status.in_progress_op.is_some() {
+        (theme.conflicted, theme.uncommitted)
+    } else if status.has_uncommitted && status.has_unpushed {
+        (theme.status, theme.danger)
+    } else if status.has_uncommitted {
+        (theme.status, theme.uncommitted)
+    } else if status.has_unpushed {
+        (theme.status, theme.unpushed)
+    } else {
+        (theme.status, theme.clean)
+    }
+}
+
+/// Note that remote state comes from cached refs when running offline.
+pub fn print_offline_notice() {
+    if crate::git::is_offline() {
+        println_colored(
+            "Offline: comparing against cached remote refs, results may be stale",
+            theme().dim,
+        );
+    }
 }
 
 /// Clear the current line (for updating loading messages).
@@ -39,3 +377,135 @@ pub fn clear_line() {
         crossterm::terminal::Clear(crossterm::terminal::ClearType::CurrentLine)
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(has_uncommitted: bool, has_unpushed: bool, is_orphaned: bool) -> WorktreeStatus {
+        WorktreeStatus {
+            has_uncommitted,
+            has_unpushed,
+            is_orphaned,
+            ..Default::default()
+        }
+    }
+
+    fn in_progress(has_uncommitted: bool, has_unpushed: bool, is_orphaned: bool) -> WorktreeStatus {
+        WorktreeStatus {
+            in_progress_op: Some("rebase".to_string()),
+            ..status(has_uncommitted, has_unpushed, is_orphaned)
+        }
+    }
+
+    #[test]
+    fn test_utf8_locale() {
+        assert!(is_utf8_locale("en_US.UTF-8"));
+        assert!(is_utf8_locale("C.utf8"));
+        assert!(!is_utf8_locale("C"));
+        assert!(!is_utf8_locale("en_US.ISO-8859-1"));
+    }
+
+    #[test]
+    fn test_spinner_names() {
+        for spinner in Spinner::ALL {
+            assert_eq!(Spinner::from_name(spinner.name()), Some(spinner));
+        }
+        assert_eq!(Spinner::from_name("unknown"), None);
+        assert!(Spinner::Ascii.frames().iter().all(char::is_ascii));
+        assert!(Spinner::Dots.frames().iter().all(char::is_ascii));
+    }
+
+    #[test]
+    fn test_ascii_theme_glyphs() {
+        let mut theme = Theme::ascii();
+        for key in [
+            "status",
+            "orphaned",
+            "conflicted",
+            "pending",
+            "checked",
+            "ahead",
+            "behind",
+            "at_least",
+            "ok",
+            "fail",
+            "tree_branch",
+            "tree_last",
+            "tree_pipe",
+            "box_vertical",
+            "box_corner",
+            "box_horizontal",
+        ] {
+            let glyph = *theme.icon_mut(key).unwrap();
+            assert!(glyph.is_ascii(), "{} = {}", key, glyph);
+        }
+        assert_eq!(
+            theme.tree_pipe.chars().count(),
+            theme.tree_last.chars().count()
+        );
+    }
+
+    #[test]
+    fn test_with_icons() {
+        let theme = Theme::unicode().with_icons("ok=v:fail=X:").unwrap();
+        assert_eq!((theme.ok, theme.fail), ("v", "X"));
+        assert_eq!(theme.status, Theme::unicode().status);
+
+        assert!(Theme::check_icons("ok=v:tree_last=`-- ").is_ok());
+        assert!(Theme::check_icons("okay=v").is_err());
+        assert!(Theme::check_icons("ok").is_err());
+        assert!(Theme::unicode().with_colors("clean=dark_green").is_ok());
+        assert!(Theme::unicode().with_colors("clean=greenish").is_err());
+    }
+
+    #[test]
+    fn test_status_indicator_combinations() {
+        let theme = Theme::unicode();
+        let cases = [
+            (status(false, false, false), theme.status, theme.clean),
+            (status(true, false, false), theme.status, theme.uncommitted),
+            (status(false, true, false), theme.status, theme.unpushed),
+            (status(true, true, false), theme.status, theme.danger),
+            // Orphaned wins over any other state
+            (status(false, false, true), theme.orphaned, theme.danger),
+            (status(true, false, true), theme.orphaned, theme.danger),
+            (status(false, true, true), theme.orphaned, theme.danger),
+            (status(true, true, true), theme.orphaned, theme.danger),
+            // A rebase or merge in progress wins over changes, not orphaning
+            (
+                in_progress(false, false, false),
+                theme.conflicted,
+                theme.uncommitted,
+            ),
+            (
+                in_progress(true, false, false),
+                theme.conflicted,
+                theme.uncommitted,
+            ),
+            (
+                in_progress(false, true, false),
+                theme.conflicted,
+                theme.uncommitted,
+            ),
+            (
+                in_progress(true, true, false),
+                theme.conflicted,
+                theme.uncommitted,
+            ),
+            (in_progress(true, true, true), theme.orphaned, theme.danger),
+        ];
+
+        for (status, icon, color) in cases {
+            assert_eq!(
+                status_indicator(&status),
+                (icon, color),
+                "uncommitted={} unpushed={} orphaned={} op={:?}",
+                status.has_uncommitted,
+                status.has_unpushed,
+                status.is_orphaned,
+                status.in_progress_op
+            );
+        }
+    }
+}
@@ -0,0 +1,217 @@
+//! AI summaries of worktree changes, cached in `.vibe/summary.json`.
+//!
+//! A summary is reused for as long as the worktree's HEAD and uncommitted
+//! changes stay the same, so Claude only runs again after they changed.
+//!
+//! Incremental summaries only describe what changed since the worktree was
+//! last looked at, i.e. listed by `vibe status --incremental` or left by a
+//! session.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::sync::OnceLock;
+use std::time::UNIX_EPOCH;
+
+use crate::git::WorktreeStatus;
+use crate::{config, git, meta};
+
+/// Cache file name inside the `.vibe` directory
+const SUMMARY_FILE: &str = "summary.json";
+
+/// Whether summaries were turned off from the command line
+static DISABLED: OnceLock<bool> = OnceLock::new();
+
+/// Turn summaries off regardless of the config. Call once at startup.
+pub fn set_disabled(disabled: bool) {
+    let _ = DISABLED.set(disabled);
+}
+
+/// Whether changes are summarized at all, see the `summaries` setting.
+pub fn enabled() -> bool {
+    !DISABLED.get().copied().unwrap_or(false) && config::get().summaries
+}
+
+/// Whether a worktree in this state gets a summary.
+pub fn needed(status: &WorktreeStatus) -> bool {
+    enabled() && status.has_uncommitted && !status.is_orphaned
+}
+
+/// The last summary generated for a worktree
+#[derive(Serialize, Deserialize)]
+struct CachedSummary {
+    /// State of the worktree the summary describes, see `state_key`
+    key: String,
+    summary: String,
+}
+
+/// Summary of a worktree's changes, from the cache unless `refresh`.
+///
+/// Generates and caches a new summary if the cached one is outdated.
+pub fn get(worktree_path: &Path, refresh: bool) -> Result<String> {
+    if !refresh && let Some(summary) = cached_summary(worktree_path) {
+        return Ok(summary);
+    }
+    generate(worktree_path)
+}
+
+/// The cached summary, if it still describes the worktree's changes.
+pub fn cached_summary(worktree_path: &Path) -> Option<String> {
+    load(worktree_path, &state_key(worktree_path)?)
+}
+
+/// Ask Claude for a new summary and cache it.
+pub fn generate(worktree_path: &Path) -> Result<String> {
+    // Taken first: changes made while Claude runs make the entry outdated
+    let key = state_key(worktree_path);
+    let summary = git::get_ai_summary(worktree_path, None)?;
+    if let Some(key) = key {
+        // A summary that can't be cached is still worth showing
+        let _ = store(worktree_path, &key, &summary);
+    }
+    Ok(summary)
+}
+
+/// Summary of what changed in a worktree since it was last marked.
+///
+/// Falls back to the summary of all changes when there is no marker (see
+/// `mark`). Not cached, since the marker moves every time it is shown.
+pub fn incremental(worktree_path: &Path) -> Result<String> {
+    match marker(worktree_path) {
+        Some(tree) => git::get_ai_summary(worktree_path, Some(&tree)),
+        None => get(worktree_path, false),
+    }
+}
+
+/// Remember the current state of a worktree for `incremental` summaries.
+///
+/// Does nothing while summaries are turned off.
+pub fn mark(worktree_path: &Path) -> Result<()> {
+    if !enabled() {
+        return Ok(());
+    }
+    let tree = git::snapshot_tree(worktree_path)?;
+    let mut meta = meta::read_meta(worktree_path).unwrap_or_default();
+    if meta.summary_marker.as_deref() == Some(tree.as_str()) {
+        return Ok(());
+    }
+    meta.summary_marker = Some(tree);
+    meta::write_meta(worktree_path, &meta)
+}
+
+/// The recorded marker of a worktree, if its snapshot still exists.
+///
+/// Snapshots aren't referenced by anything, so `git gc` eventually drops them.
+fn marker(worktree_path: &Path) -> Option<String> {
+    let tree = meta::read_meta(worktree_path)?.summary_marker?;
+    let exists = Command::new("git")
+        .current_dir(worktree_path)
+        .args(["cat-file", "-e", &format!("{}^{{tree}}", tree)])
+        .output()
+        .is_ok_and(|o| o.status.success());
+    exists.then_some(tree)
+}
+
+/// Identify the current HEAD and uncommitted changes of a worktree.
+///
+/// Untracked files count by their size and modification time, to avoid
+/// reading them.
+fn state_key(worktree_path: &Path) -> Option<String> {
+    let git_output = |args: &[&str]| {
+        let output = Command::new("git")
+            .current_dir(worktree_path)
+            .args(args)
+            .output()
+            .ok()?;
+        output.status.success().then_some(output.stdout)
+    };
+
+    let head = git_output(&["rev-parse", "HEAD"])?;
+    let status = git_output(&["status", "--porcelain", "--untracked-files=all"])?;
+    let diff = git_output(&["diff", "HEAD"])?;
+
+    let mut untracked = Vec::new();
+    for path in String::from_utf8_lossy(&status)
+        .lines()
+        .filter_map(|l| l.strip_prefix("?? "))
+    {
+        if let Ok(metadata) = fs::symlink_metadata(worktree_path.join(path)) {
+            let modified = metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .unwrap_or_default();
+            untracked.extend(metadata.len().to_le_bytes());
+            untracked.extend(modified.as_nanos().to_le_bytes());
+        }
+    }
+
+    let head = String::from_utf8_lossy(&head);
+    let hash = fnv1a(&[&status, &diff, &untracked]);
+    Some(format!("{}-{:016x}", head.trim(), hash))
+}
+
+/// 64-bit FNV-1a hash, which unlike `DefaultHasher` is stable across builds.
+fn fnv1a(parts: &[&[u8]]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for part in parts {
+        for &byte in *part {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+        // Keep ("ab", "") apart from ("a", "b")
+        hash ^= 0xff;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// Read the cached summary if it was made for `key`.
+fn load(worktree_path: &Path, key: &str) -> Option<String> {
+    let content = fs::read_to_string(meta::meta_dir(worktree_path).join(SUMMARY_FILE)).ok()?;
+    let cached: CachedSummary = serde_json::from_str(&content).ok()?;
+    (cached.key == key).then_some(cached.summary)
+}
+
+/// Cache `summary` as describing the state `key`.
+fn store(worktree_path: &Path, key: &str, summary: &str) -> Result<()> {
+    let dir = meta::ensure_meta_dir(worktree_path)?;
+    let cached = CachedSummary {
+        key: key.to_string(),
+        summary: summary.to_string(),
+    };
+    fs::write(
+        dir.join(SUMMARY_FILE),
+        serde_json::to_string_pretty(&cached)?,
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_cache() {
+        let dir = std::env::temp_dir().join(format!("vibe-summary-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        assert_eq!(load(&dir, "abc-1"), None);
+        store(&dir, "abc-1", "Add login form").unwrap();
+        assert_eq!(load(&dir, "abc-1").as_deref(), Some("Add login form"));
+        // Outdated once the worktree changed
+        assert_eq!(load(&dir, "abc-2"), None);
+        // The cache never shows up as a change itself
+        assert!(dir.join(".vibe/.gitignore").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_fnv1a() {
+        assert_ne!(fnv1a(&[b"ab", b""]), fnv1a(&[b"a", b"b"]));
+        assert_eq!(fnv1a(&[b"diff"]), fnv1a(&[b"diff"]));
+    }
+}
@@ -0,0 +1,311 @@
+//! Keep what cleanup removes so the last cleanup can be undone.
+//!
+//! Before a worktree is removed, its branch tip is saved under
+//! `refs/vibe/trash/heads/<branch>` and, if it has uncommitted changes, a
+//! snapshot commit of its working tree under `refs/vibe/trash/changes/<branch>`.
+//! The removed worktrees are recorded in `~/.local/state/vibe/last-cleanup.json`.
+//!
+//! Only the most recent cleanup is kept: recording a new one drops the refs
+//! of the previous one.
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::{git, meta, paths};
+
+/// Name of the record file inside the state directory
+const RECORD_FILE: &str = "last-cleanup.json";
+
+/// Ref namespace holding the branch tips of removed worktrees
+const HEADS_PREFIX: &str = "refs/vibe/trash/heads/";
+
+/// Ref namespace holding snapshots of uncommitted changes
+const CHANGES_PREFIX: &str = "refs/vibe/trash/changes/";
+
+/// A worktree removed by cleanup
+#[derive(Serialize, Deserialize)]
+pub struct Removed {
+    pub branch: String,
+    /// Commit the branch pointed to
+    pub commit: String,
+    /// Snapshot commit of uncommitted changes, on top of `commit`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changes: Option<String>,
+    /// Worktree metadata, restored along with the worktree
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<meta::Meta>,
+}
+
+/// The worktrees removed by the most recent cleanup
+#[derive(Serialize, Deserialize)]
+pub struct LastCleanup {
+    pub workspace_root: PathBuf,
+    pub removed: Vec<Removed>,
+}
+
+/// Save the branch tip and uncommitted changes of a worktree about to be removed.
+pub fn preserve(workspace_root: &Path, wt: &git::Worktree) -> Result<Removed> {
+    let commit = rev_parse(workspace_root, &format!("refs/heads/{}", wt.branch))
+        .with_context(|| format!("Failed to resolve branch '{}'", wt.branch))?;
+    update_ref(
+        workspace_root,
+        &format!("{}{}", HEADS_PREFIX, wt.branch),
+        &commit,
+    )?;
+
+    let status = git::get_worktree_status(&wt.path).unwrap_or_default();
+    let changes = if status.has_uncommitted && !status.is_orphaned {
+        let snapshot = snapshot_changes(&wt.path, &wt.branch).inspect_err(|_| {
+            drop_refs(workspace_root, &wt.branch);
+        })?;
+        update_ref(
+            workspace_root,
+            &format!("{}{}", CHANGES_PREFIX, wt.branch),
+            &snapshot,
+        )?;
+        Some(snapshot)
+    } else {
+        None
+    };
+
+    Ok(Removed {
+        branch: wt.branch.clone(),
+        commit,
+        changes,
+        meta: meta::read_meta(&wt.path),
+    })
+}
+
+/// Record a cleanup as the one to undo, dropping the refs of the previous one.
+pub fn record(workspace_root: &Path, removed: Vec<Removed>) -> Result<()> {
+    if let Some(previous) = load()? {
+        for old in &previous.removed {
+            let replaced = previous.workspace_root == workspace_root
+                && removed.iter().any(|r| r.branch == old.branch);
+            if !replaced {
+                drop_refs(&previous.workspace_root, &old.branch);
+            }
+        }
+    }
+
+    let record = LastCleanup {
+        workspace_root: workspace_root.to_path_buf(),
+        removed,
+    };
+    save(&record)
+}
+
+/// Load the record of the most recent cleanup, if any.
+pub fn load() -> Result<Option<LastCleanup>> {
+    let path = record_path()?;
+    let Ok(content) = fs::read_to_string(&path) else {
+        return Ok(None);
+    };
+
+    let record = serde_json::from_str(&content)
+        .with_context(|| format!("Invalid cleanup record at {}", path.display()))?;
+    Ok(Some(record))
+}
+
+/// Write the cleanup record, or delete it once nothing is left to undo.
+pub fn save(record: &LastCleanup) -> Result<()> {
+    let path = record_path()?;
+
+    if record.removed.is_empty() {
+        let _ = fs::remove_file(&path);
+        return Ok(());
+    }
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).context("Failed to create state directory")?;
+    }
+    let content = serde_json::to_string_pretty(record)?;
+    fs::write(&path, content).context("Failed to write cleanup record")
+}
+
+/// Recreate a removed worktree from its preserved refs.
+///
+/// If any step fails, the recreated branch and worktree are removed again
+/// and the refs kept, so undo can be retried. Returns the path of the new
+/// worktree.
+pub fn restore(workspace_root: &Path, removed: &Removed) -> Result<PathBuf> {
+    let branch_ref = format!("refs/heads/{}", removed.branch);
+    if rev_parse(workspace_root, &branch_ref).is_ok() {
+        bail!("Branch '{}' already exists", removed.branch);
+    }
+
+    let status = Command::new("git")
+        .current_dir(workspace_root)
+        .args(["branch", &removed.branch, &removed.commit])
+        .status()
+        .context("Failed to run git branch")?;
+    if !status.success() {
+        bail!("Failed to recreate branch '{}'", removed.branch);
+    }
+
+    let worktree_path = restore_worktree(workspace_root, removed).inspect_err(|_| {
+        git::discard_worktree(
+            workspace_root,
+            &workspace_root.join(&removed.branch),
+            &removed.branch,
+        );
+    })?;
+
+    drop_refs(workspace_root, &removed.branch);
+    Ok(worktree_path)
+}
+
+/// Check out a recreated branch and put back its metadata and changes.
+fn restore_worktree(workspace_root: &Path, removed: &Removed) -> Result<PathBuf> {
+    let worktree_path = git::checkout_worktree(workspace_root, &removed.branch)?;
+
+    if let Some(meta) = &removed.meta {
+        meta::write_meta(&worktree_path, meta)?;
+    }
+
+    if let Some(changes) = &removed.changes {
+        apply_changes(&worktree_path, &removed.commit, changes)
+            .context("Failed to restore uncommitted changes")?;
+    }
+
+    Ok(worktree_path)
+}
+
+/// Path of the cleanup record.
+fn record_path() -> Result<PathBuf> {
+    Ok(paths::state_dir()?.join(RECORD_FILE))
+}
+
+/// Commit the working tree of a worktree (untracked files included) without
+/// touching its index, returning the snapshot commit.
+fn snapshot_changes(worktree_path: &Path, branch: &str) -> Result<String> {
+    let tree = git::snapshot_tree(worktree_path)?;
+    let message = format!("vibe: uncommitted changes of {}", branch);
+    let output = Command::new("git")
+        .current_dir(worktree_path)
+        // The snapshot is internal, so don't depend on a configured identity
+        .args(["-c", "user.name=vibe", "-c", "user.email=vibe@localhost"])
+        .args(["commit-tree", &tree, "-p", "HEAD", "-m", &message])
+        .output()
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        bail!(
+            "Failed to snapshot changes: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Re-apply a changes snapshot to a restored worktree as uncommitted changes.
+fn apply_changes(worktree_path: &Path, commit: &str, changes: &str) -> Result<()> {
+    let diff = Command::new("git")
+        .current_dir(worktree_path)
+        .args(["diff", "--binary", commit, changes])
+        .output()
+        .context("Failed to run git diff")?;
+    if !diff.status.success() {
+        bail!("Failed to read preserved changes");
+    }
+
+    let mut child = Command::new("git")
+        .current_dir(worktree_path)
+        .args(["apply", "-"])
+        .stdin(Stdio::piped())
+        .spawn()
+        .context("Failed to run git apply")?;
+    // Dropping stdin closes it so git apply sees the end of the patch
+    child
+        .stdin
+        .take()
+        .context("Failed to open git apply stdin")?
+        .write_all(&diff.stdout)?;
+
+    if !child.wait()?.success() {
+        bail!("Uncommitted changes did not apply cleanly");
+    }
+    Ok(())
+}
+
+/// Delete the preserved refs of a branch in the repository at `workspace_root`.
+fn drop_refs(workspace_root: &Path, branch: &str) {
+    for prefix in [HEADS_PREFIX, CHANGES_PREFIX] {
+        let _ = Command::new("git")
+            .current_dir(workspace_root)
+            .args(["update-ref", "-d", &format!("{}{}", prefix, branch)])
+            .output();
+    }
+}
+
+/// Resolve a revision to a commit id.
+fn rev_parse(workspace_root: &Path, rev: &str) -> Result<String> {
+    let output = Command::new("git")
+        .current_dir(workspace_root)
+        .args(["rev-parse", "--verify", "--quiet", rev])
+        .output()
+        .context("Failed to run git rev-parse")?;
+    if !output.status.success() {
+        bail!("Unknown revision '{}'", rev);
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Point a ref at a commit.
+fn update_ref(workspace_root: &Path, name: &str, commit: &str) -> Result<()> {
+    let status = Command::new("git")
+        .current_dir(workspace_root)
+        .args(["update-ref", name, commit])
+        .status()
+        .context("Failed to run git update-ref")?;
+    if !status.success() {
+        bail!("Failed to update {}", name);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failed_restore_can_be_retried() {
+        let repo = std::env::temp_dir().join(format!("vibe-restore-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&repo);
+        fs::create_dir_all(&repo).unwrap();
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .current_dir(&repo)
+                .args(["-c", "user.name=vibe", "-c", "user.email=vibe@example.com"])
+                .args(args)
+                .output()
+                .unwrap()
+                .status
+                .success()
+        };
+        assert!(git(&["init", "--quiet"]));
+        assert!(git(&["commit", "--quiet", "--allow-empty", "-m", "init"]));
+        let commit = rev_parse(&repo, "HEAD").unwrap();
+
+        // A changes snapshot that no longer exists makes applying it fail
+        let mut removed = Removed {
+            branch: "claude/gone".to_string(),
+            commit,
+            changes: Some("0".repeat(40)),
+            meta: None,
+        };
+        assert!(restore(&repo, &removed).is_err());
+        assert!(!repo.join("claude/gone").exists());
+        assert!(rev_parse(&repo, "refs/heads/claude/gone").is_err());
+
+        removed.changes = None;
+        let path = restore(&repo, &removed).unwrap();
+        assert!(path.ends_with("claude/gone"));
+        assert!(rev_parse(&repo, "refs/heads/claude/gone").is_ok());
+
+        fs::remove_dir_all(&repo).unwrap();
+    }
+}
@@ -6,17 +6,20 @@
 
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
 use ratatui::{
+    Terminal, TerminalOptions, Viewport,
     backend::CrosstermBackend,
+    layout::{Constraint, Layout},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, ListState},
-    Terminal, TerminalOptions, Viewport,
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Row, Table, Wrap},
 };
-use std::io::{self, stdout, Stdout};
-use std::time::Duration;
+use std::io::{self, IsTerminal, Stdout, stdout};
+use std::time::{Duration, Instant, SystemTime};
 use tokio::sync::mpsc;
 
+use crate::docker::ContainerStats;
 use crate::git::WorktreeStatus;
+use crate::{config, style, summary, util};
 
 /// Maximum height for the inline viewport
 const MAX_VIEWPORT_HEIGHT: u16 = 20;
@@ -27,14 +30,20 @@ const LINES_PER_ITEM: usize = 3;
 /// Lines used by borders and title
 const BORDER_LINES: usize = 2;
 
+/// Columns of a list row used by the side borders and highlight symbol
+const LIST_CHROME_WIDTH: usize = 4;
+
+/// Narrowest terminal that still fits the details pane next to the list
+const MIN_DETAILS_WIDTH: u16 = 80;
+
+/// Number of commits shown in the details pane
+pub const DETAILS_LOG_COUNT: usize = 10;
+
 /// Polling interval for keyboard events (milliseconds)
 const POLL_INTERVAL_MS: u64 = 50;
 
-/// Spinner frames for animation
-const SPINNER_FRAMES: &[char] = &['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
-
 /// Summary loading state
-#[derive(Clone, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum SummaryState {
     /// No summary needed or not yet determined
     None,
@@ -44,6 +53,8 @@ pub enum SummaryState {
     Summarizing,
     /// Summary complete
     Done(String),
+    /// Claude couldn't summarize the changes, with the reason
+    Failed(String),
 }
 
 /// Guard to ensure terminal raw mode is disabled on drop.
@@ -55,27 +66,130 @@ impl Drop for RawModeGuard {
     }
 }
 
+/// Filter typed after `/` to narrow down the list
+#[derive(Default)]
+struct Filter {
+    query: String,
+    /// Whether keys are currently typed into the query
+    editing: bool,
+}
+
+impl Filter {
+    /// Filter text for the title, if a filter is being typed or applied.
+    fn title(&self) -> Option<String> {
+        if self.editing {
+            Some(format!("/{}_", self.query))
+        } else if !self.query.is_empty() {
+            Some(format!("/{}", self.query))
+        } else {
+            None
+        }
+    }
+
+    /// Handle a key while the query is being typed.
+    ///
+    /// Enter keeps the filter, Esc clears it.
+    fn handle_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Char(c) => self.query.push(c),
+            KeyCode::Backspace => {
+                self.query.pop();
+            }
+            KeyCode::Enter => self.editing = false,
+            KeyCode::Esc => {
+                self.clear();
+            }
+            _ => {}
+        }
+    }
+
+    /// Drop the filter. Returns whether there was a query to drop.
+    fn clear(&mut self) -> bool {
+        self.editing = false;
+        !std::mem::take(&mut self.query).is_empty()
+    }
+}
+
+/// Title shown instead of the key help while a filter is typed
+const FILTER_HELP: &str = " Filter (Enter apply, Esc clear)";
+
+/// Whether the characters of `query` appear in `text` in order, ignoring case.
+fn fuzzy_match(text: &str, query: &str) -> bool {
+    let mut chars = text.chars().flat_map(char::to_lowercase);
+    query
+        .chars()
+        .flat_map(char::to_lowercase)
+        .all(|q| chars.any(|c| c == q))
+}
+
+/// Indices of the items whose branch or summary match the query.
+fn filter_items(items: &[WorktreeItem], query: &str) -> Vec<usize> {
+    items
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| {
+            fuzzy_match(&item.branch, query)
+                || matches!(&item.summary_state, SummaryState::Done(s) if fuzzy_match(s, query))
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Select the row showing `item` in the filtered list, or the first row.
+fn select_visible(list_state: &mut ListState, visible: &[usize], item: Option<usize>) {
+    let row = item
+        .and_then(|item| visible.iter().position(|&i| i == item))
+        .or(if visible.is_empty() { None } else { Some(0) });
+    list_state.select(row);
+}
+
 /// Item in the selection list with status information
 pub struct WorktreeItem {
     pub branch: String,
     pub status: Option<WorktreeStatus>,
     pub summary_state: SummaryState,
+    /// When the worktree was created, if recorded
+    pub created: Option<SystemTime>,
+    /// Recent commits for the details pane, once loaded
+    pub log: Option<String>,
 }
 
 /// Async update message for status or summary
 pub enum WorktreeUpdate {
-    Status { index: usize, status: WorktreeStatus },
-    SummaryStarted { index: usize },
-    Summary { index: usize, summary: String },
+    Status {
+        index: usize,
+        status: WorktreeStatus,
+    },
+    SummaryStarted {
+        index: usize,
+    },
+    Summary {
+        index: usize,
+        summary: String,
+    },
+    SummaryFailed {
+        index: usize,
+        reason: String,
+    },
+    Log {
+        index: usize,
+        log: String,
+    },
 }
 
 /// Application state for single selection with async updates
 struct SingleSelectApp {
     items: Vec<WorktreeItem>,
     list_state: ListState,
+    /// Indices into `items` of the rows shown under the current filter
+    visible: Vec<usize>,
+    filter: Filter,
+    /// Whether the details pane is shown next to the list
+    show_details: bool,
     pending_status: usize,
     pending_summaries: usize,
-    frame: usize,
+    /// When the app started, which drives the spinner animation
+    started: Instant,
 }
 
 impl SingleSelectApp {
@@ -83,9 +197,15 @@ impl SingleSelectApp {
         let pending_status = items.iter().filter(|i| i.status.is_none()).count();
         let pending_summaries = items
             .iter()
-            .filter(|i| matches!(i.summary_state, SummaryState::Queued | SummaryState::Summarizing))
+            .filter(|i| {
+                matches!(
+                    i.summary_state,
+                    SummaryState::Queued | SummaryState::Summarizing
+                )
+            })
             .count();
 
+        let visible = (0..items.len()).collect();
         let mut list_state = ListState::default();
         if !items.is_empty() {
             list_state.select(Some(0));
@@ -94,28 +214,25 @@ impl SingleSelectApp {
         Self {
             items,
             list_state,
+            visible,
+            filter: Filter::default(),
+            show_details: false,
             pending_status,
             pending_summaries,
-            frame: 0,
+            started: Instant::now(),
         }
     }
 
-    fn tick(&mut self) {
-        self.frame = self.frame.wrapping_add(1);
-    }
-
     fn spinner_char(&self) -> char {
-        SPINNER_FRAMES[self.frame % SPINNER_FRAMES.len()]
+        style::spinner_frame(self.started.elapsed())
     }
 
     fn update_status(&mut self, index: usize, status: WorktreeStatus) {
         if let Some(item) = self.items.get_mut(index) {
             // If this item needs a summary, mark as queued
-            if status.has_uncommitted && !status.is_orphaned {
-                if item.summary_state == SummaryState::None {
-                    item.summary_state = SummaryState::Queued;
-                    self.pending_summaries += 1;
-                }
+            if summary::needed(&status) && item.summary_state == SummaryState::None {
+                item.summary_state = SummaryState::Queued;
+                self.pending_summaries += 1;
             }
             if item.status.is_none() {
                 self.pending_status = self.pending_status.saturating_sub(1);
@@ -132,64 +249,151 @@ impl SingleSelectApp {
 
     fn update_summary(&mut self, index: usize, summary: String) {
         if let Some(item) = self.items.get_mut(index) {
-            if matches!(item.summary_state, SummaryState::Queued | SummaryState::Summarizing) {
+            if matches!(
+                item.summary_state,
+                SummaryState::Queued | SummaryState::Summarizing
+            ) {
                 self.pending_summaries = self.pending_summaries.saturating_sub(1);
             }
             item.summary_state = SummaryState::Done(summary);
         }
+        // The summary may change which items match
+        if !self.filter.query.is_empty() {
+            self.refilter();
+        }
     }
 
-    fn move_up(&mut self) {
-        if self.items.is_empty() {
-            return;
+    fn update_summary_failed(&mut self, index: usize, reason: String) {
+        if let Some(item) = self.items.get_mut(index) {
+            if matches!(
+                item.summary_state,
+                SummaryState::Queued | SummaryState::Summarizing
+            ) {
+                self.pending_summaries = self.pending_summaries.saturating_sub(1);
+            }
+            item.summary_state = SummaryState::Failed(reason);
         }
-        let i = self
-            .list_state
-            .selected()
-            .map(|i| i.saturating_sub(1))
-            .unwrap_or(0);
-        self.list_state.select(Some(i));
+    }
+
+    fn update_log(&mut self, index: usize, log: String) {
+        if let Some(item) = self.items.get_mut(index) {
+            item.log = Some(log);
+        }
+    }
+
+    fn move_up(&mut self) {
+        self.move_by(-1);
     }
 
     fn move_down(&mut self) {
-        if self.items.is_empty() {
+        self.move_by(1);
+    }
+
+    /// Move the highlight by `rows`, stopping at the first and last row.
+    ///
+    /// The list scrolls to keep the highlighted row in view.
+    fn move_by(&mut self, rows: isize) {
+        if self.visible.is_empty() {
             return;
         }
-        let max_idx = self.items.len().saturating_sub(1);
+        let max_idx = self.visible.len() - 1;
         let i = self
             .list_state
             .selected()
-            .map(|i| (i + 1).min(max_idx))
+            .map(|i| i.saturating_add_signed(rows).min(max_idx))
             .unwrap_or(0);
         self.list_state.select(Some(i));
     }
 
     fn selected(&self) -> Option<usize> {
-        self.list_state.selected()
+        self.current()
+    }
+
+    /// Apply a changed filter, keeping the current item selected if it still matches.
+    fn refilter(&mut self) {
+        let current = self.current();
+        self.visible = filter_items(&self.items, &self.filter.query);
+        select_visible(&mut self.list_state, &self.visible, current);
     }
 
-    fn build_list_items(&self) -> Vec<ListItem<'static>> {
-        let selected_idx = self.list_state.selected();
+    /// Index into `items` of the highlighted row.
+    fn current(&self) -> Option<usize> {
+        self.list_state
+            .selected()
+            .and_then(|row| self.visible.get(row).copied())
+    }
+
+    fn build_list_items(&self, width: u16) -> Vec<ListItem<'static>> {
+        let selected_idx = self.current();
         let spinner = self.spinner_char();
-        self.items
+        self.visible
             .iter()
-            .enumerate()
-            .map(|(i, item)| {
+            .map(|&i| {
+                let item = &self.items[i];
                 build_worktree_list_item(
-                    &item.branch,
-                    item.status.as_ref(),
-                    &item.summary_state,
+                    item,
                     false,
                     false, // no checkbox for single-select
                     selected_idx == Some(i),
                     spinner,
+                    width,
                 )
             })
             .collect()
     }
 
+    /// Lines of the details pane for the highlighted worktree.
+    fn build_details(&self) -> Vec<Line<'static>> {
+        let Some(item) = self.current().map(|i| &self.items[i]) else {
+            return Vec::new();
+        };
+        let heading = |text: &str| {
+            Line::from(Span::styled(
+                text.to_string(),
+                Style::default().add_modifier(Modifier::BOLD),
+            ))
+        };
+        let dim = |text: &str| {
+            Line::from(Span::styled(
+                text.to_string(),
+                Style::default().fg(Color::DarkGray),
+            ))
+        };
+
+        let mut lines = vec![heading(&item.branch)];
+        match &item.status {
+            Some(status) => lines.extend(status_details(status).into_iter().map(Line::from)),
+            None => lines.push(dim("Loading...")),
+        }
+
+        let summary = match &item.summary_state {
+            SummaryState::None => None,
+            SummaryState::Queued | SummaryState::Summarizing => Some(dim("Summarizing...")),
+            SummaryState::Done(text) => Some(Line::from(text.clone())),
+            SummaryState::Failed(reason) => Some(dim(&format!("summary failed ({})", reason))),
+        };
+        if let Some(summary) = summary {
+            lines.push(Line::default());
+            lines.push(heading("Summary"));
+            lines.push(summary);
+        }
+
+        lines.push(Line::default());
+        lines.push(heading("Recent commits"));
+        match item.log.as_deref() {
+            Some("") => lines.push(dim("No commits")),
+            Some(log) => lines.extend(log.lines().map(|l| Line::from(l.to_string()))),
+            None => lines.push(dim("Loading...")),
+        }
+        lines
+    }
+
     fn build_title(&self) -> String {
-        let base = " Select a session (↑/↓ navigate, Enter select, q quit)";
+        let base = if self.filter.editing {
+            FILTER_HELP
+        } else {
+            " Select a session (↑/↓ navigate, / filter, Tab details, Enter select, q quit)"
+        };
         let mut indicators = Vec::new();
 
         if self.pending_status > 0 {
@@ -199,11 +403,14 @@ impl SingleSelectApp {
             indicators.push(format!("Summarizing: {}", self.pending_summaries));
         }
 
-        if indicators.is_empty() {
-            format!("{} ", base)
-        } else {
-            format!("{} [{}] ", base, indicators.join(", "))
+        let mut title = base.to_string();
+        if !indicators.is_empty() {
+            title = format!("{} [{}]", title, indicators.join(", "));
+        }
+        if let Some(filter) = self.filter.title() {
+            title = format!("{} {}", title, filter);
         }
+        format!("{} ", title)
     }
 }
 
@@ -211,21 +418,31 @@ impl SingleSelectApp {
 struct MultiSelectApp {
     items: Vec<WorktreeItem>,
     list_state: ListState,
+    /// Indices into `items` of the rows shown under the current filter
+    visible: Vec<usize>,
+    filter: Filter,
     selected: Vec<bool>,
     pending_status: usize,
     pending_summaries: usize,
-    frame: usize,
+    /// When the app started, which drives the spinner animation
+    started: Instant,
 }
 
 impl MultiSelectApp {
-    fn new(items: Vec<WorktreeItem>) -> Self {
+    fn new(items: Vec<WorktreeItem>, checked: bool) -> Self {
         let len = items.len();
         let pending_status = items.iter().filter(|i| i.status.is_none()).count();
         let pending_summaries = items
             .iter()
-            .filter(|i| matches!(i.summary_state, SummaryState::Queued | SummaryState::Summarizing))
+            .filter(|i| {
+                matches!(
+                    i.summary_state,
+                    SummaryState::Queued | SummaryState::Summarizing
+                )
+            })
             .count();
 
+        let visible = (0..items.len()).collect();
         let mut list_state = ListState::default();
         if !items.is_empty() {
             list_state.select(Some(0));
@@ -234,28 +451,24 @@ impl MultiSelectApp {
         Self {
             items,
             list_state,
-            selected: vec![false; len],
+            visible,
+            filter: Filter::default(),
+            selected: vec![checked; len],
             pending_status,
             pending_summaries,
-            frame: 0,
+            started: Instant::now(),
         }
     }
 
-    fn tick(&mut self) {
-        self.frame = self.frame.wrapping_add(1);
-    }
-
     fn spinner_char(&self) -> char {
-        SPINNER_FRAMES[self.frame % SPINNER_FRAMES.len()]
+        style::spinner_frame(self.started.elapsed())
     }
 
     fn update_status(&mut self, index: usize, status: WorktreeStatus) {
         if let Some(item) = self.items.get_mut(index) {
-            if status.has_uncommitted && !status.is_orphaned {
-                if item.summary_state == SummaryState::None {
-                    item.summary_state = SummaryState::Queued;
-                    self.pending_summaries += 1;
-                }
+            if summary::needed(&status) && item.summary_state == SummaryState::None {
+                item.summary_state = SummaryState::Queued;
+                self.pending_summaries += 1;
             }
             if item.status.is_none() {
                 self.pending_status = self.pending_status.saturating_sub(1);
@@ -272,53 +485,79 @@ impl MultiSelectApp {
 
     fn update_summary(&mut self, index: usize, summary: String) {
         if let Some(item) = self.items.get_mut(index) {
-            if matches!(item.summary_state, SummaryState::Queued | SummaryState::Summarizing) {
+            if matches!(
+                item.summary_state,
+                SummaryState::Queued | SummaryState::Summarizing
+            ) {
                 self.pending_summaries = self.pending_summaries.saturating_sub(1);
             }
             item.summary_state = SummaryState::Done(summary);
         }
+        // The summary may change which items match
+        if !self.filter.query.is_empty() {
+            self.refilter();
+        }
     }
 
-    fn move_up(&mut self) {
-        if self.items.is_empty() {
-            return;
+    fn update_summary_failed(&mut self, index: usize, reason: String) {
+        if let Some(item) = self.items.get_mut(index) {
+            if matches!(
+                item.summary_state,
+                SummaryState::Queued | SummaryState::Summarizing
+            ) {
+                self.pending_summaries = self.pending_summaries.saturating_sub(1);
+            }
+            item.summary_state = SummaryState::Failed(reason);
+        }
+    }
+
+    fn update_log(&mut self, index: usize, log: String) {
+        if let Some(item) = self.items.get_mut(index) {
+            item.log = Some(log);
         }
-        let i = self
-            .list_state
-            .selected()
-            .map(|i| i.saturating_sub(1))
-            .unwrap_or(0);
-        self.list_state.select(Some(i));
+    }
+
+    fn move_up(&mut self) {
+        self.move_by(-1);
     }
 
     fn move_down(&mut self) {
-        if self.items.is_empty() {
+        self.move_by(1);
+    }
+
+    /// Move the highlight by `rows`, stopping at the first and last row.
+    ///
+    /// The list scrolls to keep the highlighted row in view.
+    fn move_by(&mut self, rows: isize) {
+        if self.visible.is_empty() {
             return;
         }
-        let max_idx = self.items.len().saturating_sub(1);
+        let max_idx = self.visible.len() - 1;
         let i = self
             .list_state
             .selected()
-            .map(|i| (i + 1).min(max_idx))
+            .map(|i| i.saturating_add_signed(rows).min(max_idx))
             .unwrap_or(0);
         self.list_state.select(Some(i));
     }
 
     fn toggle_current(&mut self) {
-        if let Some(idx) = self.list_state.selected() {
+        if let Some(idx) = self.current() {
             self.selected[idx] = !self.selected[idx];
         }
     }
 
+    /// Check all items shown under the current filter.
     fn select_all(&mut self) {
-        for s in &mut self.selected {
-            *s = true;
+        for &i in &self.visible {
+            self.selected[i] = true;
         }
     }
 
+    /// Uncheck all items shown under the current filter.
     fn deselect_all(&mut self) {
-        for s in &mut self.selected {
-            *s = false;
+        for &i in &self.visible {
+            self.selected[i] = false;
         }
     }
 
@@ -330,28 +569,45 @@ impl MultiSelectApp {
             .collect()
     }
 
-    fn build_list_items(&self) -> Vec<ListItem<'static>> {
-        let selected_idx = self.list_state.selected();
+    /// Apply a changed filter, keeping the current item selected if it still matches.
+    fn refilter(&mut self) {
+        let current = self.current();
+        self.visible = filter_items(&self.items, &self.filter.query);
+        select_visible(&mut self.list_state, &self.visible, current);
+    }
+
+    /// Index into `items` of the highlighted row.
+    fn current(&self) -> Option<usize> {
+        self.list_state
+            .selected()
+            .and_then(|row| self.visible.get(row).copied())
+    }
+
+    fn build_list_items(&self, width: u16) -> Vec<ListItem<'static>> {
+        let selected_idx = self.current();
         let spinner = self.spinner_char();
-        self.items
+        self.visible
             .iter()
-            .enumerate()
-            .map(|(i, item)| {
+            .map(|&i| {
+                let item = &self.items[i];
                 build_worktree_list_item(
-                    &item.branch,
-                    item.status.as_ref(),
-                    &item.summary_state,
+                    item,
                     self.selected[i],
                     true, // show checkbox for multi-select
                     selected_idx == Some(i),
                     spinner,
+                    width,
                 )
             })
             .collect()
     }
 
     fn build_title(&self) -> String {
-        let base = " Select worktrees (Space toggle, a all, n none, Enter confirm, q quit)";
+        let base = if self.filter.editing {
+            FILTER_HELP
+        } else {
+            " Select worktrees (Space toggle, a all, n none, / filter, Enter confirm, q quit)"
+        };
         let mut indicators = Vec::new();
 
         if self.pending_status > 0 {
@@ -361,77 +617,134 @@ impl MultiSelectApp {
             indicators.push(format!("Summarizing: {}", self.pending_summaries));
         }
 
-        if indicators.is_empty() {
-            format!("{} ", base)
-        } else {
-            format!("{} [{}] ", base, indicators.join(", "))
+        let mut title = base.to_string();
+        if !indicators.is_empty() {
+            title = format!("{} [{}]", title, indicators.join(", "));
+        }
+        if let Some(filter) = self.filter.title() {
+            title = format!("{} {}", title, filter);
+        }
+        format!("{} ", title)
+    }
+}
+
+/// Full description of a worktree's status for the details pane.
+fn status_details(status: &WorktreeStatus) -> Vec<String> {
+    if status.is_orphaned {
+        return vec!["Orphaned - directory missing".to_string()];
+    }
+
+    let mut lines = Vec::new();
+    if let Some(op) = &status.in_progress_op {
+        lines.push(format!("{} in progress", op));
+    }
+    if status.has_uncommitted {
+        lines.push(format!(
+            "Uncommitted: {} lines added, {} deleted",
+            status.lines_added, status.lines_deleted
+        ));
+        if status.untracked_files > 0 {
+            lines.push(format!("Untracked files: {}", status.untracked_files));
         }
+    } else {
+        lines.push("No uncommitted changes".to_string());
     }
+    if status.commits_ahead > 0 {
+        lines.push(format!("Unpushed commits: {}", status.commits_ahead));
+    } else {
+        lines.push("No unpushed commits".to_string());
+    }
+    lines
 }
 
 /// Build a list item for a worktree with status information
 fn build_worktree_list_item(
-    branch: &str,
-    status: Option<&WorktreeStatus>,
-    summary_state: &SummaryState,
+    item: &WorktreeItem,
     is_checked: bool,
     show_checkbox: bool,
     is_selected: bool,
     spinner: char,
+    width: u16,
 ) -> ListItem<'static> {
+    let theme = style::theme();
+    let status = item.status.as_ref();
+
     // Checkbox only for multi-select mode
     let prefix = if show_checkbox {
-        if is_checked { "[✓] " } else { "[ ] " }
+        if is_checked {
+            format!("[{}] ", theme.checked)
+        } else {
+            "[ ] ".to_string()
+        }
     } else {
-        ""
+        String::new()
     };
     let indent = if show_checkbox { "      " } else { "  " };
 
     // Status indicator based on state
     let (status_icon, status_color, show_summary_line) = match status {
-        None => ("◌", Color::DarkGray, false),
-        Some(s) if s.is_orphaned => ("✗", Color::Red, false),
+        None => (theme.pending, theme.dim, false),
         Some(s) => {
-            let icon_color = if s.has_uncommitted && s.has_unpushed {
-                ("●", Color::Red)
-            } else if s.has_uncommitted {
-                ("●", Color::Yellow)
-            } else if s.has_unpushed {
-                ("●", Color::Blue)
-            } else {
-                ("●", Color::Green)
-            };
-            let show_summary = s.has_uncommitted && !s.is_orphaned;
-            (icon_color.0, icon_color.1, show_summary)
+            let (icon, color) = style::status_indicator(s);
+            (icon, color, s.has_uncommitted && !s.is_orphaned)
         }
     };
 
-    // First line: branch name with status icon
-    let mut lines = vec![Line::from(vec![
-        Span::raw(prefix.to_string()),
+    // First line: branch name with status icon, and the age at the far right
+    let mut first_line = Line::from(vec![
+        Span::raw(prefix),
         Span::styled(
             format!("{} ", status_icon),
-            Style::default().fg(status_color),
+            Style::default().fg(to_ratatui_color(status_color)),
         ),
-        Span::raw(branch.to_string()),
-    ])];
+        Span::raw(item.branch.clone()),
+    ]);
+    if let Some(created) = item.created {
+        let age = util::format_relative_age(created);
+        // Borders and the highlight symbol take up the rest of the row
+        let room = usize::from(width).saturating_sub(LIST_CHROME_WIDTH);
+        let gap = room
+            .checked_sub(first_line.width() + age.len())
+            .filter(|&gap| gap > 0)
+            .unwrap_or(1);
+        first_line.push_span(Span::styled(
+            format!("{}{}", " ".repeat(gap), age),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    let mut lines = vec![first_line];
 
     // Second line: description/summary with spinner
     if show_summary_line {
-        let (summary_text, color) = match summary_state {
+        let (summary_text, color) = match &item.summary_state {
             SummaryState::None => ("".to_string(), Color::DarkGray),
             SummaryState::Queued => (
                 format!("{} Queued", spinner),
-                if is_selected { Color::White } else { Color::DarkGray },
+                if is_selected {
+                    Color::White
+                } else {
+                    Color::DarkGray
+                },
             ),
             SummaryState::Summarizing => (
                 format!("{} Summarizing...", spinner),
-                if is_selected { Color::White } else { Color::DarkGray },
+                if is_selected {
+                    Color::White
+                } else {
+                    Color::DarkGray
+                },
             ),
             SummaryState::Done(text) => (
                 text.clone(),
-                if is_selected { Color::White } else { Color::DarkGray },
+                if is_selected {
+                    Color::White
+                } else {
+                    Color::DarkGray
+                },
             ),
+            SummaryState::Failed(reason) => {
+                (format!("summary failed ({})", reason), Color::DarkGray)
+            }
         };
         if !summary_text.is_empty() {
             lines.push(Line::from(vec![Span::styled(
@@ -445,11 +758,22 @@ fn build_worktree_list_item(
     let status_line = match status {
         None => Line::from(vec![Span::styled(
             format!("{}{} Loading...", indent, spinner),
-            Style::default().fg(if is_selected { Color::White } else { Color::DarkGray }),
+            Style::default().fg(if is_selected {
+                Color::White
+            } else {
+                Color::DarkGray
+            }),
         )]),
         Some(s) if s.is_orphaned => Line::from(vec![Span::styled(
             format!("{}Orphaned - directory missing", indent),
-            Style::default().fg(Color::Red),
+            Style::default().fg(to_ratatui_color(theme.danger)),
+        )]),
+        Some(WorktreeStatus {
+            in_progress_op: Some(op),
+            ..
+        }) => Line::from(vec![Span::styled(
+            format!("{}{} in progress", indent, op),
+            Style::default().fg(to_ratatui_color(theme.uncommitted)),
         )]),
         Some(s) => {
             let mut spans = vec![Span::raw(indent.to_string())];
@@ -462,7 +786,11 @@ fn build_worktree_list_item(
             if !has_changes && !has_unpushed {
                 spans.push(Span::styled(
                     "Clean",
-                    Style::default().fg(if is_selected { Color::White } else { Color::DarkGray }),
+                    Style::default().fg(if is_selected {
+                        Color::White
+                    } else {
+                        Color::DarkGray
+                    }),
                 ));
             } else {
                 // Dimmed green for additions (including untracked)
@@ -490,7 +818,7 @@ fn build_worktree_list_item(
                 // Unpushed commits
                 if has_unpushed {
                     spans.push(Span::styled(
-                        format!("↑{}", s.commits_ahead),
+                        format!("{}{}", theme.ahead, s.commits_ahead),
                         Style::default().fg(Color::Rgb(100, 140, 180)),
                     ));
                 }
@@ -504,6 +832,36 @@ fn build_worktree_list_item(
     ListItem::new(lines)
 }
 
+/// Convert a crossterm color for use with ratatui.
+///
+/// ratatui depends on a different crossterm release, so the types don't
+/// convert directly. Names follow ratatui's own crossterm backend mapping.
+fn to_ratatui_color(color: crossterm::style::Color) -> Color {
+    use crossterm::style::Color as C;
+
+    match color {
+        C::Reset => Color::Reset,
+        C::Black => Color::Black,
+        C::DarkGrey => Color::DarkGray,
+        C::Red => Color::LightRed,
+        C::DarkRed => Color::Red,
+        C::Green => Color::LightGreen,
+        C::DarkGreen => Color::Green,
+        C::Yellow => Color::LightYellow,
+        C::DarkYellow => Color::Yellow,
+        C::Blue => Color::LightBlue,
+        C::DarkBlue => Color::Blue,
+        C::Magenta => Color::LightMagenta,
+        C::DarkMagenta => Color::Magenta,
+        C::Cyan => Color::LightCyan,
+        C::DarkCyan => Color::Cyan,
+        C::White => Color::White,
+        C::Grey => Color::Gray,
+        C::Rgb { r, g, b } => Color::Rgb(r, g, b),
+        C::AnsiValue(v) => Color::Indexed(v),
+    }
+}
+
 /// Calculate viewport height based on item count.
 fn calculate_viewport_height(item_count: usize) -> u16 {
     let needed = item_count
@@ -512,6 +870,12 @@ fn calculate_viewport_height(item_count: usize) -> u16 {
     (needed as u16).min(MAX_VIEWPORT_HEIGHT)
 }
 
+/// Number of items PageUp/PageDown move by in a viewport of this height.
+fn page_rows(viewport_height: u16) -> isize {
+    let rows = usize::from(viewport_height).saturating_sub(BORDER_LINES) / LINES_PER_ITEM;
+    rows.max(1) as isize
+}
+
 /// Setup terminal with inline viewport.
 fn setup_inline_terminal(height: u16) -> io::Result<Terminal<CrosstermBackend<Stdout>>> {
     let backend = CrosstermBackend::new(stdout());
@@ -532,6 +896,7 @@ pub async fn run_single_selection_async(
 ) -> io::Result<Option<usize>> {
     let item_count = items.len();
     let viewport_height = calculate_viewport_height(item_count);
+    let page = page_rows(viewport_height);
 
     crossterm::terminal::enable_raw_mode()?;
     let _guard = RawModeGuard;
@@ -540,12 +905,22 @@ pub async fn run_single_selection_async(
     let mut app = SingleSelectApp::new(items);
 
     let result = loop {
-        app.tick();
-        let list_items = app.build_list_items();
+        let width = terminal.size()?.width;
+        // Too narrow a terminal keeps the full-width list
+        let split = app.show_details && width >= MIN_DETAILS_WIDTH;
+        let list_items = app.build_list_items(if split { width / 2 } else { width });
+        let details = split.then(|| app.build_details());
         let title = app.build_title();
 
         terminal.draw(|frame| {
             let area = frame.area();
+            let (list_area, details_area) = if split {
+                let [list, details] =
+                    Layout::horizontal([Constraint::Percentage(50); 2]).areas(area);
+                (list, Some(details))
+            } else {
+                (area, None)
+            };
 
             let list = List::new(list_items)
                 .block(
@@ -561,23 +936,50 @@ pub async fn run_single_selection_async(
                 )
                 .highlight_symbol("> ");
 
-            frame.render_stateful_widget(list, area, &mut app.list_state);
+            frame.render_stateful_widget(list, list_area, &mut app.list_state);
+
+            if let (Some(area), Some(details)) = (details_area, details) {
+                let pane = Paragraph::new(details).wrap(Wrap { trim: false }).block(
+                    Block::default()
+                        .title(" Details ")
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(Color::DarkGray)),
+                );
+                frame.render_widget(pane, area);
+            }
         })?;
 
         // Check for keyboard events (non-blocking)
-        if event::poll(Duration::from_millis(POLL_INTERVAL_MS))? {
-            if let Event::Key(KeyEvent {
+        if event::poll(Duration::from_millis(POLL_INTERVAL_MS))?
+            && let Event::Key(KeyEvent {
                 code, modifiers, ..
             }) = event::read()?
-            {
-                match code {
-                    KeyCode::Up | KeyCode::Char('k') => app.move_up(),
-                    KeyCode::Down | KeyCode::Char('j') => app.move_down(),
-                    KeyCode::Enter => break app.selected(),
-                    KeyCode::Esc | KeyCode::Char('q') => break None,
-                    KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => break None,
-                    _ => {}
+        {
+            match code {
+                KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => break None,
+                KeyCode::Up => app.move_up(),
+                KeyCode::Down => app.move_down(),
+                KeyCode::PageUp => app.move_by(-page),
+                KeyCode::PageDown => app.move_by(page),
+                KeyCode::Home => app.move_by(isize::MIN),
+                KeyCode::End => app.move_by(isize::MAX),
+                _ if app.filter.editing => {
+                    app.filter.handle_key(code);
+                    app.refilter();
                 }
+                KeyCode::Char('/') => app.filter.editing = true,
+                KeyCode::Tab => app.show_details = !app.show_details,
+                KeyCode::Char('k') => app.move_up(),
+                KeyCode::Char('j') => app.move_down(),
+                KeyCode::Enter => match app.selected() {
+                    Some(index) => break Some(index),
+                    // Nothing matches the filter
+                    None if !app.items.is_empty() => {}
+                    None => break None,
+                },
+                KeyCode::Esc if app.filter.clear() => app.refilter(),
+                KeyCode::Esc | KeyCode::Char('q') => break None,
+                _ => {}
             }
         }
 
@@ -587,6 +989,10 @@ pub async fn run_single_selection_async(
                 WorktreeUpdate::Status { index, status } => app.update_status(index, status),
                 WorktreeUpdate::SummaryStarted { index } => app.update_summary_started(index),
                 WorktreeUpdate::Summary { index, summary } => app.update_summary(index, summary),
+                WorktreeUpdate::SummaryFailed { index, reason } => {
+                    app.update_summary_failed(index, reason)
+                }
+                WorktreeUpdate::Log { index, log } => app.update_log(index, log),
             }
         }
     };
@@ -597,23 +1003,26 @@ pub async fn run_single_selection_async(
 
 /// Run interactive multi-selection with async status and summary updates.
 ///
-/// Shows the TUI immediately and updates as data arrives.
+/// Shows the TUI immediately and updates as data arrives. With `checked`,
+/// all items start out selected for the user to uncheck, and confirming an
+/// empty selection returns `Some` of nothing rather than cancelling.
 pub async fn run_multi_selection_async(
     items: Vec<WorktreeItem>,
     mut update_rx: mpsc::UnboundedReceiver<WorktreeUpdate>,
+    checked: bool,
 ) -> io::Result<Option<Vec<usize>>> {
     let item_count = items.len();
     let viewport_height = calculate_viewport_height(item_count);
+    let page = page_rows(viewport_height);
 
     crossterm::terminal::enable_raw_mode()?;
     let _guard = RawModeGuard;
 
     let mut terminal = setup_inline_terminal(viewport_height)?;
-    let mut app = MultiSelectApp::new(items);
+    let mut app = MultiSelectApp::new(items, checked);
 
     let result = loop {
-        app.tick();
-        let list_items = app.build_list_items();
+        let list_items = app.build_list_items(terminal.size()?.width);
         let title = app.build_title();
 
         terminal.draw(|frame| {
@@ -637,29 +1046,40 @@ pub async fn run_multi_selection_async(
         })?;
 
         // Check for keyboard events (non-blocking)
-        if event::poll(Duration::from_millis(POLL_INTERVAL_MS))? {
-            if let Event::Key(KeyEvent {
+        if event::poll(Duration::from_millis(POLL_INTERVAL_MS))?
+            && let Event::Key(KeyEvent {
                 code, modifiers, ..
             }) = event::read()?
-            {
-                match code {
-                    KeyCode::Up | KeyCode::Char('k') => app.move_up(),
-                    KeyCode::Down | KeyCode::Char('j') => app.move_down(),
-                    KeyCode::Char(' ') => app.toggle_current(),
-                    KeyCode::Char('a') => app.select_all(),
-                    KeyCode::Char('n') => app.deselect_all(),
-                    KeyCode::Enter => {
-                        let selected = app.get_selected_indices();
-                        if selected.is_empty() {
-                            break None;
-                        } else {
-                            break Some(selected);
-                        }
+        {
+            match code {
+                KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => break None,
+                KeyCode::Up => app.move_up(),
+                KeyCode::Down => app.move_down(),
+                KeyCode::PageUp => app.move_by(-page),
+                KeyCode::PageDown => app.move_by(page),
+                KeyCode::Home => app.move_by(isize::MIN),
+                KeyCode::End => app.move_by(isize::MAX),
+                _ if app.filter.editing => {
+                    app.filter.handle_key(code);
+                    app.refilter();
+                }
+                KeyCode::Char('/') => app.filter.editing = true,
+                KeyCode::Char('k') => app.move_up(),
+                KeyCode::Char('j') => app.move_down(),
+                KeyCode::Char(' ') => app.toggle_current(),
+                KeyCode::Char('a') => app.select_all(),
+                KeyCode::Char('n') => app.deselect_all(),
+                KeyCode::Enter => {
+                    let selected = app.get_selected_indices();
+                    if selected.is_empty() && !checked {
+                        break None;
+                    } else {
+                        break Some(selected);
                     }
-                    KeyCode::Esc | KeyCode::Char('q') => break None,
-                    KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => break None,
-                    _ => {}
                 }
+                KeyCode::Esc if app.filter.clear() => app.refilter(),
+                KeyCode::Esc | KeyCode::Char('q') => break None,
+                _ => {}
             }
         }
 
@@ -669,6 +1089,10 @@ pub async fn run_multi_selection_async(
                 WorktreeUpdate::Status { index, status } => app.update_status(index, status),
                 WorktreeUpdate::SummaryStarted { index } => app.update_summary_started(index),
                 WorktreeUpdate::Summary { index, summary } => app.update_summary(index, summary),
+                WorktreeUpdate::SummaryFailed { index, reason } => {
+                    app.update_summary_failed(index, reason)
+                }
+                WorktreeUpdate::Log { index, log } => app.update_log(index, log),
             }
         }
     };
@@ -677,8 +1101,115 @@ pub async fn run_multi_selection_async(
     Ok(result)
 }
 
+/// Column headers of the session stats table
+const STATS_HEADER: [&str; 4] = ["SESSION", "CPU", "MEMORY", "MEM %"];
+
+/// Table cells for session stats, one row per container.
+///
+/// Sessions of other repositories are marked with their workspace name.
+pub fn stats_rows(stats: &[ContainerStats]) -> Vec<[String; 4]> {
+    let current = crate::git::get_bare_repo_info()
+        .ok()
+        .flatten()
+        .map(|info| info.workspace_root.display().to_string());
+
+    stats
+        .iter()
+        .map(|s| {
+            let mut session = match s.branch.strip_prefix(config::worktree_prefix()) {
+                Some(name) => name.to_string(),
+                None if s.branch.is_empty() => "(setup)".to_string(),
+                None => s.branch.clone(),
+            };
+            if current.as_deref() != Some(s.workspace.as_str()) {
+                let workspace = std::path::Path::new(&s.workspace)
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| s.workspace.clone());
+                session = format!("{} ({})", session, workspace);
+            }
+            [
+                session,
+                s.cpu.clone(),
+                s.memory.clone(),
+                s.memory_percent.clone(),
+            ]
+        })
+        .collect()
+}
+
+/// Show a table of session stats, refreshed as samples arrive.
+///
+/// Runs until `q`, Esc or Ctrl-C.
+pub async fn run_stats_table(
+    initial: Vec<ContainerStats>,
+    mut update_rx: mpsc::UnboundedReceiver<Vec<ContainerStats>>,
+) -> io::Result<()> {
+    // Room for sessions started while the table is shown, within reason
+    let viewport_height = ((initial.len() + 1 + BORDER_LINES) as u16 + 2).min(MAX_VIEWPORT_HEIGHT);
+
+    crossterm::terminal::enable_raw_mode()?;
+    let _guard = RawModeGuard;
+
+    let mut terminal = setup_inline_terminal(viewport_height)?;
+    let mut rows = stats_rows(&initial);
+
+    loop {
+        terminal.draw(|frame| {
+            let header =
+                Row::new(STATS_HEADER).style(Style::default().add_modifier(Modifier::BOLD));
+            let widths = [
+                Constraint::Fill(1),
+                Constraint::Length(8),
+                Constraint::Length(22),
+                Constraint::Length(7),
+            ];
+            let title = if rows.is_empty() {
+                " No running sessions (q to quit) ".to_string()
+            } else {
+                format!(" {} running (q to quit) ", rows.len())
+            };
+            let table = Table::new(rows.iter().map(|r| Row::new(r.clone())), widths)
+                .header(header)
+                .block(
+                    Block::default()
+                        .title(title)
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(Color::DarkGray)),
+                );
+            frame.render_widget(table, frame.area());
+        })?;
+
+        if event::poll(Duration::from_millis(POLL_INTERVAL_MS))?
+            && let Event::Key(KeyEvent {
+                code, modifiers, ..
+            }) = event::read()?
+        {
+            match code {
+                KeyCode::Esc | KeyCode::Char('q') => break,
+                KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => break,
+                _ => {}
+            }
+        }
+
+        while let Ok(stats) = update_rx.try_recv() {
+            rows = stats_rows(&stats);
+        }
+    }
+
+    terminal.clear()?;
+    Ok(())
+}
+
 /// Ask for confirmation with a yes/no prompt.
+///
+/// When stdin isn't a terminal, reads an answer line instead of key events;
+/// anything but `y`/`yes` (including EOF) counts as no.
 pub fn confirm(message: &str) -> io::Result<bool> {
+    if !io::stdin().is_terminal() {
+        return confirm_line(message);
+    }
+
     crossterm::terminal::enable_raw_mode()?;
     let _guard = RawModeGuard;
 
@@ -686,16 +1217,16 @@ pub fn confirm(message: &str) -> io::Result<bool> {
     io::Write::flush(&mut stdout())?;
 
     let result = loop {
-        if event::poll(Duration::from_millis(POLL_INTERVAL_MS))? {
-            if let Event::Key(KeyEvent { code, .. }) = event::read()? {
-                match code {
-                    KeyCode::Char('y') | KeyCode::Char('Y') => break true,
-                    KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc | KeyCode::Char('q') => {
-                        break false
-                    }
-                    KeyCode::Enter => break false,
-                    _ => {}
+        if event::poll(Duration::from_millis(POLL_INTERVAL_MS))?
+            && let Event::Key(KeyEvent { code, .. }) = event::read()?
+        {
+            match code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => break true,
+                KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc | KeyCode::Char('q') => {
+                    break false;
                 }
+                KeyCode::Enter => break false,
+                _ => {}
             }
         }
     };
@@ -703,3 +1234,160 @@ pub fn confirm(message: &str) -> io::Result<bool> {
     println!();
     Ok(result)
 }
+
+/// Ask for a line of text, returning None if the answer is empty or stdin
+/// was closed.
+pub fn ask(message: &str) -> io::Result<Option<String>> {
+    print!("{} ", message);
+    io::Write::flush(&mut stdout())?;
+
+    let mut answer = String::new();
+    if io::stdin().read_line(&mut answer)? == 0 {
+        println!();
+        return Ok(None);
+    }
+
+    let answer = answer.trim();
+    Ok((!answer.is_empty()).then(|| answer.to_string()))
+}
+
+/// Line-based confirmation for non-interactive stdin.
+fn confirm_line(message: &str) -> io::Result<bool> {
+    print!("{} [y/N] ", message);
+    io::Write::flush(&mut stdout())?;
+
+    let mut answer = String::new();
+    if io::stdin().read_line(&mut answer)? == 0 {
+        // EOF: take the safe default
+        println!();
+        return Ok(false);
+    }
+
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failed_summary_is_no_longer_pending() {
+        let items = vec![WorktreeItem {
+            branch: "claude/abc".to_string(),
+            status: None,
+            summary_state: SummaryState::None,
+            created: None,
+            log: None,
+        }];
+        let mut app = SingleSelectApp::new(items);
+
+        let status = WorktreeStatus {
+            has_uncommitted: true,
+            ..Default::default()
+        };
+        app.update_status(0, status);
+        app.update_summary_started(0);
+        assert_eq!(app.pending_summaries, 1);
+
+        app.update_summary_failed(0, "timed out".to_string());
+        assert_eq!(app.pending_summaries, 0);
+        assert_eq!(
+            app.items[0].summary_state,
+            SummaryState::Failed("timed out".to_string())
+        );
+    }
+
+    fn item(branch: &str, summary: Option<&str>) -> WorktreeItem {
+        WorktreeItem {
+            branch: branch.to_string(),
+            status: None,
+            summary_state: match summary {
+                Some(s) => SummaryState::Done(s.to_string()),
+                None => SummaryState::None,
+            },
+            created: None,
+            log: None,
+        }
+    }
+
+    #[test]
+    fn test_fuzzy_match() {
+        assert!(fuzzy_match("claude/fix-login", "fxlog"));
+        assert!(fuzzy_match("claude/fix-login", "FIX"));
+        assert!(fuzzy_match("anything", ""));
+        assert!(!fuzzy_match("claude/fix-login", "golf"));
+    }
+
+    #[test]
+    fn test_filter_maps_to_original_indices() {
+        let items = vec![
+            item("claude/aaa", None),
+            item("claude/bbb", Some("Rework the parser")),
+            item("claude/ccc", None),
+            item("claude/ddd", None),
+        ];
+        let mut app = MultiSelectApp::new(items, false);
+        app.move_down();
+
+        app.filter.query = "parser".to_string();
+        app.refilter();
+        assert_eq!(app.visible, vec![1]);
+        assert_eq!(app.current(), Some(1));
+
+        app.filter.query = "cc".to_string();
+        app.refilter();
+        // bbb is no longer shown, so the first match is highlighted
+        assert_eq!(app.visible, vec![2]);
+        app.toggle_current();
+        app.filter.clear();
+        app.refilter();
+        assert_eq!(app.get_selected_indices(), vec![2]);
+        assert_eq!(app.current(), Some(2));
+    }
+
+    #[test]
+    fn test_status_details() {
+        let status = WorktreeStatus {
+            has_uncommitted: true,
+            lines_added: 3,
+            lines_deleted: 1,
+            untracked_files: 2,
+            commits_ahead: 4,
+            ..Default::default()
+        };
+        assert_eq!(
+            status_details(&status),
+            vec![
+                "Uncommitted: 3 lines added, 1 deleted",
+                "Untracked files: 2",
+                "Unpushed commits: 4",
+            ]
+        );
+
+        let orphaned = WorktreeStatus {
+            is_orphaned: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            status_details(&orphaned),
+            vec!["Orphaned - directory missing"]
+        );
+    }
+
+    #[test]
+    fn test_move_by_stops_at_the_ends() {
+        let items = (0..10)
+            .map(|i| item(&format!("claude/{}", i), None))
+            .collect();
+        let mut app = SingleSelectApp::new(items);
+
+        app.move_by(page_rows(MAX_VIEWPORT_HEIGHT));
+        assert_eq!(app.selected(), Some(6));
+        app.move_by(isize::MAX);
+        assert_eq!(app.selected(), Some(9));
+        app.move_down();
+        assert_eq!(app.selected(), Some(9));
+        app.move_by(isize::MIN);
+        assert_eq!(app.selected(), Some(0));
+    }
+}
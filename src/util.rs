@@ -0,0 +1,194 @@
+//! Parsing and formatting helpers for durations and byte sizes.
+
+use anyhow::{Context, Result, anyhow, bail};
+use std::time::{Duration, SystemTime};
+
+/// Unit suffixes for byte sizes, in powers of 1024
+const BYTE_UNITS: &[&str] = &["B", "KiB", "MiB", "GiB", "TiB"];
+
+/// Split `"1.5g"` into its number and (lowercased) unit suffix.
+fn split_number(text: &str) -> Result<(f64, String)> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+
+    let value: f64 = number
+        .parse()
+        .with_context(|| format!("Invalid number in '{}'", text))?;
+    Ok((value, unit.trim().to_lowercase()))
+}
+
+/// Duration units and their length in seconds, largest first
+const DURATION_UNITS: &[(&str, u64)] = &[
+    ("w", 7 * 24 * 60 * 60),
+    ("d", 24 * 60 * 60),
+    ("h", 60 * 60),
+    ("m", 60),
+    ("s", 1),
+];
+
+/// Parse a duration like `90m`, `24h`, `7d` or `1.5w`.
+///
+/// Units are `s`, `m`, `h`, `d` and `w`; a unit is required.
+pub fn parse_duration(text: &str) -> Result<Duration> {
+    let (value, unit) = split_number(text)?;
+
+    let seconds = match DURATION_UNITS.iter().find(|(name, _)| *name == unit) {
+        Some((_, seconds)) => *seconds as f64,
+        None if unit.is_empty() => {
+            bail!("Missing unit in duration '{}' (use s, m, h, d or w)", text)
+        }
+        None => bail!("Unknown unit '{}' in duration '{}'", unit, text),
+    };
+
+    Duration::try_from_secs_f64(value * seconds)
+        .map_err(|_| anyhow!("Duration '{}' is too long", text))
+}
+
+/// Format a duration in its largest whole unit, e.g. `3d` or `5h`.
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (unit, length) = DURATION_UNITS
+        .iter()
+        .find(|(_, length)| secs >= *length)
+        .unwrap_or(&("s", 1));
+
+    format!("{}{}", secs / length, unit)
+}
+
+/// Time since `created` in its largest whole unit, e.g. `2h` or `5d`.
+pub fn format_relative_age(created: SystemTime) -> String {
+    format_duration(
+        SystemTime::now()
+            .duration_since(created)
+            .unwrap_or_default(),
+    )
+}
+
+/// Parse a byte size like `512`, `100k`, `1.5g` or `2 GiB`.
+///
+/// Units are powers of 1024 (as in `docker --memory`); `k`, `kb` and `kib`
+/// are all accepted. A bare number is in bytes.
+#[allow(dead_code)]
+pub fn parse_bytes(text: &str) -> Result<u64> {
+    let (value, unit) = split_number(text)?;
+
+    let exponent = match unit.trim_end_matches("ib").trim_end_matches('b') {
+        "" => 0,
+        "k" => 1,
+        "m" => 2,
+        "g" => 3,
+        "t" => 4,
+        _ => bail!("Unknown unit '{}' in size '{}'", unit, text),
+    };
+
+    Ok((value * 1024f64.powi(exponent)).round() as u64)
+}
+
+/// Format a byte size for display, e.g. `1.5 GiB`.
+pub fn format_bytes(bytes: u64) -> String {
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < BYTE_UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, BYTE_UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("90m").unwrap(), Duration::from_secs(90 * 60));
+        assert_eq!(
+            parse_duration("24h").unwrap(),
+            Duration::from_secs(24 * 3600)
+        );
+        assert_eq!(
+            parse_duration("7d").unwrap(),
+            Duration::from_secs(7 * 86400)
+        );
+        assert_eq!(
+            parse_duration("2w").unwrap(),
+            Duration::from_secs(14 * 86400)
+        );
+        assert_eq!(parse_duration("1.5h").unwrap(), Duration::from_secs(5400));
+        assert_eq!(
+            parse_duration(" 7D ").unwrap(),
+            Duration::from_secs(7 * 86400)
+        );
+    }
+
+    #[test]
+    fn test_parse_duration_errors() {
+        assert!(parse_duration("7").is_err());
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("7y").is_err());
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("99999999999999999999w").is_err());
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_secs(0)), "0s");
+        assert_eq!(format_duration(Duration::from_secs(59)), "59s");
+        assert_eq!(format_duration(Duration::from_secs(90 * 60)), "1h");
+        assert_eq!(format_duration(Duration::from_secs(36 * 3600)), "1d");
+        assert_eq!(format_duration(Duration::from_secs(20 * 86400)), "2w");
+    }
+
+    #[test]
+    fn test_parse_bytes() {
+        assert_eq!(parse_bytes("512").unwrap(), 512);
+        assert_eq!(parse_bytes("100k").unwrap(), 100 * 1024);
+        assert_eq!(parse_bytes("2g").unwrap(), 2 * 1024 * 1024 * 1024);
+        assert_eq!(parse_bytes("1.5g").unwrap(), 3 * 512 * 1024 * 1024);
+        assert_eq!(parse_bytes("256MB").unwrap(), 256 * 1024 * 1024);
+        assert_eq!(parse_bytes("1 TiB").unwrap(), 1024u64.pow(4));
+        assert!(parse_bytes("1x").is_err());
+        assert!(parse_bytes("g").is_err());
+    }
+
+    #[test]
+    fn test_format_relative_age() {
+        let now = SystemTime::now();
+        assert_eq!(
+            format_relative_age(now - Duration::from_secs(2 * 3600 + 60)),
+            "2h"
+        );
+        assert_eq!(
+            format_relative_age(now - Duration::from_secs(5 * 86400)),
+            "5d"
+        );
+        // Clock skew shouldn't produce nonsense
+        assert_eq!(format_relative_age(now + Duration::from_secs(60)), "0s");
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1024), "1.0 KiB");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(3 * 512 * 1024 * 1024), "1.5 GiB");
+        assert_eq!(format_bytes(5 * 1024u64.pow(5)), "5120.0 TiB");
+    }
+
+    #[test]
+    fn test_format_bytes_round_trip() {
+        for bytes in [1536, 2 * 1024 * 1024, 3 * 512 * 1024 * 1024] {
+            assert_eq!(parse_bytes(&format_bytes(bytes)).unwrap(), bytes);
+        }
+    }
+}